@@ -3,6 +3,7 @@ use crate::opt::ConnectOpts;
 use console::style;
 use promptly::{prompt, ReadlineError};
 use sqlx::any::Any;
+use sqlx::config::Config;
 use sqlx::migrate::MigrateDatabase;
 
 pub async fn create(connect_opts: &ConnectOpts) -> anyhow::Result<()> {
@@ -17,7 +18,7 @@ pub async fn create(connect_opts: &ConnectOpts) -> anyhow::Result<()> {
             std::sync::atomic::Ordering::Release,
         );
 
-        Any::create_database(&connect_opts.database_url).await?;
+        Any::create_database(connect_opts.required_db_url()?).await?;
     }
 
     Ok(())
@@ -33,31 +34,36 @@ pub async fn drop(connect_opts: &ConnectOpts, confirm: bool) -> anyhow::Result<(
     let exists = crate::retry_connect_errors(connect_opts, Any::database_exists).await?;
 
     if exists {
-        Any::drop_database(&connect_opts.database_url).await?;
+        Any::drop_database(connect_opts.required_db_url()?).await?;
     }
 
     Ok(())
 }
 
 pub async fn reset(
+    config: &Config,
     migration_source: &str,
     connect_opts: &ConnectOpts,
     confirm: bool,
 ) -> anyhow::Result<()> {
     drop(connect_opts, confirm).await?;
-    setup(migration_source, connect_opts).await
+    setup(config, migration_source, connect_opts).await
 }
 
-pub async fn setup(migration_source: &str, connect_opts: &ConnectOpts) -> anyhow::Result<()> {
+pub async fn setup(
+    config: &Config,
+    migration_source: &str,
+    connect_opts: &ConnectOpts,
+) -> anyhow::Result<()> {
     create(connect_opts).await?;
-    migrate::run(migration_source, connect_opts, false, false).await
+    migrate::run(config, migration_source, connect_opts, false, false).await
 }
 
 fn ask_to_continue(connect_opts: &ConnectOpts) -> bool {
     loop {
         let r: Result<String, ReadlineError> = prompt(format!(
             "Drop database at {}? (y/n)",
-            style(&connect_opts.database_url).cyan()
+            style(connect_opts.database_url.as_deref().unwrap_or_default()).cyan()
         ));
         match r {
             Ok(response) => {
@@ -1,9 +1,11 @@
-use std::io;
+use std::path::PathBuf;
 use std::time::Duration;
+use std::{env, io};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use futures::{Future, TryFutureExt};
 
+use sqlx::config::{find_project_file, Config};
 use sqlx::{AnyConnection, Connection};
 
 use crate::opt::{Command, ConnectOpts, DatabaseCommand, MigrateCommand};
@@ -19,62 +21,159 @@ mod prepare;
 pub use crate::opt::Opt;
 
 pub async fn run(opt: Opt) -> Result<()> {
+    let mut config = LazyConfig::default();
+
     match opt.command {
         Command::Migrate(migrate) => match migrate.command {
             MigrateCommand::Add {
                 source,
                 description,
                 reversible,
-            } => migrate::add(&source, &description, reversible).await?,
+            } => migrate::add(&source.resolve(&mut config)?, &description, reversible).await?,
             MigrateCommand::Run {
                 source,
                 dry_run,
                 ignore_missing,
-                connect_opts,
-            } => migrate::run(&source, &connect_opts, dry_run, *ignore_missing).await?,
+                mut connect_opts,
+            } => {
+                connect_opts.populate_db_url(&mut config)?;
+
+                let source = source.resolve(&mut config)?;
+
+                migrate::run(
+                    config.get()?,
+                    &source,
+                    &connect_opts,
+                    dry_run,
+                    *ignore_missing,
+                )
+                .await?
+            }
             MigrateCommand::Revert {
                 source,
                 dry_run,
                 ignore_missing,
-                connect_opts,
-            } => migrate::revert(&source, &connect_opts, dry_run, *ignore_missing).await?,
+                mut connect_opts,
+            } => {
+                connect_opts.populate_db_url(&mut config)?;
+
+                let source = source.resolve(&mut config)?;
+
+                migrate::revert(
+                    config.get()?,
+                    &source,
+                    &connect_opts,
+                    dry_run,
+                    *ignore_missing,
+                )
+                .await?
+            }
             MigrateCommand::Info {
                 source,
-                connect_opts,
-            } => migrate::info(&source, &connect_opts).await?,
-            MigrateCommand::BuildScript { source, force } => migrate::build_script(&source, force)?,
+                mut connect_opts,
+            } => {
+                connect_opts.populate_db_url(&mut config)?;
+
+                let source = source.resolve(&mut config)?;
+
+                migrate::info(config.get()?, &source, &connect_opts).await?
+            }
+            MigrateCommand::BuildScript { source, force } => {
+                migrate::build_script(&source.resolve(&mut config)?, force)?
+            }
         },
 
         Command::Database(database) => match database.command {
-            DatabaseCommand::Create { connect_opts } => database::create(&connect_opts).await?,
+            DatabaseCommand::Create { mut connect_opts } => {
+                connect_opts.populate_db_url(&mut config)?;
+                database::create(&connect_opts).await?
+            }
             DatabaseCommand::Drop {
                 confirmation,
-                connect_opts,
-            } => database::drop(&connect_opts, !confirmation.yes).await?,
+                mut connect_opts,
+            } => {
+                connect_opts.populate_db_url(&mut config)?;
+                database::drop(&connect_opts, !confirmation.yes).await?
+            }
             DatabaseCommand::Reset {
                 confirmation,
                 source,
-                connect_opts,
-            } => database::reset(&source, &connect_opts, !confirmation.yes).await?,
+                mut connect_opts,
+            } => {
+                connect_opts.populate_db_url(&mut config)?;
+                let source = source.resolve(&mut config)?;
+
+                database::reset(config.get()?, &source, &connect_opts, !confirmation.yes).await?
+            }
             DatabaseCommand::Setup {
                 source,
-                connect_opts,
-            } => database::setup(&source, &connect_opts).await?,
+                mut connect_opts,
+            } => {
+                connect_opts.populate_db_url(&mut config)?;
+                let source = source.resolve(&mut config)?;
+
+                database::setup(config.get()?, &source, &connect_opts).await?
+            }
         },
 
         Command::Prepare {
             check,
+            prune,
             workspace,
-            connect_opts,
+            mut connect_opts,
             args,
-        } => prepare::run(check, workspace, connect_opts, args).await?,
+        } => {
+            connect_opts.populate_db_url(&mut config)?;
+            prepare::run(check, prune, workspace, connect_opts, config.take()?, args).await?
+        }
     };
 
     Ok(())
 }
 
+/// `sqlx.toml`, loaded when a command first reads it so that a broken file does not fail
+/// commands which never do.
+#[derive(Default)]
+pub(crate) struct LazyConfig(Option<Config>);
+
+impl LazyConfig {
+    pub(crate) fn get(&mut self) -> Result<&Config> {
+        if self.0.is_none() {
+            self.0 = Some(load_config()?);
+        }
+
+        Ok(self.0.as_ref().expect("config was just loaded"))
+    }
+
+    fn take(mut self) -> Result<Config> {
+        self.get()?;
+
+        Ok(self.0.expect("config was just loaded"))
+    }
+}
+
+/// Load `sqlx.toml` from the root of the current crate or else of its workspace, as the query
+/// macros do, or from the current directory if it is not in a Cargo project.
+fn load_config() -> Result<Config> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+
+    let path = match metadata::manifest_dir(&cargo) {
+        Ok(manifest_dir) => find_project_file(Config::FILENAME, &manifest_dir, || {
+            metadata::workspace_dir(&cargo).ok()
+        }),
+        Err(_) => Some(PathBuf::from(Config::FILENAME)).filter(|path| path.exists()),
+    };
+
+    match path {
+        Some(path) => {
+            Config::read_from(&path).with_context(|| format!("failed to load {}", path.display()))
+        }
+        None => Ok(Config::default()),
+    }
+}
+
 /// Attempt to connect to the database server, retrying up to `ops.connect_timeout`.
-async fn connect(opts: &ConnectOpts) -> sqlx::Result<AnyConnection> {
+async fn connect(opts: &ConnectOpts) -> Result<AnyConnection> {
     retry_connect_errors(opts, AnyConnection::connect).await
 }
 
@@ -82,22 +181,21 @@ async fn connect(opts: &ConnectOpts) -> sqlx::Result<AnyConnection> {
 /// retrying up until `ops.connect_timeout`.
 ///
 /// The closure is passed `&ops.database_url` for easy composition.
-async fn retry_connect_errors<'a, F, Fut, T>(
-    opts: &'a ConnectOpts,
-    mut connect: F,
-) -> sqlx::Result<T>
+async fn retry_connect_errors<'a, F, Fut, T>(opts: &'a ConnectOpts, mut connect: F) -> Result<T>
 where
     F: FnMut(&'a str) -> Fut,
     Fut: Future<Output = sqlx::Result<T>> + 'a,
 {
     sqlx::any::install_default_drivers();
 
-    backoff::future::retry(
+    let database_url = opts.required_db_url()?;
+
+    let res = backoff::future::retry(
         backoff::ExponentialBackoffBuilder::new()
             .with_max_elapsed_time(Some(Duration::from_secs(opts.connect_timeout)))
             .build(),
         || {
-            connect(&opts.database_url).map_err(|e| -> backoff::Error<sqlx::Error> {
+            connect(database_url).map_err(|e| -> backoff::Error<sqlx::Error> {
                 match e {
                     sqlx::Error::Io(ref ioe) => match ioe.kind() {
                         io::ErrorKind::ConnectionRefused
@@ -114,5 +212,7 @@ where
             })
         },
     )
-    .await
+    .await;
+
+    Ok(res?)
 }
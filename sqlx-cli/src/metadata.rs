@@ -181,13 +181,32 @@ impl FromStr for Metadata {
 /// The absolute path to the directory containing the `Cargo.toml` manifest.
 /// Depends on the current working directory.
 pub(crate) fn manifest_dir(cargo: &OsStr) -> anyhow::Result<PathBuf> {
-    let stdout = Command::new(cargo)
-        .args(["locate-project", "--message-format=plain"])
-        .output()
-        .context("could not locate manifest directory")?
-        .stdout;
+    locate_project(cargo, false)
+}
+
+/// The absolute path to the root directory of the workspace.
+/// Depends on the current working directory.
+pub(crate) fn workspace_dir(cargo: &OsStr) -> anyhow::Result<PathBuf> {
+    locate_project(cargo, true)
+}
+
+fn locate_project(cargo: &OsStr, workspace: bool) -> anyhow::Result<PathBuf> {
+    let mut command = Command::new(cargo);
+    command.args(["locate-project", "--message-format=plain"]);
+    if workspace {
+        command.arg("--workspace");
+    }
 
-    let mut manifest_path: PathBuf = std::str::from_utf8(&stdout)
+    let output = command
+        .output()
+        .context("could not locate manifest directory")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "could not locate manifest directory: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+
+    let mut manifest_path: PathBuf = std::str::from_utf8(&output.stdout)
         .context("output of `cargo locate-project` was not valid UTF-8")?
         // remove trailing newline
         .trim()
@@ -2,6 +2,7 @@ use crate::opt::ConnectOpts;
 use anyhow::{bail, Context};
 use chrono::Utc;
 use console::style;
+use sqlx::config::Config;
 use sqlx::migrate::{AppliedMigration, Migrate, MigrateError, MigrationType, Migrator};
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
@@ -116,9 +117,23 @@ fn short_checksum(checksum: &[u8]) -> String {
     s
 }
 
-pub async fn info(migration_source: &str, connect_opts: &ConnectOpts) -> anyhow::Result<()> {
+/// The table applied migrations are recorded in.
+fn table_name(config: &Config) -> &str {
+    config
+        .migrate
+        .table_name
+        .as_deref()
+        .unwrap_or(Migrator::DEFAULT_TABLE_NAME)
+}
+
+pub async fn info(
+    config: &Config,
+    migration_source: &str,
+    connect_opts: &ConnectOpts,
+) -> anyhow::Result<()> {
     let migrator = Migrator::new(Path::new(migration_source)).await?;
     let mut conn = crate::connect(&connect_opts).await?;
+    conn.set_migrations_table(table_name(config))?;
 
     conn.ensure_migrations_table().await?;
 
@@ -194,6 +209,7 @@ fn validate_applied_migrations(
 }
 
 pub async fn run(
+    config: &Config,
     migration_source: &str,
     connect_opts: &ConnectOpts,
     dry_run: bool,
@@ -201,6 +217,7 @@ pub async fn run(
 ) -> anyhow::Result<()> {
     let migrator = Migrator::new(Path::new(migration_source)).await?;
     let mut conn = crate::connect(connect_opts).await?;
+    conn.set_migrations_table(table_name(config))?;
 
     conn.ensure_migrations_table().await?;
 
@@ -253,6 +270,7 @@ pub async fn run(
 }
 
 pub async fn revert(
+    config: &Config,
     migration_source: &str,
     connect_opts: &ConnectOpts,
     dry_run: bool,
@@ -260,6 +278,7 @@ pub async fn revert(
 ) -> anyhow::Result<()> {
     let migrator = Migrator::new(Path::new(migration_source)).await?;
     let mut conn = crate::connect(&connect_opts).await?;
+    conn.set_migrations_table(table_name(config))?;
 
     conn.ensure_migrations_table().await?;
 
@@ -1,7 +1,11 @@
+use std::env;
 use std::ops::{Deref, Not};
 
+use anyhow::Context;
 use clap::{Args, Parser};
 
+use crate::LazyConfig;
+
 #[derive(Parser, Debug)]
 #[clap(version, about, author)]
 pub struct Opt {
@@ -25,10 +29,19 @@ pub enum Command {
     #[clap(alias = "prep")]
     Prepare {
         /// Run in 'check' mode. Exits with 0 if the query metadata is up-to-date. Exits with
-        /// 1 if the query metadata needs updating.
+        /// 1 if the query metadata needs updating, printing the queries which were added,
+        /// removed or changed.
         #[clap(long)]
         check: bool,
 
+        /// Remove query data from `.sqlx` which is not used by any query compiled in this run.
+        ///
+        /// Without this option, existing query data is only added to and updated, so data for
+        /// crates which were not rebuilt (e.g. because of `-p` passed to `cargo check`) is kept.
+        /// Nothing is pruned if any package which may use the `.sqlx` folder was not rebuilt.
+        #[clap(long, conflicts_with = "check")]
+        prune: bool,
+
         /// Generate a single workspace-level `.sqlx` folder.
         ///
         /// This option is intended for workspaces where multiple crates use SQLx. If there is only
@@ -174,25 +187,39 @@ pub enum MigrateCommand {
 /// Argument for the migration scripts source.
 #[derive(Args, Debug)]
 pub struct Source {
-    /// Path to folder containing migrations.
-    #[clap(long, default_value = "migrations")]
-    source: String,
+    /// Path to folder containing migrations. Defaults to `migrations-dir` under `[migrate]`
+    /// in `sqlx.toml`, or else `migrations`.
+    #[clap(long)]
+    source: Option<String>,
 }
 
-impl Deref for Source {
-    type Target = String;
-
-    fn deref(&self) -> &Self::Target {
-        &self.source
+impl Source {
+    /// The path of the migrations folder, falling back to the one configured in `sqlx.toml`.
+    pub(crate) fn resolve(&self, config: &mut LazyConfig) -> anyhow::Result<String> {
+        if let Some(source) = &self.source {
+            return Ok(source.clone());
+        }
+
+        Ok(match &config.get()?.migrate.migrations_dir {
+            // keep the path short in messages if the configured folder is below the current one
+            Some(dir) => env::current_dir()
+                .ok()
+                .and_then(|cwd| dir.strip_prefix(cwd).ok())
+                .unwrap_or(dir)
+                .display()
+                .to_string(),
+            None => "migrations".into(),
+        })
     }
 }
 
 /// Argument for the database URL.
 #[derive(Args, Debug)]
 pub struct ConnectOpts {
-    /// Location of the DB, by default will be read from the DATABASE_URL env var
-    #[clap(long, short = 'D', env)]
-    pub database_url: String,
+    /// Location of the DB, by default will be read from the DATABASE_URL env var, or the one
+    /// named by `database-url-var` under `[common]` in `sqlx.toml`.
+    #[clap(long, short = 'D')]
+    pub database_url: Option<String>,
 
     /// The maximum time, in seconds, to try connecting to the database server before
     /// returning an error.
@@ -212,6 +239,31 @@ pub struct ConnectOpts {
     pub sqlite_create_db_wal: bool,
 }
 
+impl ConnectOpts {
+    /// Read the database URL from the environment if it was not passed as an argument.
+    pub(crate) fn populate_db_url(&mut self, config: &mut LazyConfig) -> anyhow::Result<()> {
+        if self.database_url.is_none() {
+            let var = config.get()?.common.database_url_var();
+
+            self.database_url = Some(env::var(var).with_context(|| {
+                format!(
+                    "the database URL must be passed with --database-url, or set in `{}`",
+                    var
+                )
+            })?);
+        }
+
+        Ok(())
+    }
+
+    /// The database URL, which must have been passed or populated from the environment.
+    pub fn required_db_url(&self) -> anyhow::Result<&str> {
+        self.database_url
+            .as_deref()
+            .context("the database URL must be passed with --database-url")
+    }
+}
+
 /// Argument for automatic confirmation.
 #[derive(Args, Copy, Clone, Debug)]
 pub struct Confirmation {
@@ -1,13 +1,17 @@
-use std::collections::{BTreeSet, HashSet};
+use std::cmp;
+use std::collections::BTreeSet;
 use std::env;
 use std::ffi::{OsStr, OsString};
 use std::fs;
+use std::io::BufReader;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 use anyhow::{bail, Context};
+use cargo_metadata::{Message, PackageId as MetadataId};
 use console::style;
 
+use sqlx::config::Config;
 use sqlx::Connection;
 
 use crate::metadata::{manifest_dir, Metadata};
@@ -19,23 +23,55 @@ pub struct PrepareCtx {
     pub cargo_args: Vec<String>,
     pub metadata: Metadata,
     pub connect_opts: ConnectOpts,
+    pub config: Config,
 }
 
 impl PrepareCtx {
     /// Path to the directory where cached queries should be placed.
     fn prepare_dir(&self) -> anyhow::Result<PathBuf> {
-        if self.workspace {
+        if let Some(dir) = &self.config.macros.offline_dir {
+            Ok(dir.clone())
+        } else if self.workspace {
             Ok(self.metadata.workspace_root().join(".sqlx"))
         } else {
             Ok(manifest_dir(&self.cargo)?.join(".sqlx"))
         }
     }
+
+    /// The names of the packages which may have written the query data in `.sqlx`.
+    ///
+    /// A crate-level `.sqlx` folder only holds the queries of the current package, while a
+    /// workspace-level (or configured) one may hold those of every package using `sqlx-macros`.
+    fn query_data_packages(&self) -> anyhow::Result<BTreeSet<String>> {
+        if self.workspace || self.config.macros.offline_dir.is_some() {
+            Ok(sqlx_macros_dependents(&self.metadata)
+                .into_iter()
+                .filter_map(|id| self.metadata.package(id))
+                .map(|package| package.name().to_owned())
+                .collect())
+        } else {
+            let package = self.metadata.current_package().context(
+                "failed to get package in current working directory, pass `--workspace` if running from a workspace root",
+            )?;
+            Ok(BTreeSet::from([package.name().to_owned()]))
+        }
+    }
+
+    /// The directories query data is written to, relative to the one for `DATABASE_URL`:
+    /// that directory itself, and one for each database configured in `sqlx.toml`.
+    fn query_data_dirs(&self) -> Vec<PathBuf> {
+        std::iter::once(PathBuf::new())
+            .chain(self.config.databases.keys().map(PathBuf::from))
+            .collect()
+    }
 }
 
 pub async fn run(
     check: bool,
+    prune: bool,
     workspace: bool,
     connect_opts: ConnectOpts,
+    config: Config,
     cargo_args: Vec<String>,
 ) -> anyhow::Result<()> {
     let cargo = env::var_os("CARGO")
@@ -54,28 +90,89 @@ hint: This command only works in the manifest directory of a Cargo package or wo
         cargo_args,
         metadata,
         connect_opts,
+        config,
     };
 
     if check {
         prepare_check(&ctx).await
     } else {
-        prepare(&ctx).await
+        prepare(&ctx, prune).await
     }
 }
 
-async fn prepare(ctx: &PrepareCtx) -> anyhow::Result<()> {
-    check_backend(&ctx.connect_opts).await?;
+async fn prepare(ctx: &PrepareCtx, prune: bool) -> anyhow::Result<()> {
+    check_backends(ctx).await?;
 
+    // Generate the queries in a separate directory, so the query data of crates which are
+    // not rebuilt is not lost from .sqlx.
     let prepare_dir = ctx.prepare_dir()?;
-    run_prepare_step(ctx, &prepare_dir)?;
+    let cache_dir = ctx.metadata.target_directory().join("sqlx-prepare");
+    let rebuilt = run_prepare_step(ctx, &cache_dir)?;
 
     // Warn if no queries were generated. Glob since the directory may contain unrelated files.
-    if glob_query_files(prepare_dir)?.is_empty() {
+    let mut no_queries = true;
+    for dir in ctx.query_data_dirs() {
+        no_queries &= glob_query_files(cache_dir.join(dir))?.is_empty();
+    }
+    if no_queries {
         println!("{} no queries found", style("warning:").yellow());
         return Ok(());
     }
 
-    if ctx.workspace {
+    fs::create_dir_all(&prepare_dir).context(format!(
+        "Failed to create query cache directory: {:?}",
+        prepare_dir
+    ))?;
+
+    let diff = QueryDiff::between(&prepare_dir, &cache_dir, &ctx.query_data_dirs())?;
+
+    for change in diff.added.iter().chain(&diff.changed) {
+        let query_file = prepare_dir.join(&change.filename);
+
+        if let Some(dir) = query_file.parent() {
+            fs::create_dir_all(dir)
+                .context(format!("Failed to create query cache directory: {:?}", dir))?;
+        }
+
+        fs::copy(cache_dir.join(&change.filename), &query_file)
+            .with_context(|| format!("Failed to write query file: {}", query_file.display()))?;
+    }
+
+    // Query data which was not regenerated may still be used by a package which was not
+    // rebuilt, so only prune if every package which may have written it was.
+    let not_rebuilt = packages_not_rebuilt(&ctx.query_data_packages()?, &rebuilt);
+
+    if prune && not_rebuilt.is_empty() {
+        for change in &diff.removed {
+            let query_file = prepare_dir.join(&change.filename);
+            fs::remove_file(&query_file).with_context(|| {
+                format!("Failed to delete query file: {}", query_file.display())
+            })?;
+        }
+    }
+
+    diff.print();
+
+    if prune && !not_rebuilt.is_empty() && !diff.removed.is_empty() {
+        println!(
+            "{} not pruning .sqlx since these packages were not rebuilt: {}",
+            style("warning:").yellow(),
+            not_rebuilt.join(", ")
+        );
+    } else if !prune && !diff.removed.is_empty() {
+        println!(
+            "{} potentially unused queries found in .sqlx; \
+             re-run with `--prune` to remove them",
+            style("warning:").yellow()
+        );
+    }
+
+    if ctx.config.macros.offline_dir.is_some() {
+        println!(
+            "query data written to {}; please check this into version control",
+            prepare_dir.display()
+        );
+    } else if ctx.workspace {
         println!(
             "query data written to .sqlx in the workspace root; \
              please check this into version control"
@@ -90,7 +187,7 @@ async fn prepare(ctx: &PrepareCtx) -> anyhow::Result<()> {
 }
 
 async fn prepare_check(ctx: &PrepareCtx) -> anyhow::Result<()> {
-    let _ = check_backend(&ctx.connect_opts).await?;
+    check_backends(ctx).await?;
 
     // Re-generate and store the queries in a separate directory from both the prepared
     // queries and the ones generated by `cargo check`, to avoid conflicts.
@@ -98,72 +195,258 @@ async fn prepare_check(ctx: &PrepareCtx) -> anyhow::Result<()> {
     let cache_dir = ctx.metadata.target_directory().join("sqlx-prepare-check");
     run_prepare_step(ctx, &cache_dir)?;
 
-    // Compare .sqlx to cache.
-    let prepare_filenames: HashSet<String> = glob_query_files(&prepare_dir)?
-        .into_iter()
-        .filter_map(|path| path.file_name().map(|f| f.to_string_lossy().into_owned()))
-        .collect();
-    let cache_filenames: HashSet<String> = glob_query_files(&cache_dir)?
-        .into_iter()
-        .filter_map(|path| path.file_name().map(|f| f.to_string_lossy().into_owned()))
-        .collect();
+    let diff = QueryDiff::between(&prepare_dir, &cache_dir, &ctx.query_data_dirs())?;
+    diff.print();
 
-    // Error: files in cache but not .sqlx.
-    if cache_filenames
-        .difference(&prepare_filenames)
-        .next()
-        .is_some()
-    {
-        bail!("prepare check failed: .sqlx is missing one or more queries; you should re-run sqlx prepare");
+    // Error: files in cache but not .sqlx, or which differ.
+    if !diff.added.is_empty() || !diff.changed.is_empty() {
+        bail!("prepare check failed: .sqlx is out of date; you should re-run sqlx prepare");
     }
     // Warn: files in .sqlx but not cache.
-    if prepare_filenames
-        .difference(&cache_filenames)
-        .next()
-        .is_some()
-    {
+    if !diff.removed.is_empty() {
         println!(
-            "{} potentially unused queries found in .sqlx; you may want to re-run sqlx prepare",
+            "{} potentially unused queries found in .sqlx; \
+             you may want to re-run sqlx prepare --prune",
             style("warning:").yellow()
         );
     }
 
-    // Compare file contents as JSON to ignore superficial differences.
-    // Everything in cache checked to be in .sqlx already.
-    for filename in cache_filenames {
-        let prepare_json = load_json_file(prepare_dir.join(&filename))?;
-        let cache_json = load_json_file(cache_dir.join(&filename))?;
-        if prepare_json != cache_json {
-            bail!("prepare check failed: one or more query files differ ({}); you should re-run sqlx prepare", filename);
+    Ok(())
+}
+
+/// A query data file which differs between `.sqlx` and the freshly generated query data.
+#[derive(Debug, PartialEq)]
+struct QueryChange {
+    filename: String,
+    /// The SQL of the query.
+    query: String,
+    /// What changed in the query data, if it is in both.
+    details: Vec<String>,
+}
+
+/// The differences between the query data in `.sqlx` and the freshly generated query data.
+#[derive(Debug, Default)]
+struct QueryDiff {
+    /// Queries only in the generated data.
+    added: Vec<QueryChange>,
+    /// Queries only in `.sqlx`.
+    removed: Vec<QueryChange>,
+    /// Queries in both, whose data differs.
+    changed: Vec<QueryChange>,
+}
+
+impl QueryDiff {
+    /// Compare the query data in each of `dirs`, relative to `prepare_dir` and `cache_dir`.
+    fn between(prepare_dir: &Path, cache_dir: &Path, dirs: &[PathBuf]) -> anyhow::Result<Self> {
+        let mut prepared = BTreeSet::new();
+        let mut generated = BTreeSet::new();
+
+        for dir in dirs {
+            prepared.extend(query_filenames(prepare_dir.join(dir))?.map(|f| dir.join(f)));
+            generated.extend(query_filenames(cache_dir.join(dir))?.map(|f| dir.join(f)));
+        }
+
+        let mut diff = QueryDiff::default();
+
+        for filename in &generated {
+            let new = load_json_file(cache_dir.join(filename))?;
+
+            if !prepared.contains(filename) {
+                diff.added
+                    .push(QueryChange::new(filename, &new, Vec::new()));
+                continue;
+            }
+
+            // Compare file contents as JSON to ignore superficial differences.
+            let old = load_json_file(prepare_dir.join(filename))?;
+            if old != new {
+                let details = describe_changes(&old, &new);
+                diff.changed.push(QueryChange::new(filename, &new, details));
+            }
+        }
+
+        for filename in prepared.difference(&generated) {
+            let old = load_json_file(prepare_dir.join(filename))?;
+            diff.removed
+                .push(QueryChange::new(filename, &old, Vec::new()));
         }
+
+        Ok(diff)
     }
 
-    Ok(())
+    fn print(&self) {
+        let sections = [
+            (style("added").green(), &self.added),
+            (style("removed").red(), &self.removed),
+            (style("changed").yellow(), &self.changed),
+        ];
+
+        for (label, changes) in sections {
+            for change in changes {
+                println!("{} {}: {}", label, change.filename, change.query);
+
+                for detail in &change.details {
+                    println!("    {}", detail);
+                }
+            }
+        }
+    }
+}
+
+impl QueryChange {
+    fn new(filename: &Path, data: &serde_json::Value, details: Vec<String>) -> Self {
+        QueryChange {
+            filename: filename.display().to_string(),
+            query: summarize_query(data["query"].as_str().unwrap_or_default()),
+            details,
+        }
+    }
+}
+
+/// Collapse the whitespace in `query` and shorten it to fit on one line.
+fn summarize_query(query: &str) -> String {
+    const MAX_CHARS: usize = 80;
+
+    let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    match query.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}...", &query[..end]),
+        None => query,
+    }
+}
+
+/// Describe the differences between two versions of the data of a query, column by column.
+fn describe_changes(old: &serde_json::Value, new: &serde_json::Value) -> Vec<String> {
+    let mut details = Vec::new();
+
+    if old["db_name"] != new["db_name"] {
+        details.push(format!(
+            "database: {} -> {}",
+            display_json(&old["db_name"]),
+            display_json(&new["db_name"])
+        ));
+    }
+
+    let (old, new) = (&old["describe"], &new["describe"]);
+
+    let old_columns = old["columns"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let new_columns = new["columns"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    for i in 0..cmp::max(old_columns.len(), new_columns.len()) {
+        let (old_column, new_column) = match (old_columns.get(i), new_columns.get(i)) {
+            (Some(old_column), Some(new_column)) => (old_column, new_column),
+            (Some(old_column), None) => {
+                let name = display_json(&old_column["name"]);
+                details.push(format!("column {} `{}`: removed", i, name));
+                continue;
+            }
+            (None, Some(new_column)) => {
+                let name = display_json(&new_column["name"]);
+                details.push(format!("column {} `{}`: added", i, name));
+                continue;
+            }
+            (None, None) => unreachable!(),
+        };
+
+        let name = display_json(&new_column["name"]);
+
+        if old_column["name"] != new_column["name"] {
+            details.push(format!(
+                "column {}: renamed `{}` -> `{}`",
+                i,
+                display_json(&old_column["name"]),
+                name
+            ));
+        }
+
+        if old_column["type_info"] != new_column["type_info"] {
+            details.push(format!(
+                "column {} `{}`: type {} -> {}",
+                i,
+                name,
+                display_json(&old_column["type_info"]),
+                display_json(&new_column["type_info"])
+            ));
+        }
+
+        if old["nullable"][i] != new["nullable"][i] {
+            details.push(format!(
+                "column {} `{}`: nullable {} -> {}",
+                i,
+                name,
+                display_json(&old["nullable"][i]),
+                display_json(&new["nullable"][i])
+            ));
+        }
+    }
+
+    if old["parameters"] != new["parameters"] {
+        details.push(format!(
+            "parameters: {} -> {}",
+            display_json(&old["parameters"]),
+            display_json(&new["parameters"])
+        ));
+    }
+
+    details
+}
+
+/// Display a value of the query data, without quoting strings.
+fn display_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(string) => string.clone(),
+        serde_json::Value::Null => "unknown".into(),
+        value => value.to_string(),
+    }
 }
 
-fn run_prepare_step(ctx: &PrepareCtx, cache_dir: &Path) -> anyhow::Result<()> {
+/// The packages of `expected` which are not in `rebuilt`.
+fn packages_not_rebuilt(expected: &BTreeSet<String>, rebuilt: &BTreeSet<String>) -> Vec<String> {
+    expected.difference(rebuilt).cloned().collect()
+}
+
+/// Generate the query data into `cache_dir`, returning the names of the packages which were
+/// rebuilt in the process.
+fn run_prepare_step(ctx: &PrepareCtx, cache_dir: &Path) -> anyhow::Result<BTreeSet<String>> {
     // Create and/or clean the directory.
     fs::create_dir_all(cache_dir).context(format!(
         "Failed to create query cache directory: {:?}",
         cache_dir
     ))?;
     // Only delete sqlx-*.json files to avoid accidentally deleting any user data.
-    for query_file in glob_query_files(cache_dir).context("Failed to read query cache files")? {
-        fs::remove_file(&query_file)
-            .with_context(|| format!("Failed to delete query file: {}", query_file.display()))?;
+    for dir in ctx.query_data_dirs() {
+        let query_files =
+            glob_query_files(cache_dir.join(dir)).context("Failed to read query cache files")?;
+
+        for query_file in query_files {
+            fs::remove_file(&query_file).with_context(|| {
+                format!("Failed to delete query file: {}", query_file.display())
+            })?;
+        }
     }
 
     // Try only triggering a recompile on crates that use `sqlx-macros` falling back to a full
     // clean on error
     setup_minimal_project_recompile(&ctx.cargo, &ctx.metadata, ctx.workspace)?;
 
-    // Compile the queries.
-    let check_status = {
+    // Compile the queries. Messages are requested as JSON to tell which packages were rebuilt;
+    // diagnostics are still rendered to stderr.
+    let mut check_child = {
         let mut check_command = Command::new(&ctx.cargo);
         check_command
             .arg("check")
+            .arg("--message-format=json-render-diagnostics")
             .args(&ctx.cargo_args)
-            .env("DATABASE_URL", &ctx.connect_opts.database_url)
+            .env(
+                ctx.config.common.database_url_var(),
+                ctx.connect_opts.required_db_url()?,
+            )
             .env("SQLX_OFFLINE", "false")
             .env("SQLX_OFFLINE_DIR", cache_dir);
 
@@ -174,13 +457,30 @@ fn run_prepare_step(ctx: &PrepareCtx, cache_dir: &Path) -> anyhow::Result<()> {
             check_command.env("RUSTFLAGS", rustflags);
         }
 
-        check_command.status()?
+        check_command.stdout(Stdio::piped()).spawn()?
     };
+
+    let mut rebuilt = BTreeSet::new();
+    let stdout = check_child
+        .stdout
+        .take()
+        .context("failed to read `cargo check` output")?;
+    for message in Message::parse_stream(BufReader::new(stdout)) {
+        if let Message::CompilerArtifact(artifact) = message? {
+            if !artifact.fresh {
+                if let Some(package) = ctx.metadata.package(&artifact.package_id) {
+                    rebuilt.insert(package.name().to_owned());
+                }
+            }
+        }
+    }
+
+    let check_status = check_child.wait()?;
     if !check_status.success() {
         bail!("`cargo check` failed with status: {}", check_status);
     }
 
-    Ok(())
+    Ok(rebuilt)
 }
 
 #[derive(Debug, PartialEq)]
@@ -260,8 +560,8 @@ fn minimal_project_clean(
     Ok(())
 }
 
-fn minimal_project_recompile_action(metadata: &Metadata) -> ProjectRecompileAction {
-    // Get all the packages that depend on `sqlx-macros`
+/// Get all the packages that depend on `sqlx-macros`, directly or transitively.
+fn sqlx_macros_dependents(metadata: &Metadata) -> BTreeSet<&MetadataId> {
     let mut sqlx_macros_dependents = BTreeSet::new();
     let sqlx_macros_ids: BTreeSet<_> = metadata
         .entries()
@@ -273,6 +573,11 @@ fn minimal_project_recompile_action(metadata: &Metadata) -> ProjectRecompileActi
     for sqlx_macros_id in sqlx_macros_ids {
         sqlx_macros_dependents.extend(metadata.all_dependents_of(sqlx_macros_id));
     }
+    sqlx_macros_dependents
+}
+
+fn minimal_project_recompile_action(metadata: &Metadata) -> ProjectRecompileAction {
+    let sqlx_macros_dependents = sqlx_macros_dependents(metadata);
 
     // Figure out which `sqlx-macros` dependents are in the workspace vs out
     let mut in_workspace_dependents = Vec::new();
@@ -317,6 +622,27 @@ async fn check_backend(opts: &ConnectOpts) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Ensure the database server, and those of the databases in `sqlx.toml`, are available.
+async fn check_backends(ctx: &PrepareCtx) -> anyhow::Result<()> {
+    check_backend(&ctx.connect_opts).await?;
+
+    for (name, database) in &ctx.config.databases {
+        let database_url = database
+            .url(|var| env::var(var).ok())
+            .with_context(|| format!("the URL of database {:?} in sqlx.toml is not set", name))?;
+
+        check_backend(&ConnectOpts {
+            database_url: Some(database_url),
+            connect_timeout: ctx.connect_opts.connect_timeout,
+            #[cfg(feature = "sqlite")]
+            sqlite_create_db_wal: ctx.connect_opts.sqlite_create_db_wal,
+        })
+        .await?;
+    }
+
+    Ok(())
+}
+
 /// Find all `query-*.json` files in a directory.
 fn glob_query_files(path: impl AsRef<Path>) -> anyhow::Result<Vec<PathBuf>> {
     let path = path.as_ref();
@@ -331,6 +657,13 @@ fn glob_query_files(path: impl AsRef<Path>) -> anyhow::Result<Vec<PathBuf>> {
     .context("glob failed")
 }
 
+/// The names of all `query-*.json` files in a directory.
+fn query_filenames(path: impl AsRef<Path>) -> anyhow::Result<impl Iterator<Item = OsString>> {
+    Ok(glob_query_files(path)?
+        .into_iter()
+        .filter_map(|path| path.file_name().map(OsStr::to_owned)))
+}
+
 /// Load the JSON contents of a query data file.
 fn load_json_file(path: impl AsRef<Path>) -> anyhow::Result<serde_json::Value> {
     let path = path.as_ref();
@@ -352,7 +685,7 @@ mod tests {
         let sample_metadata = std::fs::read_to_string(sample_metadata_path)?;
         let metadata: Metadata = sample_metadata.parse()?;
 
-        let action = minimal_project_recompile_action(&metadata)?;
+        let action = minimal_project_recompile_action(&metadata);
         assert_eq!(
             action,
             ProjectRecompileAction {
@@ -366,4 +699,92 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn packages_not_rebuilt_works() -> anyhow::Result<()> {
+        let sample_metadata_path = Path::new("tests")
+            .join("assets")
+            .join("sample_metadata.json");
+        let sample_metadata = std::fs::read_to_string(sample_metadata_path)?;
+        let metadata: Metadata = sample_metadata.parse()?;
+
+        let expected: BTreeSet<String> = sqlx_macros_dependents(&metadata)
+            .into_iter()
+            .filter_map(|id| metadata.package(id))
+            .map(|package| package.name().to_owned())
+            .collect();
+
+        // Every package using `sqlx-macros` was rebuilt, so `.sqlx` may be pruned.
+        let rebuilt: BTreeSet<String> = ["b_in_workspace_lib", "c_in_workspace_bin", "sqlx"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert!(packages_not_rebuilt(&expected, &rebuilt).is_empty());
+
+        // `c_in_workspace_bin` was not rebuilt (e.g. `-p b_in_workspace_lib`), so its query
+        // data must be kept.
+        let rebuilt: BTreeSet<String> = ["b_in_workspace_lib", "sqlx"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(
+            packages_not_rebuilt(&expected, &rebuilt),
+            ["c_in_workspace_bin"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn describe_changes_works() {
+        let old = serde_json::json!({
+            "db_name": "PostgreSQL",
+            "query": "SELECT id, name FROM users",
+            "describe": {
+                "columns": [
+                    { "ordinal": 0, "name": "id", "type_info": "Int4" },
+                    { "ordinal": 1, "name": "name", "type_info": "Text" }
+                ],
+                "parameters": { "Left": [] },
+                "nullable": [false, true]
+            },
+            "hash": "abc"
+        });
+        let new = serde_json::json!({
+            "db_name": "PostgreSQL",
+            "query": "SELECT id, name FROM users",
+            "describe": {
+                "columns": [
+                    { "ordinal": 0, "name": "id", "type_info": "Int8" },
+                    { "ordinal": 1, "name": "name", "type_info": "Text" },
+                    { "ordinal": 2, "name": "email", "type_info": "Text" }
+                ],
+                "parameters": { "Left": [] },
+                "nullable": [false, false, null]
+            },
+            "hash": "abc"
+        });
+
+        assert_eq!(
+            describe_changes(&old, &new),
+            [
+                "column 0 `id`: type Int4 -> Int8",
+                "column 1 `name`: nullable true -> false",
+                "column 2 `email`: added",
+            ]
+        );
+        assert!(describe_changes(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn summarize_query_works() {
+        assert_eq!(
+            summarize_query("SELECT id\n  FROM users\n WHERE id = $1"),
+            "SELECT id FROM users WHERE id = $1"
+        );
+        assert_eq!(
+            summarize_query(&"x".repeat(100)),
+            format!("{}...", "x".repeat(80))
+        );
+    }
 }
@@ -72,4 +72,8 @@ impl Migrate for AnyConnection {
     ) -> BoxFuture<'m, Result<Duration, MigrateError>> {
         Box::pin(async { self.get_migrate()?.revert(migration).await })
     }
+
+    fn set_migrations_table(&mut self, table_name: &str) -> Result<(), MigrateError> {
+        self.get_migrate()?.set_migrations_table(table_name)
+    }
 }
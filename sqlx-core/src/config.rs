@@ -0,0 +1,495 @@
+//! Configuration of a project using SQLx, read from `sqlx.toml` by the query macros and
+//! `sqlx-cli`.
+//!
+//! The file is looked for in the directory of the crate being compiled, then at the root of
+//! its workspace. Every setting is optional, and relative paths in the file are relative to the
+//! directory it is in.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+use crate::error::Error;
+
+/// The contents of `sqlx.toml`.
+///
+/// ```toml
+/// [common]
+/// database-url-var = "APP_DATABASE_URL"
+///
+/// [macros]
+/// offline-dir = "queries"
+/// preferred-crates.date-time = "time"
+/// preferred-crates.numeric = "rust_decimal"
+///
+/// [macros.type-overrides.postgres]
+/// mood = "crate::models::Mood"
+///
+/// [macros.column-nullability."public.active_users"]
+/// id = false
+/// last_seen = true
+///
+/// [migrate]
+/// migrations-dir = "db/migrations"
+/// table-name = "schema_migrations"
+///
+/// [databases.analytics]
+/// url-var = "ANALYTICS_DATABASE_URL"
+///
+/// [databases.cache]
+/// url = "sqlite://cache.db"
+/// ```
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Settings shared by the macros and `sqlx-cli`.
+    pub common: CommonConfig,
+
+    /// Settings of the query macros.
+    pub macros: MacrosConfig,
+
+    /// Settings of `migrate!()` and `sqlx migrate`.
+    pub migrate: MigrateConfig,
+
+    /// The databases the query macros can use instead of `DATABASE_URL`, selected by name with
+    /// `db = "<name>"`.
+    pub databases: BTreeMap<String, DatabaseConfig>,
+}
+
+/// Settings under `[common]`.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CommonConfig {
+    /// The environment variable holding the URL of the database, instead of `DATABASE_URL`.
+    pub database_url_var: Option<String>,
+}
+
+/// Settings under `[macros]`.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct MacrosConfig {
+    /// The directory query data is saved to by `cargo sqlx prepare` and read from in offline
+    /// mode, instead of `.sqlx`.
+    pub offline_dir: Option<PathBuf>,
+
+    /// The crates whose types are used for columns and parameters when more than one crate
+    /// supporting them is enabled.
+    pub preferred_crates: PreferredCrates,
+
+    /// The Rust types used for columns and parameters of SQL types, by database.
+    pub type_overrides: TypeOverrides,
+
+    /// The nullability of Postgres columns which can't be inferred, such as those of views over
+    /// expressions, by table or view and then by column; `true` if the column may be `NULL`.
+    ///
+    /// The schema may be left out of the name of the table or view to match it in any schema.
+    pub column_nullability: BTreeMap<String, BTreeMap<String, bool>>,
+}
+
+/// Settings under `[macros.type-overrides]`: the Rust types used for columns and parameters of
+/// SQL types, by the name of the type as the database reports it, compared case-insensitively.
+///
+/// These take precedence over the types the macros would otherwise use, and may map custom
+/// types such as Postgres enums and domains, which have no type by default. The Rust type
+/// must implement `Type`, `Encode` and `Decode` for the database, e.g. by deriving `Type`.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TypeOverrides {
+    /// The types of PostgreSQL, under `[macros.type-overrides.postgres]`.
+    pub postgres: BTreeMap<String, String>,
+
+    /// The types of MySQL, under `[macros.type-overrides.mysql]`.
+    pub mysql: BTreeMap<String, String>,
+
+    /// The types of SQLite, under `[macros.type-overrides.sqlite]`.
+    pub sqlite: BTreeMap<String, String>,
+}
+
+/// Settings under `[macros.preferred-crates]`.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PreferredCrates {
+    /// The crate for date and time types.
+    pub date_time: DateTimeCrate,
+
+    /// The crate for arbitrary-precision decimal types, such as for `NUMERIC` in Postgres.
+    pub numeric: NumericCrate,
+}
+
+/// A crate providing date and time types.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DateTimeCrate {
+    /// Whichever crate the macros check first; `chrono` if both are enabled.
+    #[default]
+    Inferred,
+
+    /// [`chrono`](https://docs.rs/chrono)
+    Chrono,
+
+    /// [`time`](https://docs.rs/time)
+    Time,
+}
+
+/// A crate providing arbitrary-precision decimal types.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, serde::Deserialize)]
+pub enum NumericCrate {
+    /// Whichever crate the macros check first; `bigdecimal` if both are enabled.
+    #[default]
+    #[serde(rename = "inferred")]
+    Inferred,
+
+    /// [`bigdecimal`](https://docs.rs/bigdecimal)
+    #[serde(rename = "bigdecimal")]
+    BigDecimal,
+
+    /// [`rust_decimal`](https://docs.rs/rust_decimal)
+    #[serde(rename = "rust_decimal")]
+    RustDecimal,
+}
+
+/// Settings under `[migrate]`.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct MigrateConfig {
+    /// The directory of the migrations used by `migrate!()` and `sqlx migrate` if none is given,
+    /// instead of `migrations`.
+    pub migrations_dir: Option<PathBuf>,
+
+    /// The table applied migrations are recorded in, instead of `_sqlx_migrations`.
+    pub table_name: Option<String>,
+}
+
+/// A database the query macros can select by name, configured under `[databases.<name>]`.
+///
+/// The driver is chosen by the scheme of its URL, as it is for `DATABASE_URL`.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// The environment variable holding the URL of the database, which may be set in `.env`
+    /// like `DATABASE_URL`. Takes precedence over `url` if it is set.
+    pub url_var: Option<String>,
+
+    /// The URL of the database, for URLs which hold no credentials, such as SQLite files.
+    ///
+    /// A relative SQLite path is relative to the directory of `sqlx.toml`, when read from a file.
+    pub url: Option<String>,
+}
+
+impl Config {
+    /// The name of the configuration file.
+    pub const FILENAME: &'static str = "sqlx.toml";
+
+    /// Parse the configuration from the contents of `sqlx.toml`.
+    pub fn from_toml(toml: &str) -> Result<Self, Error> {
+        toml::from_str(toml).map_err(Error::config)
+    }
+
+    /// Read the configuration from the file at `path`, resolving relative paths in it against
+    /// the directory of the file.
+    pub fn read_from(path: &Path) -> Result<Self, Error> {
+        let mut config = Self::from_toml(&std::fs::read_to_string(path)?)?;
+
+        if let Some(dir) = path.parent() {
+            let paths = [
+                &mut config.macros.offline_dir,
+                &mut config.migrate.migrations_dir,
+            ];
+
+            for path in paths.into_iter().flatten() {
+                *path = dir.join(&*path);
+            }
+
+            for database in config.databases.values_mut() {
+                if let Some(url) = database
+                    .url
+                    .as_deref()
+                    .and_then(|url| sqlite_url_in(dir, url))
+                {
+                    database.url = Some(url);
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Get the database configured under `name`.
+    pub fn database(&self, name: &str) -> Result<&DatabaseConfig, Error> {
+        self.databases.get(name).ok_or_else(|| {
+            Error::Configuration(
+                format!(
+                    "no database named {:?} in {}; expected a `[databases.{}]` table",
+                    name,
+                    Self::FILENAME,
+                    name
+                )
+                .into(),
+            )
+        })
+    }
+}
+
+/// Find the project file `name`, such as `sqlx.toml`, at the root of the crate in `manifest_dir`,
+/// or else at the root of its workspace, if `workspace_root` returns it.
+///
+/// This is where both the query macros and `sqlx-cli` look for project files.
+pub fn find_project_file(
+    name: &str,
+    manifest_dir: &Path,
+    workspace_root: impl FnOnce() -> Option<PathBuf>,
+) -> Option<PathBuf> {
+    Some(manifest_dir.join(name))
+        .filter(|path| path.exists())
+        .or_else(|| Some(workspace_root()?.join(name)).filter(|path| path.exists()))
+}
+
+impl TypeOverrides {
+    /// The Rust type overriding the SQL type named `name` of the database named `database`, as
+    /// given by `Database::NAME`, if there is one.
+    pub fn get(&self, database: &str, name: &str) -> Option<&str> {
+        let overrides = match database {
+            "PostgreSQL" => &self.postgres,
+            "MySQL" => &self.mysql,
+            "SQLite" => &self.sqlite,
+            _ => return None,
+        };
+
+        overrides
+            .iter()
+            .find(|(sql_type, _)| sql_type.eq_ignore_ascii_case(name))
+            .map(|(_, rust_type)| rust_type.as_str())
+    }
+}
+
+impl CommonConfig {
+    /// The environment variable holding the URL of the database.
+    pub fn database_url_var(&self) -> &str {
+        self.database_url_var.as_deref().unwrap_or("DATABASE_URL")
+    }
+}
+
+impl DatabaseConfig {
+    /// Get the URL of the database, reading environment variables with `var`.
+    pub fn url(&self, var: impl FnOnce(&str) -> Option<String>) -> Option<String> {
+        self.url_var
+            .as_deref()
+            .and_then(var)
+            .or_else(|| self.url.clone())
+    }
+}
+
+/// Resolve the path of the SQLite database `url` against `dir`, if it is relative.
+fn sqlite_url_in(dir: &Path, url: &str) -> Option<String> {
+    let path_and_params = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))?;
+
+    let (path, params) = match path_and_params.split_once('?') {
+        Some((path, params)) => (path, Some(params)),
+        None => (path_and_params, None),
+    };
+
+    if path.is_empty() || path == ":memory:" || Path::new(path).is_absolute() {
+        return None;
+    }
+
+    // the path of the URL is percent-decoded when it is parsed
+    const RESERVED: &AsciiSet = &CONTROLS.add(b'%').add(b'?').add(b'#');
+    let dir = utf8_percent_encode(&dir.to_string_lossy(), RESERVED).to_string();
+
+    let mut url = format!("sqlite://{}/{}", dir.trim_end_matches('/'), path);
+    if let Some(params) = params {
+        url.push('?');
+        url.push_str(params);
+    }
+    Some(url)
+}
+
+#[test]
+fn test_sqlite_url_in() {
+    let dir = Path::new("/home/user/project");
+
+    assert_eq!(
+        sqlite_url_in(dir, "sqlite:data/app.db").as_deref(),
+        Some("sqlite:///home/user/project/data/app.db")
+    );
+    assert_eq!(
+        sqlite_url_in(dir, "sqlite://app.db?mode=ro").as_deref(),
+        Some("sqlite:///home/user/project/app.db?mode=ro")
+    );
+    assert_eq!(
+        sqlite_url_in(Path::new("/tmp/100%?"), "sqlite:app.db").as_deref(),
+        Some("sqlite:///tmp/100%25%3F/app.db")
+    );
+
+    assert_eq!(sqlite_url_in(dir, "sqlite:///var/app.db"), None);
+    assert_eq!(sqlite_url_in(dir, "sqlite::memory:"), None);
+    assert_eq!(sqlite_url_in(dir, "sqlite://?mode=memory"), None);
+    assert_eq!(sqlite_url_in(dir, "postgres://localhost/app"), None);
+}
+
+#[test]
+fn test_from_toml() -> Result<(), Error> {
+    let config = Config::from_toml(
+        r#"
+[common]
+database-url-var = "APP_DATABASE_URL"
+
+[macros]
+offline-dir = "queries"
+preferred-crates.date-time = "time"
+preferred-crates.numeric = "rust_decimal"
+
+[macros.column-nullability."public.active_users"]
+id = false
+last_seen = true
+
+[migrate]
+migrations-dir = "db/migrations"
+table-name = "schema_migrations"
+
+[databases.analytics]
+url-var = "ANALYTICS_DATABASE_URL"
+
+[databases.cache]
+url = "sqlite://cache.db"
+"#,
+    )?;
+
+    assert_eq!(config.common.database_url_var(), "APP_DATABASE_URL");
+    assert_eq!(config.macros.offline_dir, Some(PathBuf::from("queries")));
+    assert_eq!(
+        config.macros.preferred_crates.date_time,
+        DateTimeCrate::Time
+    );
+    assert_eq!(
+        config.macros.preferred_crates.numeric,
+        NumericCrate::RustDecimal
+    );
+    assert_eq!(
+        config.macros.column_nullability["public.active_users"],
+        BTreeMap::from([("id".into(), false), ("last_seen".into(), true)])
+    );
+    assert_eq!(
+        config.migrate.migrations_dir,
+        Some(PathBuf::from("db/migrations"))
+    );
+    assert_eq!(
+        config.migrate.table_name.as_deref(),
+        Some("schema_migrations")
+    );
+
+    let analytics = config.database("analytics")?;
+    assert_eq!(
+        analytics.url(|var| Some(format!("postgres://{}", var))),
+        Some("postgres://ANALYTICS_DATABASE_URL".into())
+    );
+    assert_eq!(analytics.url(|_| None), None);
+    assert_eq!(
+        config.database("cache")?.url(|_| None).as_deref(),
+        Some("sqlite://cache.db")
+    );
+    assert!(config.database("missing").is_err());
+
+    // every setting is optional
+    let config = Config::from_toml("")?;
+    assert_eq!(config.common.database_url_var(), "DATABASE_URL");
+    assert_eq!(
+        config.macros.preferred_crates.date_time,
+        DateTimeCrate::Inferred
+    );
+    assert!(config.databases.is_empty());
+
+    // misspelled settings are not silently ignored
+    assert!(Config::from_toml("[macros]\noffline_dir = \"queries\"").is_err());
+    assert!(Config::from_toml("[macros.preferred-crates]\nnumeric = \"decimal\"").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_type_overrides() -> Result<(), Error> {
+    let config = Config::from_toml(
+        r#"
+[macros.type-overrides.postgres]
+status = "crate::PgStatus"
+TIMESTAMPTZ = "crate::Timestamp"
+
+[macros.type-overrides.sqlite]
+status = "crate::SqliteStatus"
+"#,
+    )?;
+
+    let overrides = &config.macros.type_overrides;
+
+    // looked up by the database
+    assert_eq!(
+        overrides.get("PostgreSQL", "status"),
+        Some("crate::PgStatus")
+    );
+    assert_eq!(
+        overrides.get("SQLite", "status"),
+        Some("crate::SqliteStatus")
+    );
+    assert_eq!(overrides.get("MySQL", "status"), None);
+    assert_eq!(overrides.get("MSSQL", "status"), None);
+
+    // names are compared case-insensitively
+    assert_eq!(
+        overrides.get("PostgreSQL", "STATUS"),
+        Some("crate::PgStatus")
+    );
+    assert_eq!(
+        overrides.get("PostgreSQL", "timestamptz"),
+        Some("crate::Timestamp")
+    );
+    assert_eq!(overrides.get("SQLite", "timestamptz"), None);
+
+    // overrides must be given for a database
+    assert!(Config::from_toml("[macros.type-overrides]\nstatus = \"crate::Status\"").is_err());
+    assert!(
+        Config::from_toml("[macros.type-overrides.oracle]\nstatus = \"crate::Status\"").is_err()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_read_from() -> Result<(), Error> {
+    let dir = std::env::temp_dir().join(format!("sqlx-config-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+
+    let path = dir.join(Config::FILENAME);
+    std::fs::write(
+        &path,
+        r#"
+[macros]
+offline-dir = "queries"
+
+[migrate]
+migrations-dir = "/srv/migrations"
+
+[databases.cache]
+url = "sqlite:cache.db"
+"#,
+    )?;
+
+    let config = Config::read_from(&path);
+    std::fs::remove_dir_all(&dir)?;
+    let config = config?;
+
+    // relative paths are relative to the directory of the file
+    assert_eq!(config.macros.offline_dir, Some(dir.join("queries")));
+    assert_eq!(
+        config.migrate.migrations_dir,
+        Some(PathBuf::from("/srv/migrations"))
+    );
+    assert_eq!(
+        config.database("cache")?.url,
+        Some(format!("sqlite://{}/cache.db", dir.display()))
+    );
+
+    Ok(())
+}
@@ -104,6 +104,11 @@ pub enum Error {
     #[error("attempted to communicate with a crashed background worker")]
     WorkerCrashed,
 
+    /// A running query was interrupted, because it exceeded its timeout or was stopped by a
+    /// progress handler.
+    #[error("the query was interrupted")]
+    Interrupted,
+
     #[cfg(feature = "migrate")]
     #[error("{0}")]
     Migrate(#[source] Box<crate::migrate::MigrateError>),
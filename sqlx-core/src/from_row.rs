@@ -1,5 +1,10 @@
+use std::any::type_name;
+
+use crate::column::{Column, ColumnIndex};
+use crate::decode::Decode;
 use crate::error::Error;
 use crate::row::Row;
+use crate::types::Type;
 
 /// A record that can be built from a row returned by the database.
 ///
@@ -19,6 +24,65 @@ use crate::row::Row;
 /// }
 /// ```
 ///
+/// The fields of a tuple struct are read by index instead:
+///
+/// ```rust,ignore
+/// #[derive(sqlx::FromRow)]
+/// struct User(i32, String);
+/// ```
+///
+/// ### Enums
+///
+/// For an enum, the `tag` attribute names the column which selects the variant. The fields of
+/// the variant are then read from the other columns, like those of a struct, which allows
+/// modeling tables holding several kinds of records:
+///
+/// ```rust,ignore
+/// #[derive(sqlx::FromRow)]
+/// #[sqlx(tag = "kind", rename_all = "snake_case")]
+/// enum Shape {
+///     Circle { radius: f64 },
+///     // reads `Dimensions` with its own `FromRow` implementation
+///     Rectangle(Dimensions),
+///     #[sqlx(rename = "pt")]
+///     Point,
+/// }
+/// ```
+///
+/// Given a query such as:
+///
+/// ```sql
+/// SELECT kind, radius, width, height FROM shapes;
+/// ```
+///
+/// will read a `Shape::Circle` from the rows where `kind` is `circle`. The tag of each variant
+/// is its name, changed by `rename` on the variant or `rename_all` on the enum. An unknown
+/// tag is reported as [`Error::ColumnDecode`]. Like on a struct, `rename_all` also applies to
+/// the columns of the fields of each variant.
+///
+/// The tag is read as a string by default. To read it as another type, like an integer or a
+/// PostgreSQL enum, name a type with a variant of the same name for each variant of the enum
+/// in the `tag_type` attribute:
+///
+/// ```rust,ignore
+/// #[derive(sqlx::Type)]
+/// #[repr(i32)]
+/// enum ShapeKind {
+///     Circle = 1,
+///     Rectangle = 2,
+/// }
+///
+/// #[derive(sqlx::FromRow)]
+/// #[sqlx(tag = "kind", tag_type = "ShapeKind")]
+/// enum Shape {
+///     Circle { radius: f64 },
+///     Rectangle(Dimensions),
+/// }
+/// ```
+///
+/// The tag is then decoded by the `Decode` implementation of the tag type, so `rename` and
+/// `rename_all` don't change the tag of the variants.
+///
 /// ### Field attributes
 ///
 /// Several attributes can be specified to customize how each column in a row is read:
@@ -123,6 +187,70 @@ use crate::row::Row;
 ///
 /// This field is compatible with the `default` attribute.
 ///
+/// To flatten several fields of the same type, add a `prefix` to the names of their columns:
+///
+/// ```rust,ignore
+/// #[derive(sqlx::FromRow)]
+/// struct Post {
+///     id: i32,
+///     #[sqlx(flatten, prefix = "author_")]
+///     author: User,
+///     #[sqlx(flatten, prefix = "editor_")]
+///     editor: User,
+/// }
+/// ```
+///
+/// Given a query such as:
+///
+/// ```sql
+/// SELECT posts.id,
+///        author.id AS author_id, author.name AS author_name, ...
+///        editor.id AS editor_id, editor.name AS editor_name, ...
+/// FROM posts ...
+/// ```
+///
+/// The prefix applies to the columns of nested flattened fields as well, and is only supported
+/// by types that derive `FromRow`. A flattened tuple struct reads the columns whose names start
+/// with the prefix, in order.
+///
+/// #### `skip`
+///
+/// This is a variant of the `default` attribute which instead always takes the value from
+/// the `Default` implementation for this field type, ignoring any column in the row. This is
+/// useful for fields computed after the row is read:
+///
+/// ```rust,ignore
+/// #[derive(sqlx::FromRow)]
+/// struct User {
+///     id: i32,
+///     name: String,
+///     #[sqlx(skip)]
+///     permissions: Vec<Permission>,
+/// }
+/// ```
+///
+/// #### `json`
+///
+/// If your database supports a JSON type, you can use the `json` attribute to decode a column
+/// as `Json<T>` and unwrap it, for any field type that implements
+/// `serde::Deserialize`:
+///
+/// ```rust,ignore
+/// #[derive(serde::Deserialize)]
+/// struct Settings {
+///     theme: String,
+/// }
+///
+/// #[derive(sqlx::FromRow)]
+/// struct User {
+///     id: i32,
+///     #[sqlx(json)]
+///     settings: Settings,
+/// }
+/// ```
+///
+/// This attribute is compatible with `rename` and `default`.
+///
 /// ## Manual implementation
 ///
 /// You can also implement the [`FromRow`] trait by hand. This can be useful if you
@@ -177,6 +305,79 @@ use crate::row::Row;
 ///
 pub trait FromRow<'r, R: Row>: Sized {
     fn from_row(row: &'r R) -> Result<Self, Error>;
+
+    /// Like [`from_row()`][Self::from_row], but reads each column named `name` from the column
+    /// named `{prefix}{name}` instead.
+    ///
+    /// Used for `#[sqlx(flatten, prefix = "..")]`; only derived implementations support a
+    /// non-empty prefix.
+    #[doc(hidden)]
+    fn from_row_prefixed(row: &'r R, prefix: &str) -> Result<Self, Error>
+    where
+        usize: ColumnIndex<R>,
+    {
+        if prefix.is_empty() {
+            Self::from_row(row)
+        } else {
+            Err(Error::Decode(
+                format!(
+                    "`{}` does not support column prefixes; derive `FromRow` for it",
+                    type_name::<Self>()
+                )
+                .into(),
+            ))
+        }
+    }
+}
+
+/// Read the column named `{prefix}{name}`; used by `FromRow::from_row_prefixed()`.
+#[doc(hidden)]
+pub fn try_get_prefixed<'r, R, T>(row: &'r R, prefix: &str, name: &str) -> Result<T, Error>
+where
+    R: Row,
+    usize: ColumnIndex<R>,
+    T: Decode<'r, R::Database> + Type<R::Database>,
+{
+    let index = row
+        .columns()
+        .iter()
+        .position(|column| column.name().strip_prefix(prefix) == Some(name))
+        .ok_or_else(|| Error::ColumnNotFound(format!("{}{}", prefix, name)))?;
+
+    row.try_get(index).map_err(|e| match e {
+        Error::ColumnDecode { source, .. } => Error::ColumnDecode {
+            index: format!("{:?}", format!("{}{}", prefix, name)),
+            source,
+        },
+        e => e,
+    })
+}
+
+/// Read the `n`th column whose name starts with `prefix`; used by `FromRow::from_row_prefixed()`
+/// for tuple structs.
+#[doc(hidden)]
+pub fn try_get_prefixed_nth<'r, R, T>(row: &'r R, prefix: &str, n: usize) -> Result<T, Error>
+where
+    R: Row,
+    usize: ColumnIndex<R>,
+    T: Decode<'r, R::Database> + Type<R::Database>,
+{
+    let (index, name) = row
+        .columns()
+        .iter()
+        .enumerate()
+        .filter(|(_, column)| column.name().starts_with(prefix))
+        .nth(n)
+        .map(|(index, column)| (index, column.name().to_owned()))
+        .ok_or_else(|| Error::ColumnNotFound(format!("{}.. (column {})", prefix, n)))?;
+
+    row.try_get(index).map_err(|e| match e {
+        Error::ColumnDecode { source, .. } => Error::ColumnDecode {
+            index: format!("{:?}", name),
+            source,
+        },
+        e => e,
+    })
 }
 
 // implement FromRow for tuples of types that implement Decode
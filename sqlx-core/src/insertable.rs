@@ -0,0 +1,144 @@
+use crate::database::Database;
+use crate::query_builder::QueryBuilder;
+
+/// A record that can be written to a table.
+///
+/// The queries are returned as a [`QueryBuilder`] with the fields of the record bound as
+/// arguments, so they can be extended further before being built and executed.
+///
+/// ## Derivable
+///
+/// This trait can be derived by SQLx for any struct with named fields. Each field is a column
+/// of the table named by the `table` attribute, and at least one field must be marked as part
+/// of the primary key:
+///
+/// ```rust,ignore
+/// #[derive(sqlx::Insertable)]
+/// #[sqlx(table = "users")]
+/// struct User {
+///     #[sqlx(primary_key)]
+///     id: i32,
+///     name: String,
+/// }
+///
+/// User { id: 1, name: "Alice".into() }
+///     .insert()
+///     .build()
+///     .execute(&mut conn)
+///     .await?;
+/// ```
+///
+/// The `rename`, `rename_all` and `json` attributes map fields to columns as they do for
+/// [`FromRow`](crate::from_row::FromRow), and fields marked with `skip` are not written at all,
+/// so the same struct can usually derive both traits.
+///
+/// The derived implementation writes statements in the [`InsertDialect`] of the database, so it
+/// is not available for `Any`.
+pub trait Insertable<'q, DB: Database>: Sized + 'q {
+    /// The name of the table the record is written to.
+    const TABLE: &'static str;
+
+    /// Build an `INSERT` statement for this record.
+    fn insert(&'q self) -> QueryBuilder<'q, DB> {
+        Self::insert_all(std::iter::once(self))
+    }
+
+    /// Build a single `INSERT` statement for all of `rows`.
+    ///
+    /// The `VALUES` clause is written with [`QueryBuilder::push_values`], so the same limits
+    /// on the number of bind parameters apply. `rows` must not be empty, or the generated
+    /// statement is not valid SQL.
+    fn insert_all<I>(rows: I) -> QueryBuilder<'q, DB>
+    where
+        I: IntoIterator<Item = &'q Self>;
+
+    /// Build an `UPDATE` statement setting every column of the row with the primary key of
+    /// this record.
+    fn update(&'q self) -> QueryBuilder<'q, DB>;
+
+    /// Build a statement which inserts this record, or updates the existing row if one with
+    /// the same primary key exists.
+    ///
+    /// See [`InsertDialect::upsert_sql()`] for the syntax used by each database.
+    fn upsert(&'q self) -> QueryBuilder<'q, DB>;
+}
+
+/// The syntax a database uses for the statements built by [`Insertable`].
+///
+/// This is not implemented for `Any`, as the syntax depends on the database it connects to
+/// at runtime.
+pub trait InsertDialect: Database {
+    /// Quote `name` as an identifier, quoting each part of a qualified name like
+    /// `schema.table` separately.
+    ///
+    /// Uses double quotes by default, as in standard SQL.
+    fn quote_identifier(name: &str) -> String {
+        quote_identifier_with(name, '"')
+    }
+
+    /// The `INSERT` statement, up to the `VALUES` clause, which inserts `columns` into `table`.
+    fn insert_sql(table: &str, columns: &[&str]) -> String {
+        format!(
+            "INSERT INTO {} ({}) ",
+            Self::quote_identifier(table),
+            columns
+                .iter()
+                .map(|column| Self::quote_identifier(column))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    /// The SQL before and after the `VALUES` clause of a statement which inserts `columns` into
+    /// `table`, or updates the `updated` columns of the existing row with the same `keys`.
+    ///
+    /// If `updated` is empty, the existing row is left unchanged. By default this uses
+    /// `ON CONFLICT (..) DO UPDATE` or `ON CONFLICT (..) DO NOTHING`, like PostgreSQL and SQLite.
+    fn upsert_sql(
+        table: &str,
+        columns: &[&str],
+        keys: &[&str],
+        updated: &[&str],
+    ) -> (String, String) {
+        let keys = keys
+            .iter()
+            .map(|key| Self::quote_identifier(key))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let on_conflict = if updated.is_empty() {
+            format!(" ON CONFLICT ({}) DO NOTHING", keys)
+        } else {
+            format!(
+                " ON CONFLICT ({}) DO UPDATE SET {}",
+                keys,
+                updated
+                    .iter()
+                    .map(|column| {
+                        let column = Self::quote_identifier(column);
+                        format!("{0} = EXCLUDED.{0}", column)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+
+        (Self::insert_sql(table, columns), on_conflict)
+    }
+}
+
+/// Quote each part of `name` with `quote`, doubling any `quote` in it.
+pub fn quote_identifier_with(name: &str, quote: char) -> String {
+    let escaped = quote.to_string().repeat(2);
+
+    name.split('.')
+        .map(|part| {
+            format!(
+                "{quote}{}{quote}",
+                part.replace(quote, &escaped),
+                quote = quote
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
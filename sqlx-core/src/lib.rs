@@ -63,11 +63,14 @@ pub mod column;
 pub mod statement;
 
 pub mod common;
+#[cfg(feature = "sqlx-toml")]
+pub mod config;
 pub mod database;
 pub mod describe;
 pub mod executor;
 pub mod from_row;
 pub mod fs;
+pub mod insertable;
 pub mod io;
 pub mod logger;
 pub mod net;
@@ -20,7 +20,10 @@ pub enum MigrateError {
 
     // NOTE: this will only happen with a database that does not have transactional DDL (.e.g, MySQL or Oracle)
     #[error(
-        "migration {0} is partially applied; fix and remove row from `_sqlx_migrations` table"
+        "migration {0} is partially applied; fix and remove its row from the migrations table"
     )]
     Dirty(i64),
+
+    #[error("recording applied migrations in table {0:?} is not supported by this database")]
+    TableNameUnsupported(String),
 }
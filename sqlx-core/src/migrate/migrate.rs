@@ -1,5 +1,5 @@
 use crate::error::Error;
-use crate::migrate::{AppliedMigration, MigrateError, Migration};
+use crate::migrate::{AppliedMigration, MigrateError, Migration, Migrator};
 use futures_core::future::BoxFuture;
 use std::time::Duration;
 
@@ -56,4 +56,16 @@ pub trait Migrate {
         &'e mut self,
         migration: &'m Migration,
     ) -> BoxFuture<'m, Result<Duration, MigrateError>>;
+
+    // Record applied migrations in `table_name` instead of `_sqlx_migrations` in the methods
+    // above, until another table is set. [`Migrator`] sets its table before running.
+    // By default, only `_sqlx_migrations` is supported and any other table fails with
+    // [`MigrateError::TableNameUnsupported`].
+    fn set_migrations_table(&mut self, table_name: &str) -> Result<(), MigrateError> {
+        if table_name == Migrator::DEFAULT_TABLE_NAME {
+            Ok(())
+        } else {
+            Err(MigrateError::TableNameUnsupported(table_name.into()))
+        }
+    }
 }
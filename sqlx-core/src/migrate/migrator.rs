@@ -11,6 +11,7 @@ pub struct Migrator {
     pub migrations: Cow<'static, [Migration]>,
     pub ignore_missing: bool,
     pub locking: bool,
+    table_name: Cow<'static, str>,
 }
 
 fn validate_applied_migrations(
@@ -33,6 +34,20 @@ fn validate_applied_migrations(
 }
 
 impl Migrator {
+    /// The table applied migrations are recorded in, unless another is set with
+    /// [`set_table_name()`][Self::set_table_name].
+    pub const DEFAULT_TABLE_NAME: &'static str = "_sqlx_migrations";
+
+    #[doc(hidden)]
+    pub const fn __from_static(migrations: &'static [Migration], table_name: &'static str) -> Self {
+        Self {
+            migrations: Cow::Borrowed(migrations),
+            ignore_missing: false,
+            locking: true,
+            table_name: Cow::Borrowed(table_name),
+        }
+    }
+
     /// Creates a new instance with the given source.
     ///
     /// # Examples
@@ -59,6 +74,7 @@ impl Migrator {
             migrations: Cow::Owned(source.resolve().await.map_err(MigrateError::Source)?),
             ignore_missing: false,
             locking: true,
+            table_name: Cow::Borrowed(Self::DEFAULT_TABLE_NAME),
         })
     }
 
@@ -81,6 +97,15 @@ impl Migrator {
         self
     }
 
+    /// Specify the table applied migrations are recorded in. Defaults to `_sqlx_migrations`.
+    ///
+    /// The name is quoted as an identifier, and may be qualified with a schema as
+    /// `schema.table`. Databases other than those built into SQLx may only support the default.
+    pub fn set_table_name(&mut self, table_name: impl Into<Cow<'static, str>>) -> &Self {
+        self.table_name = table_name.into();
+        self
+    }
+
     /// Get an iterator over all known migrations.
     pub fn iter(&self) -> slice::Iter<'_, Migration> {
         self.migrations.iter()
@@ -119,6 +144,9 @@ impl Migrator {
     where
         C: Migrate,
     {
+        // record applied migrations in our table in the methods below
+        conn.set_migrations_table(&self.table_name)?;
+
         // lock the database for exclusive access by the migrator
         if self.locking {
             conn.lock().await?;
@@ -191,6 +219,9 @@ impl Migrator {
     {
         let mut conn = migrator.acquire().await?;
 
+        // record applied migrations in our table in the methods below
+        conn.set_migrations_table(&self.table_name)?;
+
         // lock the database for exclusive access by the migrator
         if self.locking {
             conn.lock().await?;
@@ -4,6 +4,7 @@ use std::sync::Mutex;
 
 use once_cell::sync::Lazy;
 
+use sqlx_core::config::{DateTimeCrate, NumericCrate};
 use sqlx_core::connection::Connection;
 use sqlx_core::database::Database;
 use sqlx_core::describe::Describe;
@@ -22,6 +23,14 @@ pub trait DatabaseExt: Database {
 
     const PARAM_CHECKING: ParamChecking;
 
+    /// Whether bind parameters are numbered as `$N`, bound once however many times it is used,
+    /// instead of `?`, bound again each time it is used.
+    const NUMBERED_PARAMS: bool;
+
+    /// Whether `\` escapes the next character in every quoted string, not just in `E'..'`
+    /// strings.
+    const BACKSLASH_ESCAPES: bool;
+
     fn db_path() -> syn::Path {
         syn::parse_str(Self::DATABASE_PATH).unwrap()
     }
@@ -39,6 +48,67 @@ pub trait DatabaseExt: Database {
     fn describe_blocking(query: &str, database_url: &str) -> sqlx_core::Result<Describe<Self>>;
 }
 
+/// Whether `ty`, the path of a Rust type in the mapping of a database, is not from a crate
+/// which `sqlx.toml` prefers another one over.
+///
+/// Types mapped under `[macros.type-overrides]` are not subject to this, as they are looked up
+/// with [`type_override()`] first.
+pub(crate) fn is_preferred_type(ty: &str) -> bool {
+    // a broken `sqlx.toml` is reported by the macro itself
+    let Ok(config) = crate::query::config() else {
+        return true;
+    };
+
+    let preferred = &config.macros.preferred_crates;
+
+    // the modules of `sqlx::types` re-exporting the crates which are not preferred, or the
+    // types they re-export at its root
+    let date_time = match preferred.date_time {
+        DateTimeCrate::Inferred => None,
+        DateTimeCrate::Chrono => Some("time"),
+        DateTimeCrate::Time => Some("chrono"),
+    };
+    let numeric = match preferred.numeric {
+        NumericCrate::Inferred => None,
+        NumericCrate::BigDecimal => Some("Decimal"),
+        NumericCrate::RustDecimal => Some("BigDecimal"),
+    };
+
+    let avoided: Vec<&str> = [date_time, numeric].into_iter().flatten().collect();
+
+    !uses_sqlx_types(ty, &avoided)
+}
+
+/// Whether `ty` names `sqlx::types::<name>`, or an item below it, for any of `names`.
+fn uses_sqlx_types(ty: &str, names: &[&str]) -> bool {
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    // `stringify!()` puts spaces between the tokens of a path
+    let ty: String = ty.split_whitespace().collect();
+
+    ty.match_indices("sqlx::types::").any(|(i, prefix)| {
+        // e.g. `my_sqlx::types::time` is not ours
+        let is_sqlx = !ty[..i].ends_with(is_ident_char);
+        let segment = ty[i + prefix.len()..]
+            .split(|c: char| !is_ident_char(c))
+            .next()
+            .unwrap_or_default();
+
+        is_sqlx && names.contains(&segment)
+    })
+}
+
+/// The Rust type `sqlx.toml` maps the SQL type named `name` of `DB` to, if there is one.
+pub(crate) fn type_override<DB: Database>(name: &str) -> Option<&'static str> {
+    crate::query::config()
+        .ok()?
+        .macros
+        .type_overrides
+        .get(DB::NAME, name)
+}
+
+type ConnectOptionsOf<DB> = <<DB as Database>::Connection as Connection>::Options;
+
 pub struct CachingDescribeBlocking<DB: DatabaseExt> {
     connections: Lazy<Mutex<HashMap<String, DB::Connection>>>,
 }
@@ -50,7 +120,14 @@ impl<DB: DatabaseExt> CachingDescribeBlocking<DB> {
         }
     }
 
-    pub fn describe(&self, query: &str, database_url: &str) -> sqlx_core::Result<Describe<DB>>
+    /// Describe `query` with a connection to `database_url`, applying `configure` to the
+    /// options of the connection if one has to be opened.
+    pub fn describe(
+        &self,
+        query: &str,
+        database_url: &str,
+        configure: impl FnOnce(ConnectOptionsOf<DB>) -> sqlx_core::Result<ConnectOptionsOf<DB>>,
+    ) -> sqlx_core::Result<Describe<DB>>
     where
         for<'a> &'a mut DB::Connection: Executor<'a, Database = DB>,
     {
@@ -62,8 +139,9 @@ impl<DB: DatabaseExt> CachingDescribeBlocking<DB> {
 
             let conn = match cache.entry(database_url.to_string()) {
                 hash_map::Entry::Occupied(hit) => hit.into_mut(),
-                hash_map::Entry::Vacant(mut miss) => {
-                    miss.insert(DB::Connection::connect(&database_url).await?)
+                hash_map::Entry::Vacant(miss) => {
+                    let options = configure(database_url.parse()?)?;
+                    miss.insert(DB::Connection::connect_with(&options).await?)
                 }
             };
 
@@ -78,6 +156,8 @@ macro_rules! impl_database_ext {
             $($(#[$meta:meta])? $ty:ty $(| $input:ty)?),*$(,)?
         },
         ParamChecking::$param_checking:ident,
+        numbered-params: $numbered_params:literal,
+        backslash-escapes: $backslash_escapes:literal,
         feature-types: $ty_info:ident => $get_gate:expr,
         row: $row:path,
         $(describe-blocking: $describe:path,)?
@@ -86,9 +166,22 @@ macro_rules! impl_database_ext {
             const DATABASE_PATH: &'static str = stringify!($database);
             const ROW_PATH: &'static str = stringify!($row);
             const PARAM_CHECKING: $crate::database::ParamChecking = $crate::database::ParamChecking::$param_checking;
+            const NUMBERED_PARAMS: bool = $numbered_params;
+            const BACKSLASH_ESCAPES: bool = $backslash_escapes;
 
             fn param_type_for_id(info: &Self::TypeInfo) -> Option<&'static str> {
+                use $crate::database::is_preferred_type as preferred;
+
+                // types from crates preferred in `sqlx.toml` are tried first
                 match () {
+                    $(
+                        $(#[$meta])?
+                        _ if <$ty as sqlx_core::types::Type<$database>>::type_info() == *info && preferred(input_ty!($ty $(, $input)?)) => Some(input_ty!($ty $(, $input)?)),
+                    )*
+                    $(
+                        $(#[$meta])?
+                        _ if <$ty as sqlx_core::types::Type<$database>>::compatible(info) && preferred(input_ty!($ty $(, $input)?)) => Some(input_ty!($ty $(, $input)?)),
+                    )*
                     $(
                         $(#[$meta])?
                         _ if <$ty as sqlx_core::types::Type<$database>>::type_info() == *info => Some(input_ty!($ty $(, $input)?)),
@@ -102,7 +195,17 @@ macro_rules! impl_database_ext {
             }
 
             fn return_type_for_id(info: &Self::TypeInfo) -> Option<&'static str> {
+                use $crate::database::is_preferred_type as preferred;
+
                 match () {
+                    $(
+                        $(#[$meta])?
+                        _ if <$ty as sqlx_core::types::Type<$database>>::type_info() == *info && preferred(stringify!($ty)) => return Some(stringify!($ty)),
+                    )*
+                    $(
+                        $(#[$meta])?
+                        _ if <$ty as sqlx_core::types::Type<$database>>::compatible(info) && preferred(stringify!($ty)) => return Some(stringify!($ty)),
+                    )*
                     $(
                         $(#[$meta])?
                         _ if <$ty as sqlx_core::types::Type<$database>>::type_info() == *info => return Some(stringify!($ty)),
@@ -135,7 +238,7 @@ macro_rules! impl_describe_blocking {
             // This can't be a provided method because the `static` can't reference `Self`.
             static CACHE: CachingDescribeBlocking<$database> = CachingDescribeBlocking::new();
 
-            CACHE.describe(query, database_url)
+            CACHE.describe(query, database_url, Ok)
         }
     };
     ($database:path, $describe:path) => {
@@ -178,3 +281,29 @@ mod fake_sqlx {
     #[cfg(feature = "sqlite")]
     pub use sqlx_sqlite as sqlite;
 }
+
+#[test]
+fn test_uses_sqlx_types() {
+    assert!(uses_sqlx_types("sqlx :: types :: time :: Date", &["time"]));
+    assert!(uses_sqlx_types(
+        "::sqlx::types::BigDecimal",
+        &["BigDecimal"]
+    ));
+    assert!(uses_sqlx_types(
+        "sqlx::postgres::types::PgTimeTz<sqlx::types::time::Time, sqlx::types::time::UtcOffset>",
+        &["chrono", "time"]
+    ));
+
+    // only the segment right after `sqlx::types::` is matched, and in full
+    assert!(!uses_sqlx_types(
+        "sqlx::types::chrono::NaiveTime",
+        &["time"]
+    ));
+    assert!(!uses_sqlx_types("sqlx::types::Decimals", &["Decimal"]));
+    assert!(!uses_sqlx_types(
+        "sqlx::types::Json<my_app::time::Timestamp>",
+        &["time"]
+    ));
+    assert!(!uses_sqlx_types("my_app::time::Timestamp", &["time"]));
+    assert!(!uses_sqlx_types("my_sqlx::types::time::Date", &["time"]));
+}
@@ -54,6 +54,8 @@ impl_database_ext! {
         sqlx::types::JsonValue,
     },
     ParamChecking::Weak,
+    numbered-params: false,
+    backslash-escapes: true,
     feature-types: info => info.__type_feature_gate(),
     row: sqlx::mysql::MySqlRow,
 }
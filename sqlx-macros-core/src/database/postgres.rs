@@ -1,4 +1,7 @@
+use sqlx_core::describe::Describe;
+
 use super::fake_sqlx as sqlx;
+use super::CachingDescribeBlocking;
 
 impl_database_ext! {
     sqlx::postgres::Postgres {
@@ -202,6 +205,31 @@ impl_database_ext! {
             &[sqlx::postgres::types::PgRange<sqlx::types::time::OffsetDateTime>],
     },
     ParamChecking::Strong,
+    numbered-params: true,
+    backslash-escapes: false,
     feature-types: info => info.__type_feature_gate(),
     row: sqlx::postgres::PgRow,
+    describe-blocking: describe_blocking,
+}
+
+/// Describe `query`, applying the column nullability set in `sqlx.toml` to its columns.
+fn describe_blocking(
+    query: &str,
+    database_url: &str,
+) -> sqlx_core::Result<Describe<sqlx::postgres::Postgres>> {
+    static CACHE: CachingDescribeBlocking<sqlx::postgres::Postgres> =
+        CachingDescribeBlocking::new();
+
+    CACHE.describe(query, database_url, |mut options| {
+        let config = crate::query::config()
+            .map_err(|e| sqlx_core::Error::Configuration(e.to_string().into()))?;
+
+        for (relation, columns) in &config.macros.column_nullability {
+            for (column, nullable) in columns {
+                options = options.column_nullability(relation.clone(), column.clone(), *nullable);
+            }
+        }
+
+        Ok(options)
+    })
 }
@@ -28,6 +28,8 @@ impl_database_ext! {
         sqlx::types::Uuid,
     },
     ParamChecking::Weak,
+    numbered-params: false,
+    backslash-escapes: false,
     feature-types: _info => None,
     row: sqlx::sqlite::SqliteRow,
     // Since proc-macros don't benefit from async, we can make a describe call directly
@@ -1,9 +1,11 @@
 use proc_macro2::{Ident, Span, TokenStream};
-use quote::{quote, quote_spanned};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::token::Comma;
-use syn::{Attribute, DeriveInput, Field, Lit, Meta, MetaNameValue, NestedMeta, Type, Variant};
+use syn::{
+    Attribute, DeriveInput, Field, Lit, Meta, MetaNameValue, NestedMeta, Path, Type, Variant,
+};
 
 macro_rules! assert_attribute {
     ($e:expr, $err:expr, $input:expr) => {
@@ -56,6 +58,9 @@ pub struct SqlxContainerAttributes {
     pub type_name: Option<TypeName>,
     pub rename_all: Option<RenameAll>,
     pub repr: Option<Ident>,
+    pub tag: Option<String>,
+    pub tag_type: Option<Path>,
+    pub table: Option<String>,
 }
 
 pub struct SqlxChildAttributes {
@@ -63,6 +68,10 @@ pub struct SqlxChildAttributes {
     pub default: bool,
     pub flatten: bool,
     pub try_from: Option<Type>,
+    pub skip: bool,
+    pub json: bool,
+    pub prefix: Option<String>,
+    pub primary_key: bool,
 }
 
 pub fn parse_container_attributes(input: &[Attribute]) -> syn::Result<SqlxContainerAttributes> {
@@ -70,6 +79,9 @@ pub fn parse_container_attributes(input: &[Attribute]) -> syn::Result<SqlxContai
     let mut repr = None;
     let mut type_name = None;
     let mut rename_all = None;
+    let mut tag = None;
+    let mut tag_type = None;
+    let mut table = None;
 
     for attr in input
         .iter()
@@ -121,6 +133,26 @@ pub fn parse_container_attributes(input: &[Attribute]) -> syn::Result<SqlxContai
                                 )
                             }
 
+                            Meta::NameValue(MetaNameValue {
+                                path,
+                                lit: Lit::Str(val),
+                                ..
+                            }) if path.is_ident("tag") => try_set!(tag, val.value(), value),
+
+                            Meta::NameValue(MetaNameValue {
+                                path,
+                                lit: Lit::Str(val),
+                                ..
+                            }) if path.is_ident("tag_type") => {
+                                try_set!(tag_type, val.parse()?, value)
+                            }
+
+                            Meta::NameValue(MetaNameValue {
+                                path,
+                                lit: Lit::Str(val),
+                                ..
+                            }) if path.is_ident("table") => try_set!(table, val.value(), value),
+
                             u => fail!(u, "unexpected attribute"),
                         },
                         u => fail!(u, "unexpected attribute"),
@@ -147,6 +179,9 @@ pub fn parse_container_attributes(input: &[Attribute]) -> syn::Result<SqlxContai
         repr,
         type_name,
         rename_all,
+        tag,
+        tag_type,
+        table,
     })
 }
 
@@ -155,6 +190,10 @@ pub fn parse_child_attributes(input: &[Attribute]) -> syn::Result<SqlxChildAttri
     let mut default = false;
     let mut try_from = None;
     let mut flatten = false;
+    let mut skip = false;
+    let mut json = false;
+    let mut prefix = None;
+    let mut primary_key = false;
 
     for attr in input.iter().filter(|a| a.path.is_ident("sqlx")) {
         let meta = attr
@@ -177,6 +216,14 @@ pub fn parse_child_attributes(input: &[Attribute]) -> syn::Result<SqlxChildAttri
                         }) if path.is_ident("try_from") => try_set!(try_from, val.parse()?, value),
                         Meta::Path(path) if path.is_ident("default") => default = true,
                         Meta::Path(path) if path.is_ident("flatten") => flatten = true,
+                        Meta::Path(path) if path.is_ident("skip") => skip = true,
+                        Meta::Path(path) if path.is_ident("json") => json = true,
+                        Meta::Path(path) if path.is_ident("primary_key") => primary_key = true,
+                        Meta::NameValue(MetaNameValue {
+                            path,
+                            lit: Lit::Str(val),
+                            ..
+                        }) if path.is_ident("prefix") => try_set!(prefix, val.value(), value),
                         u => fail!(u, "unexpected attribute"),
                     },
                     u => fail!(u, "unexpected attribute"),
@@ -185,11 +232,41 @@ pub fn parse_child_attributes(input: &[Attribute]) -> syn::Result<SqlxChildAttri
         }
     }
 
+    if let Some(attr) = input.iter().find(|a| a.path.is_ident("sqlx")) {
+        assert_attribute!(
+            !skip
+                || (rename.is_none()
+                    && !default
+                    && !flatten
+                    && try_from.is_none()
+                    && !json
+                    && !primary_key),
+            "#[sqlx(skip)] cannot be combined with other attributes",
+            attr
+        );
+
+        assert_attribute!(
+            !json || (!flatten && try_from.is_none()),
+            "#[sqlx(json)] cannot be combined with #[sqlx(flatten)] or #[sqlx(try_from = ..)]",
+            attr
+        );
+
+        assert_attribute!(
+            prefix.is_none() || flatten,
+            "#[sqlx(prefix = ..)] requires #[sqlx(flatten)]",
+            attr
+        );
+    }
+
     Ok(SqlxChildAttributes {
         rename,
         default,
         flatten,
         try_from,
+        skip,
+        json,
+        prefix,
+        primary_key,
     })
 }
 
@@ -205,6 +282,24 @@ pub fn check_transparent_attributes(
         field
     );
 
+    assert_attribute!(
+        attributes.tag.is_none(),
+        "unexpected #[sqlx(tag = ..)]",
+        field
+    );
+
+    assert_attribute!(
+        attributes.tag_type.is_none(),
+        "unexpected #[sqlx(tag_type = ..)]",
+        field
+    );
+
+    assert_attribute!(
+        attributes.table.is_none(),
+        "unexpected #[sqlx(table = ..)]",
+        field
+    );
+
     let ch_attributes = parse_child_attributes(&field.attrs)?;
 
     assert_attribute!(
@@ -213,6 +308,8 @@ pub fn check_transparent_attributes(
         field
     );
 
+    check_type_child_attributes(&ch_attributes, field)?;
+
     Ok(attributes)
 }
 
@@ -225,6 +322,24 @@ pub fn check_enum_attributes(input: &DeriveInput) -> syn::Result<SqlxContainerAt
         input
     );
 
+    assert_attribute!(
+        attributes.tag.is_none(),
+        "unexpected #[sqlx(tag = ..)]",
+        input
+    );
+
+    assert_attribute!(
+        attributes.tag_type.is_none(),
+        "unexpected #[sqlx(tag_type = ..)]",
+        input
+    );
+
+    assert_attribute!(
+        attributes.table.is_none(),
+        "unexpected #[sqlx(table = ..)]",
+        input
+    );
+
     Ok(attributes)
 }
 
@@ -250,6 +365,8 @@ pub fn check_weak_enum_attributes(
             "unexpected #[sqlx(rename = ..)]",
             variant
         );
+
+        check_type_child_attributes(&attributes, variant)?;
     }
 
     Ok(attributes)
@@ -257,12 +374,16 @@ pub fn check_weak_enum_attributes(
 
 pub fn check_strong_enum_attributes(
     input: &DeriveInput,
-    _variants: &Punctuated<Variant, Comma>,
+    variants: &Punctuated<Variant, Comma>,
 ) -> syn::Result<SqlxContainerAttributes> {
     let attributes = check_enum_attributes(input)?;
 
     assert_attribute!(attributes.repr.is_none(), "unexpected #[repr(..)]", input);
 
+    for variant in variants {
+        check_type_child_attributes(&parse_child_attributes(&variant.attrs)?, variant)?;
+    }
+
     Ok(attributes)
 }
 
@@ -286,6 +407,24 @@ pub fn check_struct_attributes<'a>(
 
     assert_attribute!(attributes.repr.is_none(), "unexpected #[repr(..)]", input);
 
+    assert_attribute!(
+        attributes.tag.is_none(),
+        "unexpected #[sqlx(tag = ..)]",
+        input
+    );
+
+    assert_attribute!(
+        attributes.tag_type.is_none(),
+        "unexpected #[sqlx(tag_type = ..)]",
+        input
+    );
+
+    assert_attribute!(
+        attributes.table.is_none(),
+        "unexpected #[sqlx(table = ..)]",
+        input
+    );
+
     for field in fields {
         let attributes = parse_child_attributes(&field.attrs)?;
 
@@ -294,7 +433,34 @@ pub fn check_struct_attributes<'a>(
             "unexpected #[sqlx(rename = ..)]",
             field
         );
+
+        check_type_child_attributes(&attributes, field)?;
     }
 
     Ok(attributes)
 }
+
+/// Rejects the attributes of `FromRow` and `Insertable` fields, which `Type`, `Encode` and
+/// `Decode` would ignore.
+fn check_type_child_attributes(
+    attributes: &SqlxChildAttributes,
+    input: impl ToTokens,
+) -> syn::Result<()> {
+    assert_attribute!(!attributes.skip, "unexpected #[sqlx(skip)]", input);
+
+    assert_attribute!(!attributes.json, "unexpected #[sqlx(json)]", input);
+
+    assert_attribute!(
+        attributes.prefix.is_none(),
+        "unexpected #[sqlx(prefix = ..)]",
+        input
+    );
+
+    assert_attribute!(
+        !attributes.primary_key,
+        "unexpected #[sqlx(primary_key)]",
+        input
+    );
+
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    parse_quote, punctuated::Punctuated, token::Comma, Data, DataStruct, DeriveInput, Expr, Field,
+    Fields, FieldsNamed, Lifetime, Stmt,
+};
+
+use super::attributes::{parse_child_attributes, parse_container_attributes, SqlxChildAttributes};
+
+pub fn expand_derive_insertable(input: &DeriveInput) -> syn::Result<TokenStream> {
+    match &input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(FieldsNamed { named, .. }),
+            ..
+        }) => expand_derive_insertable_struct(input, named),
+
+        Data::Struct(_) => Err(syn::Error::new_spanned(
+            input,
+            "only structs with named fields are supported",
+        )),
+
+        Data::Enum(_) => Err(syn::Error::new_spanned(input, "enums are not supported")),
+
+        Data::Union(_) => Err(syn::Error::new_spanned(input, "unions are not supported")),
+    }
+}
+
+struct Column<'a> {
+    field: &'a Field,
+    attributes: SqlxChildAttributes,
+    name: String,
+}
+
+impl Column<'_> {
+    /// The expression binding this column of `record`.
+    fn bind(&self, record: TokenStream) -> Expr {
+        let id = &self.field.ident;
+
+        if self.attributes.json {
+            parse_quote!(::sqlx::types::Json(&#record.#id))
+        } else {
+            parse_quote!(&#record.#id)
+        }
+    }
+}
+
+fn expand_derive_insertable_struct(
+    input: &DeriveInput,
+    fields: &Punctuated<Field, Comma>,
+) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+
+    let container_attributes = parse_container_attributes(&input.attrs)?;
+
+    let table = container_attributes.table.as_ref().ok_or_else(|| {
+        syn::Error::new_spanned(
+            input,
+            "expected #[sqlx(table = \"..\")] naming the table the struct is written to",
+        )
+    })?;
+
+    if container_attributes.transparent || container_attributes.tag.is_some() {
+        return Err(syn::Error::new_spanned(
+            input,
+            "#[sqlx(transparent)] and #[sqlx(tag = ..)] are not supported",
+        ));
+    }
+
+    let mut columns = Vec::new();
+
+    for field in fields {
+        let attributes = parse_child_attributes(&field.attrs)?;
+
+        if attributes.skip {
+            continue;
+        }
+
+        if attributes.flatten {
+            return Err(syn::Error::new_spanned(
+                field,
+                "#[sqlx(flatten)] is not supported",
+            ));
+        }
+
+        let id = field.ident.as_ref().expect("named field");
+
+        let name = attributes
+            .rename
+            .clone()
+            .unwrap_or_else(|| id.to_string().trim_start_matches("r#").to_owned());
+        let name = match container_attributes.rename_all {
+            Some(pattern) => super::rename_all(&name, pattern),
+            None => name,
+        };
+
+        columns.push(Column {
+            field,
+            attributes,
+            name,
+        });
+    }
+
+    let (keys, values): (Vec<&Column>, Vec<&Column>) = columns
+        .iter()
+        .partition(|column| column.attributes.primary_key);
+
+    if keys.is_empty() {
+        return Err(syn::Error::new_spanned(
+            input,
+            "expected at least one field marked with #[sqlx(primary_key)]",
+        ));
+    }
+
+    // a table made only of its key has nothing else to update, so `update()` sets the key to
+    // itself and `upsert()` leaves the existing row unchanged
+    let assigned = if values.is_empty() { &keys } else { &values };
+
+    let lifetime = Lifetime::new("'q", Span::call_site());
+
+    let generics = &input.generics;
+    let (_, ty_generics, _) = generics.split_for_impl();
+
+    let mut generics = generics.clone();
+    generics
+        .params
+        .insert(0, parse_quote!(DB: ::sqlx::Database));
+    generics.params.insert(0, parse_quote!(#lifetime));
+
+    let predicates = &mut generics.make_where_clause().predicates;
+
+    predicates.push(parse_quote!(DB: ::sqlx::InsertDialect));
+    predicates.push(parse_quote!(
+        <DB as ::sqlx::database::HasArguments<#lifetime>>::Arguments: ::std::default::Default
+    ));
+
+    for column in &columns {
+        let ty = &column.field.ty;
+
+        predicates.push(parse_quote!(#ty: #lifetime + ::std::marker::Sync));
+
+        if column.attributes.json {
+            predicates.push(parse_quote!(
+                ::sqlx::types::Json<&#lifetime #ty>: ::sqlx::encode::Encode<#lifetime, DB>
+                    + ::sqlx::types::Type<DB>
+            ));
+        } else {
+            predicates.push(parse_quote!(
+                #ty: ::sqlx::encode::Encode<#lifetime, DB> + ::sqlx::types::Type<DB>
+            ));
+        }
+    }
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let names: Vec<&str> = columns.iter().map(|column| column.name.as_str()).collect();
+    let key_names: Vec<&str> = keys.iter().map(|key| key.name.as_str()).collect();
+    let value_names: Vec<&str> = values.iter().map(|column| column.name.as_str()).collect();
+
+    let push_values: Vec<Expr> = columns
+        .iter()
+        .map(|column| column.bind(quote!(record)))
+        .collect();
+
+    let mut update: Vec<Stmt> = Vec::new();
+
+    for (i, column) in assigned.iter().enumerate() {
+        let name = &column.name;
+        let separator = if i == 0 { "" } else { ", " };
+        let bind = column.bind(quote!(self));
+
+        update.push(parse_quote!(query.push(::std::format_args!(
+            "{}{} = ",
+            #separator,
+            <DB as ::sqlx::InsertDialect>::quote_identifier(#name),
+        ));));
+        update.push(parse_quote!(query.push_bind(#bind);));
+    }
+
+    for (i, key) in keys.iter().enumerate() {
+        let name = &key.name;
+        let keyword = if i == 0 { " WHERE" } else { " AND" };
+        let bind = key.bind(quote!(self));
+
+        update.push(parse_quote!(query.push(::std::format_args!(
+            "{} {} = ",
+            #keyword,
+            <DB as ::sqlx::InsertDialect>::quote_identifier(#name),
+        ));));
+        update.push(parse_quote!(query.push_bind(#bind);));
+    }
+
+    Ok(quote!(
+        #[automatically_derived]
+        impl #impl_generics ::sqlx::Insertable<#lifetime, DB> for #ident #ty_generics #where_clause {
+            const TABLE: &'static ::std::primitive::str = #table;
+
+            fn insert_all<I>(rows: I) -> ::sqlx::QueryBuilder<#lifetime, DB>
+            where
+                I: ::std::iter::IntoIterator<Item = &#lifetime Self>,
+            {
+                let mut query = ::sqlx::QueryBuilder::new(
+                    <DB as ::sqlx::InsertDialect>::insert_sql(#table, &[#(#names),*]),
+                );
+
+                query.push_values(rows, |mut row, record| {
+                    #(row.push_bind(#push_values);)*
+                });
+
+                query
+            }
+
+            fn update(&#lifetime self) -> ::sqlx::QueryBuilder<#lifetime, DB> {
+                let mut query = ::sqlx::QueryBuilder::new(::std::format!(
+                    "UPDATE {} SET ",
+                    <DB as ::sqlx::InsertDialect>::quote_identifier(#table),
+                ));
+
+                #(#update)*
+
+                query
+            }
+
+            fn upsert(&#lifetime self) -> ::sqlx::QueryBuilder<#lifetime, DB> {
+                let (insert, on_conflict) = <DB as ::sqlx::InsertDialect>::upsert_sql(
+                    #table,
+                    &[#(#names),*],
+                    &[#(#key_names),*],
+                    &[#(#value_names),*],
+                );
+
+                let mut query = ::sqlx::QueryBuilder::new(insert);
+
+                query.push_values(::std::iter::once(self), |mut row, record| {
+                    #(row.push_bind(#push_values);)*
+                });
+                query.push(on_conflict);
+
+                query
+            }
+        }
+    ))
+}
@@ -1,12 +1,15 @@
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::{
-    parse_quote, punctuated::Punctuated, token::Comma, Data, DataStruct, DeriveInput, Expr, Field,
-    Fields, FieldsNamed, FieldsUnnamed, Lifetime, Stmt,
+    parse_quote, punctuated::Punctuated, token::Comma, Data, DataEnum, DataStruct, DeriveInput,
+    Expr, Field, Fields, FieldsNamed, FieldsUnnamed, Lifetime, Stmt, Type, Variant, WherePredicate,
 };
 
 use super::{
-    attributes::{parse_child_attributes, parse_container_attributes},
+    attributes::{
+        parse_child_attributes, parse_container_attributes, RenameAll, SqlxChildAttributes,
+        SqlxContainerAttributes,
+    },
     rename_all,
 };
 
@@ -30,7 +33,7 @@ pub fn expand_derive_from_row(input: &DeriveInput) -> syn::Result<TokenStream> {
             "unit structs are not supported",
         )),
 
-        Data::Enum(_) => Err(syn::Error::new_spanned(input, "enums are not supported")),
+        Data::Enum(DataEnum { variants, .. }) => expand_derive_from_row_enum(input, variants),
 
         Data::Union(_) => Err(syn::Error::new_spanned(input, "unions are not supported")),
     }
@@ -65,72 +68,62 @@ fn expand_derive_from_row_struct(
 
     let container_attributes = parse_container_attributes(&input.attrs)?;
 
+    if container_attributes.tag.is_some() || container_attributes.tag_type.is_some() {
+        return Err(syn::Error::new_spanned(
+            input,
+            "#[sqlx(tag = ..)] is only supported on enums",
+        ));
+    }
+
+    let fields = fields
+        .iter()
+        .map(|field| {
+            let attributes = parse_child_attributes(&field.attrs)?;
+            check_primary_key(&container_attributes, &attributes, field)?;
+            Ok((field, attributes))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    if fields
+        .iter()
+        .any(|(_, attributes)| attributes.prefix.is_some())
+    {
+        predicates.push(parse_quote!(::std::primitive::usize: ::sqlx::ColumnIndex<R>));
+    }
+
+    for (field, attributes) in &fields {
+        push_field_predicates(predicates, field, attributes, &lifetime);
+    }
+
     let reads: Vec<Stmt> = fields
         .iter()
-        .filter_map(|field| -> Option<Stmt> {
-            let id = &field.ident.as_ref()?;
-            let attributes = parse_child_attributes(&field.attrs).unwrap();
-            let ty = &field.ty;
-
-            let expr: Expr = match (attributes.flatten, attributes.try_from) {
-                (true, None) => {
-                    predicates.push(parse_quote!(#ty: ::sqlx::FromRow<#lifetime, R>));
-                    parse_quote!(<#ty as ::sqlx::FromRow<#lifetime, R>>::from_row(row))
-                }
-                (false, None) => {
-                    predicates
-                        .push(parse_quote!(#ty: ::sqlx::decode::Decode<#lifetime, R::Database>));
-                    predicates.push(parse_quote!(#ty: ::sqlx::types::Type<R::Database>));
-
-                    let id_s = attributes
-                        .rename
-                        .or_else(|| Some(id.to_string().trim_start_matches("r#").to_owned()))
-                        .map(|s| match container_attributes.rename_all {
-                            Some(pattern) => rename_all(&s, pattern),
-                            None => s,
-                        })
-                        .unwrap();
-                    parse_quote!(row.try_get(#id_s))
-                }
-                (true,Some(try_from)) => {
-                    predicates.push(parse_quote!(#try_from: ::sqlx::FromRow<#lifetime, R>));
-                    parse_quote!(<#try_from as ::sqlx::FromRow<#lifetime, R>>::from_row(row).and_then(|v| <#ty as ::std::convert::TryFrom::<#try_from>>::try_from(v).map_err(|e| ::sqlx::Error::ColumnNotFound("FromRow: try_from failed".to_string())))) 
-                }
-                (false,Some(try_from)) => {
-                    predicates
-                        .push(parse_quote!(#try_from: ::sqlx::decode::Decode<#lifetime, R::Database>));
-                    predicates.push(parse_quote!(#try_from: ::sqlx::types::Type<R::Database>)); 
-
-                    let id_s = attributes
-                        .rename
-                        .or_else(|| Some(id.to_string().trim_start_matches("r#").to_owned()))
-                        .map(|s| match container_attributes.rename_all {
-                            Some(pattern) => rename_all(&s, pattern),
-                            None => s,
-                        })
-                        .unwrap();
-                    parse_quote!(row.try_get(#id_s).and_then(|v| <#ty as ::std::convert::TryFrom::<#try_from>>::try_from(v).map_err(|e| ::sqlx::Error::ColumnNotFound("FromRow: try_from failed".to_string()))))
-                }
-            };
-
-            if attributes.default {
-                Some(parse_quote!(let #id: #ty = #expr.or_else(|e| match e {
-                ::sqlx::Error::ColumnNotFound(_) => {
-                    ::std::result::Result::Ok(Default::default())
-                },
-                e => ::std::result::Result::Err(e)
-            })?;))
-            } else {
-                Some(parse_quote!(
-                    let #id: #ty = #expr?;
-                ))
-            }
+        .filter_map(|(field, attributes)| {
+            read_field(
+                field,
+                attributes,
+                container_attributes.rename_all,
+                &lifetime,
+                false,
+            )
+        })
+        .collect();
+
+    let prefixed_reads: Vec<Stmt> = fields
+        .iter()
+        .filter_map(|(field, attributes)| {
+            read_field(
+                field,
+                attributes,
+                container_attributes.rename_all,
+                &lifetime,
+                true,
+            )
         })
         .collect();
 
     let (impl_generics, _, where_clause) = generics.split_for_impl();
 
-    let names = fields.iter().map(|field| &field.ident);
+    let names: Vec<_> = fields.iter().map(|(field, _)| &field.ident).collect();
 
     Ok(quote!(
         #[automatically_derived]
@@ -142,10 +135,391 @@ fn expand_derive_from_row_struct(
                     #(#names),*
                 })
             }
+
+            fn from_row_prefixed(
+                row: &#lifetime R,
+                prefix: &::std::primitive::str,
+            ) -> ::sqlx::Result<Self>
+            where
+                ::std::primitive::usize: ::sqlx::ColumnIndex<R>,
+            {
+                #(#prefixed_reads)*
+
+                ::std::result::Result::Ok(#ident {
+                    #(#names),*
+                })
+            }
         }
     ))
 }
 
+fn expand_derive_from_row_enum(
+    input: &DeriveInput,
+    variants: &Punctuated<Variant, Comma>,
+) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+
+    let generics = &input.generics;
+
+    let (lifetime, provided) = generics
+        .lifetimes()
+        .next()
+        .map(|def| (def.lifetime.clone(), false))
+        .unwrap_or_else(|| (Lifetime::new("'a", Span::call_site()), true));
+
+    let (_, ty_generics, _) = generics.split_for_impl();
+
+    let mut generics = generics.clone();
+    generics.params.insert(0, parse_quote!(R: ::sqlx::Row));
+
+    if provided {
+        generics.params.insert(0, parse_quote!(#lifetime));
+    }
+
+    let predicates = &mut generics.make_where_clause().predicates;
+
+    let container_attributes = parse_container_attributes(&input.attrs)?;
+
+    let tag = container_attributes.tag.as_ref().ok_or_else(|| {
+        syn::Error::new_spanned(
+            input,
+            "expected #[sqlx(tag = \"..\")] naming the column which selects the variant",
+        )
+    })?;
+
+    // without a `tag_type`, the tag is the (renamed) name of the variant
+    let tag_type: Type = match &container_attributes.tag_type {
+        Some(tag_type) => parse_quote!(#tag_type),
+        None => parse_quote!(::std::string::String),
+    };
+
+    predicates.push(parse_quote!(&#lifetime ::std::primitive::str: ::sqlx::ColumnIndex<R>));
+    predicates.push(parse_quote!(#tag_type: ::sqlx::decode::Decode<#lifetime, R::Database>));
+    predicates.push(parse_quote!(#tag_type: ::sqlx::types::Type<R::Database>));
+
+    let mut arms = Vec::new();
+    let mut prefixed_arms = Vec::new();
+
+    for variant in variants {
+        let attributes = parse_child_attributes(&variant.attrs)?;
+
+        if attributes.default
+            || attributes.flatten
+            || attributes.try_from.is_some()
+            || attributes.skip
+            || attributes.json
+            || attributes.primary_key
+        {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "only #[sqlx(rename = ..)] is supported on variants",
+            ));
+        }
+
+        let variant_ident = &variant.ident;
+
+        let tag_value = match &container_attributes.tag_type {
+            Some(tag_type) => {
+                if attributes.rename.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        variant,
+                        "#[sqlx(rename = ..)] has no effect with #[sqlx(tag_type = ..)]; \
+                         rename the variant of the tag type instead",
+                    ));
+                }
+
+                quote!(#tag_type::#variant_ident)
+            }
+            None => {
+                let tag_value = match (attributes.rename, container_attributes.rename_all) {
+                    (Some(rename), _) => rename,
+                    (None, Some(pattern)) => rename_all(&variant_ident.to_string(), pattern),
+                    (None, None) => variant_ident.to_string(),
+                };
+
+                quote!(#tag_value)
+            }
+        };
+
+        match &variant.fields {
+            Fields::Named(FieldsNamed { named, .. }) => {
+                let fields = named
+                    .iter()
+                    .map(|field| {
+                        let attributes = parse_child_attributes(&field.attrs)?;
+                        check_primary_key(&container_attributes, &attributes, field)?;
+                        Ok((field, attributes))
+                    })
+                    .collect::<syn::Result<Vec<_>>>()?;
+
+                if fields
+                    .iter()
+                    .any(|(_, attributes)| attributes.prefix.is_some())
+                {
+                    predicates.push(parse_quote!(::std::primitive::usize: ::sqlx::ColumnIndex<R>));
+                }
+
+                for (field, attributes) in &fields {
+                    push_field_predicates(predicates, field, attributes, &lifetime);
+                }
+
+                let names: Vec<_> = fields.iter().map(|(field, _)| &field.ident).collect();
+
+                for (arms, prefixed) in [(&mut arms, false), (&mut prefixed_arms, true)] {
+                    let reads = fields.iter().filter_map(|(field, attributes)| {
+                        read_field(
+                            field,
+                            attributes,
+                            container_attributes.rename_all,
+                            &lifetime,
+                            prefixed,
+                        )
+                    });
+
+                    arms.push(quote!(
+                        #tag_value => {
+                            #(#reads)*
+
+                            ::std::result::Result::Ok(#ident::#variant_ident {
+                                #(#names),*
+                            })
+                        }
+                    ));
+                }
+            }
+
+            Fields::Unnamed(FieldsUnnamed { unnamed, .. }) if unnamed.len() == 1 => {
+                // the fields of the variant are read by the `FromRow` implementation of its type
+                let ty = &unnamed[0].ty;
+
+                predicates.push(parse_quote!(#ty: ::sqlx::FromRow<#lifetime, R>));
+
+                arms.push(quote!(
+                    #tag_value => ::std::result::Result::Ok(#ident::#variant_ident(
+                        <#ty as ::sqlx::FromRow<#lifetime, R>>::from_row(row)?,
+                    )),
+                ));
+                prefixed_arms.push(quote!(
+                    #tag_value => ::std::result::Result::Ok(#ident::#variant_ident(
+                        <#ty as ::sqlx::FromRow<#lifetime, R>>::from_row_prefixed(row, prefix)?,
+                    )),
+                ));
+            }
+
+            Fields::Unnamed(_) => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "tuple variants must have exactly one field implementing `FromRow`",
+                ))
+            }
+
+            Fields::Unit => {
+                let arm = quote!(
+                    #tag_value => ::std::result::Result::Ok(#ident::#variant_ident),
+                );
+
+                arms.push(arm.clone());
+                prefixed_arms.push(arm);
+            }
+        }
+    }
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    // a `tag_type` has a variant for each variant of the enum, so the match is exhaustive
+    let (tag_expr, unknown_tag) = match &container_attributes.tag_type {
+        Some(_) => (quote!(tag), quote!()),
+        None => (
+            quote!(tag.as_str()),
+            quote!(
+                _ => ::std::result::Result::Err(::sqlx::Error::ColumnDecode {
+                    index: ::std::format!("{:?}", #tag),
+                    source: ::std::format!(
+                        "unknown tag {:?} for `{}`",
+                        tag,
+                        ::std::stringify!(#ident),
+                    )
+                    .into(),
+                }),
+            ),
+        ),
+    };
+
+    Ok(quote!(
+        #[automatically_derived]
+        impl #impl_generics ::sqlx::FromRow<#lifetime, R> for #ident #ty_generics #where_clause {
+            fn from_row(row: &#lifetime R) -> ::sqlx::Result<Self> {
+                let tag: #tag_type = row.try_get(#tag)?;
+
+                match #tag_expr {
+                    #(#arms)*
+                    #unknown_tag
+                }
+            }
+
+            fn from_row_prefixed(
+                row: &#lifetime R,
+                prefix: &::std::primitive::str,
+            ) -> ::sqlx::Result<Self>
+            where
+                ::std::primitive::usize: ::sqlx::ColumnIndex<R>,
+            {
+                let tag: #tag_type = ::sqlx::__try_get_prefixed(row, prefix, #tag)?;
+
+                match #tag_expr {
+                    #(#prefixed_arms)*
+                    #unknown_tag
+                }
+            }
+        }
+    ))
+}
+
+/// `#[sqlx(primary_key)]` is only read by `Insertable`, which also needs `#[sqlx(table = ..)]`;
+/// on its own, it would be silently ignored.
+fn check_primary_key(
+    container_attributes: &SqlxContainerAttributes,
+    attributes: &SqlxChildAttributes,
+    field: &Field,
+) -> syn::Result<()> {
+    if attributes.primary_key && container_attributes.table.is_none() {
+        return Err(syn::Error::new_spanned(
+            field,
+            "#[sqlx(primary_key)] is only used by #[derive(Insertable)], \
+             which requires #[sqlx(table = ..)]",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Add the bounds required to read a field.
+fn push_field_predicates(
+    predicates: &mut Punctuated<WherePredicate, Comma>,
+    field: &Field,
+    attributes: &SqlxChildAttributes,
+    lifetime: &Lifetime,
+) {
+    let ty = &field.ty;
+
+    if attributes.skip {
+        return;
+    }
+
+    match (attributes.flatten, &attributes.try_from) {
+        (true, None) => {
+            predicates.push(parse_quote!(#ty: ::sqlx::FromRow<#lifetime, R>));
+        }
+        (false, None) if attributes.json => {
+            predicates.push(parse_quote!(
+                ::sqlx::types::Json<#ty>: ::sqlx::decode::Decode<#lifetime, R::Database>
+            ));
+            predicates
+                .push(parse_quote!(::sqlx::types::Json<#ty>: ::sqlx::types::Type<R::Database>));
+        }
+        (false, None) => {
+            predicates.push(parse_quote!(#ty: ::sqlx::decode::Decode<#lifetime, R::Database>));
+            predicates.push(parse_quote!(#ty: ::sqlx::types::Type<R::Database>));
+        }
+        (true, Some(try_from)) => {
+            predicates.push(parse_quote!(#try_from: ::sqlx::FromRow<#lifetime, R>));
+        }
+        (false, Some(try_from)) => {
+            predicates
+                .push(parse_quote!(#try_from: ::sqlx::decode::Decode<#lifetime, R::Database>));
+            predicates.push(parse_quote!(#try_from: ::sqlx::types::Type<R::Database>));
+        }
+    }
+}
+
+/// Generate the statement reading the value of a field from `row`.
+///
+/// If `prefixed` is `true`, the column names are prefixed with the `prefix` argument of
+/// `FromRow::from_row_prefixed()`.
+fn read_field(
+    field: &Field,
+    attributes: &SqlxChildAttributes,
+    rename_all: Option<RenameAll>,
+    lifetime: &Lifetime,
+    prefixed: bool,
+) -> Option<Stmt> {
+    let id = field.ident.as_ref()?;
+    let ty = &field.ty;
+
+    if attributes.skip {
+        return Some(parse_quote!(
+            let #id: #ty = ::std::default::Default::default();
+        ));
+    }
+
+    let id_s = attributes
+        .rename
+        .clone()
+        .unwrap_or_else(|| id.to_string().trim_start_matches("r#").to_owned());
+    let id_s = match rename_all {
+        Some(pattern) => super::rename_all(&id_s, pattern),
+        None => id_s,
+    };
+
+    // `&str: ColumnIndex<R>` is only required for the lifetime of the row, so prefixed
+    // column names are looked up by index instead
+    let try_get = |ty: TokenStream| -> Expr {
+        if prefixed {
+            parse_quote!(::sqlx::__try_get_prefixed::<_, #ty>(row, prefix, #id_s))
+        } else {
+            parse_quote!(row.try_get::<#ty, _>(#id_s))
+        }
+    };
+
+    let from_row = |ty: &Type| -> Expr {
+        match (&attributes.prefix, prefixed) {
+            (None, false) => parse_quote!(<#ty as ::sqlx::FromRow<#lifetime, R>>::from_row(row)),
+            (None, true) => parse_quote!(
+                <#ty as ::sqlx::FromRow<#lifetime, R>>::from_row_prefixed(row, prefix)
+            ),
+            (Some(field_prefix), false) => parse_quote!(
+                <#ty as ::sqlx::FromRow<#lifetime, R>>::from_row_prefixed(row, #field_prefix)
+            ),
+            (Some(field_prefix), true) => parse_quote!(
+                <#ty as ::sqlx::FromRow<#lifetime, R>>::from_row_prefixed(
+                    row,
+                    &::std::format!("{}{}", prefix, #field_prefix),
+                )
+            ),
+        }
+    };
+
+    let expr: Expr = match (attributes.flatten, &attributes.try_from) {
+        (true, None) => from_row(ty),
+        (false, None) if attributes.json => {
+            let try_get = try_get(quote!(::sqlx::types::Json<#ty>));
+            parse_quote!(#try_get.map(|json| json.0))
+        }
+        (false, None) => try_get(quote!(#ty)),
+        (true, Some(try_from)) => {
+            let from_row = from_row(try_from);
+            parse_quote!(#from_row.and_then(|v| <#ty as ::std::convert::TryFrom::<#try_from>>::try_from(v).map_err(|e| ::sqlx::Error::ColumnNotFound("FromRow: try_from failed".to_string()))))
+        }
+        (false, Some(try_from)) => {
+            let try_get = try_get(quote!(#try_from));
+            parse_quote!(#try_get.and_then(|v| <#ty as ::std::convert::TryFrom::<#try_from>>::try_from(v).map_err(|e| ::sqlx::Error::ColumnNotFound("FromRow: try_from failed".to_string()))))
+        }
+    };
+
+    if attributes.default {
+        Some(parse_quote!(let #id: #ty = #expr.or_else(|e| match e {
+            ::sqlx::Error::ColumnNotFound(_) => {
+                ::std::result::Result::Ok(Default::default())
+            },
+            e => ::std::result::Result::Err(e)
+        })?;))
+    } else {
+        Some(parse_quote!(
+            let #id: #ty = #expr?;
+        ))
+    }
+}
+
 fn expand_derive_from_row_struct_unnamed(
     input: &DeriveInput,
     fields: &Punctuated<Field, Comma>,
@@ -189,6 +563,11 @@ fn expand_derive_from_row_struct_unnamed(
         .enumerate()
         .map(|(idx, _)| quote!(row.try_get(#idx)?));
 
+    let prefixed_gets = fields
+        .iter()
+        .enumerate()
+        .map(|(idx, _)| quote!(::sqlx::__try_get_prefixed_nth(row, prefix, #idx)?));
+
     Ok(quote!(
         #[automatically_derived]
         impl #impl_generics ::sqlx::FromRow<#lifetime, R> for #ident #ty_generics #where_clause {
@@ -197,6 +576,15 @@ fn expand_derive_from_row_struct_unnamed(
                     #(#gets),*
                 ))
             }
+
+            fn from_row_prefixed(
+                row: &#lifetime R,
+                prefix: &::std::primitive::str,
+            ) -> ::sqlx::Result<Self> {
+                ::std::result::Result::Ok(#ident (
+                    #(#prefixed_gets),*
+                ))
+            }
         }
     ))
 }
@@ -1,9 +1,9 @@
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens, TokenStreamExt};
 use sha2::{Digest, Sha384};
-use sqlx_core::migrate::MigrationType;
+use sqlx_core::migrate::{MigrationType, Migrator};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use syn::LitStr;
 
 pub struct QuotedMigrationType(MigrationType);
@@ -61,6 +61,22 @@ pub fn expand_migrator_from_lit_dir(dir: LitStr) -> crate::Result<TokenStream> {
     expand_migrator_from_dir(&dir.value(), dir.span())
 }
 
+/// Expand `migrate!()` without a directory, using the one configured in `sqlx.toml`.
+pub fn expand_migrator_from_config() -> crate::Result<TokenStream> {
+    expand_migrator(&default_migrations_dir()?)
+}
+
+/// The directory configured in `sqlx.toml` as `migrate.migrations-dir`, or else `migrations`.
+pub(crate) fn default_migrations_dir() -> crate::Result<PathBuf> {
+    match &crate::query::config()?.migrate.migrations_dir {
+        Some(dir) => Ok(dir.clone()),
+        None => Ok(crate::common::resolve_path(
+            "./migrations",
+            proc_macro2::Span::call_site(),
+        )?),
+    }
+}
+
 pub(crate) fn expand_migrator_from_dir(
     dir: &str,
     err_span: proc_macro2::Span,
@@ -140,13 +156,24 @@ pub(crate) fn expand_migrator(path: &Path) -> crate::Result<TokenStream> {
         proc_macro::tracked_path::path(path);
     }
 
+    let table_name = crate::query::config()?
+        .migrate
+        .table_name
+        .as_deref()
+        .unwrap_or(Migrator::DEFAULT_TABLE_NAME);
+
+    let project_files = crate::query::quote_project_files()?;
+
     Ok(quote! {
-        ::sqlx::migrate::Migrator {
-            migrations: ::std::borrow::Cow::Borrowed(&[
-                #(#migrations),*
-            ]),
-            ignore_missing: false,
-            locking: true,
+        {
+            #project_files
+
+            ::sqlx::migrate::Migrator::__from_static(
+                &[
+                    #(#migrations),*
+                ],
+                #table_name,
+            )
         }
     })
 }
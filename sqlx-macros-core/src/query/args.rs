@@ -4,18 +4,22 @@ use either::Either;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote, quote_spanned};
 use sqlx_core::describe::Describe;
+use sqlx_core::type_info::TypeInfo;
 use syn::spanned::Spanned;
 use syn::{Expr, ExprCast, ExprGroup, ExprType, Type};
 
 /// Returns a tokenstream which typechecks the arguments passed to the macro
 /// and binds them to `DB::Arguments` with the ident `query_args`.
+///
+/// The guard of each optional clause is bound to `clause<N>`, and its value is only added to
+/// the arguments if it is `Some`.
 pub fn quote_args<DB: DatabaseExt>(
     input: &QueryMacroInput,
     info: &Describe<DB>,
 ) -> crate::Result<TokenStream> {
     let db_path = DB::db_path();
 
-    if input.arg_exprs.is_empty() {
+    if input.arg_exprs.is_empty() && input.optional_clauses.is_empty() {
         return Ok(quote! {
             let query_args = <#db_path as ::sqlx::database::HasArguments>::Arguments::default();
         });
@@ -28,10 +32,46 @@ pub fn quote_args<DB: DatabaseExt>(
     let arg_name = &arg_names;
     let arg_expr = input.arg_exprs.iter().cloned().map(strip_wildcard);
 
+    let clause_names = (0..input.optional_clauses.len())
+        .map(|i| format_ident!("clause{}", i))
+        .collect::<Vec<_>>();
+
+    let clause_name = &clause_names;
+    let clause_guard = input.optional_clauses.iter().map(|clause| &clause.guard);
+
     let arg_bindings = quote! {
         #(let #arg_name = &(#arg_expr);)*
+        #(let #clause_name = &(#clause_guard);)*
     };
 
+    // a named argument is bound to each parameter it is used for
+    let param_arg = input
+        .param_args
+        .iter()
+        .map(|&i| &arg_names[i])
+        .collect::<Vec<_>>();
+
+    // the value checked against each parameter, and the expression it came from
+    let checked_values = input
+        .param_args
+        .iter()
+        .map(|&i| {
+            let name = &arg_names[i];
+            (quote!(#name), &input.arg_exprs[i], true)
+        })
+        .chain(
+            clause_names
+                .iter()
+                .zip(&input.optional_clauses)
+                .map(|(name, clause)| {
+                    (
+                        quote!(::std::option::Option::as_ref(#name).unwrap()),
+                        &clause.guard,
+                        false,
+                    )
+                }),
+        );
+
     let args_check = match info.parameters() {
         None | Some(Either::Right(_)) => {
             // all we can do is check arity which we did
@@ -46,15 +86,19 @@ pub fn quote_args<DB: DatabaseExt>(
         Some(Either::Left(params)) => {
             params
                 .iter()
-                .zip(arg_names.iter().zip(&input.arg_exprs))
+                .zip(checked_values)
                 .enumerate()
-                .map(|(i, (param_ty, (name, expr)))| -> crate::Result<_> {
-                    let param_ty = match get_type_override(expr) {
+                .map(|(i, (param_ty, (name, expr, overridable)))| -> crate::Result<_> {
+                    let type_override = if overridable { get_type_override(expr) } else { None };
+
+                    let param_ty = match type_override {
                         // cast or type ascription will fail to compile if the type does not match
                         // and we strip casts to wildcard
                         Some(_) => return Ok(quote!()),
                         None => {
-                            DB::param_type_for_id(&param_ty)
+                            // a type mapped in `sqlx.toml` takes precedence over the default one
+                            crate::database::type_override::<DB>(param_ty.name())
+                                .or_else(|| DB::param_type_for_id(&param_ty))
                                 .ok_or_else(|| {
                                     if let Some(feature_gate) = <DB as DatabaseExt>::get_feature_gate(&param_ty) {
                                         format!(
@@ -98,7 +142,7 @@ pub fn quote_args<DB: DatabaseExt>(
         }
     };
 
-    let args_count = input.arg_exprs.len();
+    let args_count = input.param_args.len() + input.optional_clauses.len();
 
     Ok(quote! {
         #arg_bindings
@@ -108,9 +152,14 @@ pub fn quote_args<DB: DatabaseExt>(
         let mut query_args = <#db_path as ::sqlx::database::HasArguments>::Arguments::default();
         query_args.reserve(
             #args_count,
-            0 #(+ ::sqlx::encode::Encode::<#db_path>::size_hint(#arg_name))*
+            0 #(+ ::sqlx::encode::Encode::<#db_path>::size_hint(#param_arg))*
         );
-        #(query_args.add(#arg_name);)*
+        #(query_args.add(#param_arg);)*
+        #(
+            if let ::std::option::Option::Some(value) = ::std::option::Option::as_ref(#clause_name) {
+                query_args.add(value);
+            }
+        )*
     })
 }
 
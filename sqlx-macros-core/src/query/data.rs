@@ -19,7 +19,6 @@ use crate::database::DatabaseExt;
 #[derive(Debug)]
 pub struct QueryData<DB: Database> {
     db_name: SerializeDbName<DB>,
-    #[allow(dead_code)]
     pub(super) query: String,
     pub(super) describe: Describe<DB>,
     pub(super) hash: String,
@@ -1,10 +1,14 @@
+use std::fmt::Write;
 use std::fs;
 
 use proc_macro2::{Ident, Span};
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
-use syn::{Expr, LitBool, LitStr, Token};
-use syn::{ExprArray, Type};
+use syn::Type;
+use syn::{bracketed, Expr, LitBool, LitStr, Token};
+
+/// The maximum number of optional clauses in a query, as every combination of them is checked.
+const MAX_OPTIONAL_CLAUSES: usize = 6;
 
 /// Macro input shared by `query!()` and `query_file!()`
 pub struct QueryMacroInput {
@@ -16,11 +20,32 @@ pub struct QueryMacroInput {
 
     pub(super) arg_exprs: Vec<Expr>,
 
+    /// The names of the arguments, if they were given as `name = expr`.
+    pub(super) arg_names: Vec<Ident>,
+
+    /// The index of the argument bound to each parameter of the query.
+    pub(super) param_args: Vec<usize>,
+
+    pub(super) optional_clauses: Vec<OptionalClause>,
+
     pub(super) checked: bool,
 
+    /// The name of the database configured in `sqlx.toml` to check the query against,
+    /// instead of the one at `DATABASE_URL`.
+    pub(super) database: Option<String>,
+
     pub(super) file_path: Option<String>,
 }
 
+/// A clause appended to the query when `guard` evaluates to `Some`, binding the value inside.
+///
+/// Written as `"AND status = $2" if status` after the other arguments.
+pub struct OptionalClause {
+    pub(super) sql: String,
+
+    pub(super) guard: Expr,
+}
+
 enum QuerySrc {
     String(String),
     File(String),
@@ -35,9 +60,10 @@ pub enum RecordType {
 impl Parse for QueryMacroInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut query_src: Option<(QuerySrc, Span)> = None;
-        let mut args: Option<Vec<Expr>> = None;
+        let mut args: Option<(Vec<Expr>, Vec<Ident>, Vec<OptionalClause>)> = None;
         let mut record_type = RecordType::Generated;
         let mut checked = true;
+        let mut database = None;
 
         let mut expect_comma = false;
 
@@ -61,8 +87,7 @@ impl Parse for QueryMacroInput {
                 let lit_str = input.parse::<LitStr>()?;
                 query_src = Some((QuerySrc::File(lit_str.value()), lit_str.span()));
             } else if key == "args" {
-                let exprs = input.parse::<ExprArray>()?;
-                args = Some(exprs.elems.into_iter().collect())
+                args = Some(parse_args(input)?);
             } else if key == "record" {
                 if !matches!(record_type, RecordType::Generated) {
                     return Err(input.error("colliding `scalar` or `record` key"));
@@ -82,6 +107,8 @@ impl Parse for QueryMacroInput {
             } else if key == "checked" {
                 let lit_bool = input.parse::<LitBool>()?;
                 checked = lit_bool.value;
+            } else if key == "db" {
+                database = Some(input.parse::<LitStr>()?.value());
             } else {
                 let message = format!("unexpected input key: {}", key);
                 return Err(syn::Error::new_spanned(key, message));
@@ -93,7 +120,18 @@ impl Parse for QueryMacroInput {
         let (src, src_span) =
             query_src.ok_or_else(|| input.error("expected `source` or `source_file` key"))?;
 
-        let arg_exprs = args.unwrap_or_default();
+        let (arg_exprs, arg_names, optional_clauses) = args.unwrap_or_default();
+
+        if optional_clauses.len() > MAX_OPTIONAL_CLAUSES {
+            return Err(syn::Error::new(
+                src_span,
+                format!(
+                    "expected at most {} optional clauses, got {}",
+                    MAX_OPTIONAL_CLAUSES,
+                    optional_clauses.len()
+                ),
+            ));
+        }
 
         let file_path = src.file_path(src_span)?;
 
@@ -101,13 +139,366 @@ impl Parse for QueryMacroInput {
             sql: src.resolve(src_span)?,
             src_span,
             record_type,
+            param_args: (0..arg_exprs.len()).collect(),
             arg_exprs,
+            arg_names,
+            optional_clauses,
             checked,
+            database,
             file_path,
         })
     }
 }
 
+impl QueryMacroInput {
+    /// The SQL of every combination of the optional clauses, indexed by the bitmask of the
+    /// clauses included. The last one includes every clause.
+    ///
+    /// Clauses are appended in order, separated by a space. The `$N` parameters in each are
+    /// numbered as in the query with every clause, and renumbered for the other combinations.
+    ///
+    /// `backslash_escapes` is [`DatabaseExt::BACKSLASH_ESCAPES`][crate::database::DatabaseExt].
+    pub(super) fn sql_variants(&self, backslash_escapes: bool) -> syn::Result<Vec<String>> {
+        self.variants_of(&self.sql, backslash_escapes)
+    }
+
+    /// Rewrite the named parameters of the query into `$N` parameters if `numbered`, or else
+    /// `?`, returning the SQL to execute for each of the [`sql_variants()`][Self::sql_variants].
+    ///
+    /// The variants keep the named parameters, so the query data saved for them does not depend
+    /// on the database.
+    pub(super) fn resolve_named_params(
+        &mut self,
+        numbered: bool,
+        backslash_escapes: bool,
+    ) -> syn::Result<Vec<String>> {
+        self.check_clause_params(numbered, backslash_escapes)?;
+
+        if self.arg_names.is_empty() {
+            return self.sql_variants(backslash_escapes);
+        }
+
+        let (sql, param_args) =
+            rewrite_named_params(&self.sql, &self.arg_names, numbered, backslash_escapes)
+                .map_err(|message| syn::Error::new(self.src_span, message))?;
+
+        self.param_args = param_args;
+
+        self.variants_of(&sql, backslash_escapes)
+    }
+
+    /// Whether the SQL executed differs from the source of the query.
+    pub(super) fn rewrites_sql(&self) -> bool {
+        !self.optional_clauses.is_empty() || !self.arg_names.is_empty()
+    }
+
+    /// `?` parameters can't be renumbered, so clauses must not use `$N` with them.
+    fn check_clause_params(&self, numbered: bool, backslash_escapes: bool) -> syn::Result<()> {
+        if numbered {
+            return Ok(());
+        }
+
+        for clause in &self.optional_clauses {
+            map_params(&clause.sql, backslash_escapes, |_| {
+                Err(format!(
+                    "optional clause {:?} must use `?` for its parameter on this database",
+                    clause.sql
+                ))
+            })
+            .map_err(|message| syn::Error::new(self.src_span, message))?;
+        }
+
+        Ok(())
+    }
+
+    fn variants_of(&self, base_sql: &str, backslash_escapes: bool) -> syn::Result<Vec<String>> {
+        // the parameters of the query before the clauses, which every clause may use
+        let num_query_params = self.arg_exprs.len();
+
+        (0..1usize << self.optional_clauses.len())
+            .map(|mask| {
+                let mut sql = base_sql.to_owned();
+                let mut num_params = num_query_params;
+
+                for (i, clause) in self.optional_clauses.iter().enumerate() {
+                    if mask & (1 << i) == 0 {
+                        continue;
+                    }
+
+                    num_params += 1;
+
+                    // the number of the parameter of the clause in the query with every clause
+                    let own = num_query_params + i + 1;
+
+                    let clause_sql = map_params(&clause.sql, backslash_escapes, |n| {
+                        if n <= num_query_params {
+                            Ok(n)
+                        } else if n == own {
+                            Ok(num_params)
+                        } else {
+                            Err(format!(
+                                "optional clause {:?} can use its own parameter, ${}, and those \
+                                 of the query before it, but not ${}",
+                                clause.sql, own, n
+                            ))
+                        }
+                    })
+                    .map_err(|message| syn::Error::new(self.src_span, message))?;
+
+                    sql.push(' ');
+                    sql.push_str(&clause_sql);
+                }
+
+                Ok(sql)
+            })
+            .collect()
+    }
+}
+
+/// Parse the arguments of the query, which are either all positional or all named,
+/// followed by its optional clauses.
+fn parse_args(input: ParseStream) -> syn::Result<(Vec<Expr>, Vec<Ident>, Vec<OptionalClause>)> {
+    let content;
+    bracketed!(content in input);
+
+    let mut arg_exprs = Vec::new();
+    let mut arg_names: Vec<Ident> = Vec::new();
+    let mut optional_clauses = Vec::new();
+
+    while !content.is_empty() {
+        if content.peek(LitStr) && content.peek2(Token![if]) {
+            let sql = content.parse::<LitStr>()?.value();
+            content.parse::<Token![if]>()?;
+
+            optional_clauses.push(OptionalClause {
+                sql,
+                guard: content.parse()?,
+            });
+        } else if optional_clauses.is_empty() {
+            let named =
+                content.peek(syn::Ident) && content.peek2(Token![=]) && !content.peek2(Token![==]);
+
+            // either every argument is named, or none of them
+            if !arg_exprs.is_empty() && named == arg_names.is_empty() {
+                return Err(content.error("cannot mix named and positional arguments"));
+            }
+
+            if named {
+                let name: Ident = content.parse()?;
+                content.parse::<Token![=]>()?;
+
+                if arg_names.contains(&name) {
+                    return Err(syn::Error::new_spanned(name, "duplicate argument name"));
+                }
+
+                arg_names.push(name);
+            }
+
+            arg_exprs.push(content.parse()?);
+        } else {
+            return Err(content.error("arguments must come before optional clauses"));
+        }
+
+        if !content.is_empty() {
+            content.parse::<Token![,]>()?;
+        }
+    }
+
+    Ok((arg_exprs, arg_names, optional_clauses))
+}
+
+/// Rewrite the `:name` parameters in `sql` into `$N` parameters if `numbered`, or else `?`,
+/// returning the index in `names` of the argument bound to each parameter of the rewritten
+/// query.
+///
+/// Quoted strings and identifiers, comments, dollar-quoted strings, `::` casts and array slices
+/// like `a[1:n]` are left as they are. In strings, `\` escapes the next character if
+/// `backslash_escapes`, or else only in `E'..'` strings.
+fn rewrite_named_params(
+    sql: &str,
+    names: &[Ident],
+    numbered: bool,
+    backslash_escapes: bool,
+) -> Result<(String, Vec<usize>), String> {
+    let mut rewritten = String::with_capacity(sql.len());
+    let mut param_args = Vec::new();
+    let mut used = vec![false; names.len()];
+    // whether each open bracket is a subscript, where `:` separates the bounds of a slice,
+    // rather than e.g. an `ARRAY[..]` constructor
+    let mut brackets = Vec::new();
+    let mut rest = sql;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(len) = skipped_len(rest, rewritten.chars().next_back(), backslash_escapes) {
+            rewritten.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+
+        rest = &rest[c.len_utf8()..];
+
+        match c {
+            '[' => brackets.push(is_subscript(&rewritten)),
+            ']' => {
+                brackets.pop();
+            }
+            ':' if rest.starts_with(':') => {
+                rest = &rest[1..];
+                rewritten.push_str("::");
+                continue;
+            }
+            ':' if brackets.last() != Some(&true)
+                && rest.starts_with(|c: char| c.is_alphabetic() || c == '_') =>
+            {
+                let len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                let name = &rest[..len];
+                rest = &rest[len..];
+
+                let i = names
+                    .iter()
+                    .position(|arg| *arg == name)
+                    .ok_or_else(|| format!("no argument named `{}`", name))?;
+
+                used[i] = true;
+
+                if numbered {
+                    write!(rewritten, "${}", i + 1).unwrap();
+                } else {
+                    rewritten.push('?');
+                    param_args.push(i);
+                }
+
+                continue;
+            }
+            _ => {}
+        }
+
+        rewritten.push(c);
+    }
+
+    if let Some(i) = used.iter().position(|used| !used) {
+        return Err(format!("argument `{}` is not used in the query", names[i]));
+    }
+
+    if numbered {
+        param_args = (0..names.len()).collect();
+    }
+
+    Ok((rewritten, param_args))
+}
+
+/// Whether a `[` following `sql` opens a subscript, i.e. it directly follows an identifier, `)`
+/// or `]`, but not the `ARRAY` keyword.
+fn is_subscript(sql: &str) -> bool {
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_' || c == '"';
+
+    match sql.chars().next_back() {
+        Some(')' | ']') => true,
+        Some(c) if is_ident_char(c) => {
+            let start = sql.rfind(|c: char| !is_ident_char(c)).map_or(0, |i| i + 1);
+
+            !sql[start..].eq_ignore_ascii_case("array")
+        }
+        _ => false,
+    }
+}
+
+/// The length of the quoted string or identifier, comment, or dollar-quoted string at the start
+/// of `sql`, which follows the character `prev`, if there is one. Unterminated ones extend to
+/// the end of `sql`.
+///
+/// `\` escapes the next character of strings if `backslash_escapes`, and of `E'..'` strings.
+fn skipped_len(sql: &str, prev: Option<char>, backslash_escapes: bool) -> Option<usize> {
+    let until = |start: usize, end: &str| {
+        sql[start..]
+            .find(end)
+            .map_or(sql.len(), |i| start + i + end.len())
+    };
+
+    // the length of the string quoted with `q` at `start`
+    let quoted = |start: usize, q: u8, escapes: bool| {
+        let bytes = sql.as_bytes();
+        let mut i = start + 1;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' if escapes => i += 2,
+                b if b == q => return i + 1,
+                _ => i += 1,
+            }
+        }
+
+        sql.len()
+    };
+
+    match sql.chars().next()? {
+        q @ ('\'' | '"') => Some(quoted(0, q as u8, backslash_escapes)),
+        '`' => Some(until(1, "`")),
+        // the `E` doesn't end an identifier or keyword
+        'E' | 'e'
+            if sql[1..].starts_with('\'')
+                && !prev.is_some_and(|c| c.is_alphanumeric() || c == '_') =>
+        {
+            Some(quoted(1, b'\'', true))
+        }
+        '-' if sql.starts_with("--") => Some(until(2, "\n")),
+        '/' if sql.starts_with("/*") => Some(until(2, "*/")),
+        '$' => {
+            // `$tag$ .. $tag$`, where the tag may be empty, but is not a `$N` parameter
+            let tag_len = sql[1..].find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+
+            if !sql[tag_len + 1..].starts_with('$')
+                || sql[1..].starts_with(|c: char| c.is_ascii_digit())
+            {
+                return None;
+            }
+
+            let tag = &sql[..tag_len + 2];
+
+            Some(until(tag.len(), tag))
+        }
+        _ => None,
+    }
+}
+
+/// Replace the number of every `$N` parameter in `sql` with `map(N)`, outside of the contexts
+/// skipped by [`rewrite_named_params()`].
+fn map_params(
+    sql: &str,
+    backslash_escapes: bool,
+    mut map: impl FnMut(usize) -> Result<usize, String>,
+) -> Result<String, String> {
+    let mut mapped = String::with_capacity(sql.len());
+    let mut rest = sql;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(len) = skipped_len(rest, mapped.chars().next_back(), backslash_escapes) {
+            mapped.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+
+        mapped.push(c);
+        rest = &rest[c.len_utf8()..];
+
+        if c == '$' {
+            let len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+
+            if len > 0 {
+                let n = rest[..len].parse::<usize>().map_err(|e| e.to_string())?;
+                write!(mapped, "{}", map(n)?).unwrap();
+                rest = &rest[len..];
+            }
+        }
+    }
+
+    Ok(mapped)
+}
+
 impl QuerySrc {
     /// If the query source is a file, read it to a string. Otherwise return the query string.
     fn resolve(self, source_span: Span) -> syn::Result<String> {
@@ -153,3 +544,127 @@ fn read_file_src(source: &str, source_span: Span) -> syn::Result<String> {
         )
     })
 }
+
+#[test]
+fn test_sql_variants() {
+    let input: QueryMacroInput = syn::parse_str(
+        r#"source = "SELECT * FROM tasks WHERE owner_id = $1",
+           args = [owner_id, "AND status = $2" if status, "AND (due < $3 OR owner_id = $1)" if due]"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        input.sql_variants(false).unwrap(),
+        [
+            "SELECT * FROM tasks WHERE owner_id = $1",
+            "SELECT * FROM tasks WHERE owner_id = $1 AND status = $2",
+            "SELECT * FROM tasks WHERE owner_id = $1 AND (due < $2 OR owner_id = $1)",
+            "SELECT * FROM tasks WHERE owner_id = $1 AND status = $2 AND (due < $3 OR owner_id = $1)",
+        ]
+    );
+
+    // the parameter of a clause is numbered as in the query with every clause
+    let input: QueryMacroInput = syn::parse_str(
+        r#"source = "SELECT * FROM tasks", args = ["WHERE owner_id = $1" if owner, "AND status = $1" if status]"#,
+    )
+    .unwrap();
+
+    assert!(input.sql_variants(false).is_err());
+}
+
+#[test]
+fn test_clause_params_on_positional_databases() {
+    let mut input: QueryMacroInput = syn::parse_str(
+        r#"source = "SELECT * FROM tasks WHERE true", args = ["AND status = ?" if status, "AND note = '$1'" if note]"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        input.resolve_named_params(false, false).unwrap()[3],
+        "SELECT * FROM tasks WHERE true AND status = ? AND note = '$1'"
+    );
+
+    let mut input: QueryMacroInput = syn::parse_str(
+        r#"source = "SELECT * FROM tasks WHERE true", args = ["AND status = $1" if status]"#,
+    )
+    .unwrap();
+
+    assert!(input.resolve_named_params(false, false).is_err());
+}
+
+#[test]
+fn test_rewrite_named_params() {
+    let names = [
+        Ident::new("id", Span::call_site()),
+        Ident::new("n", Span::call_site()),
+    ];
+
+    let rewrite =
+        |sql: &str, numbered: bool| rewrite_named_params(sql, &names, numbered, false).unwrap();
+
+    assert_eq!(
+        rewrite("SELECT :id::text, :n, :id", true),
+        ("SELECT $1::text, $2, $1".into(), vec![0, 1])
+    );
+    assert_eq!(
+        rewrite("SELECT :id, :n, :id", false),
+        ("SELECT ?, ?, ?".into(), vec![0, 1, 0])
+    );
+
+    // quoted strings and identifiers
+    assert_eq!(
+        rewrite(r#"SELECT ':id', ":id", `:id`, :id, :n"#, false).0,
+        r#"SELECT ':id', ":id", `:id`, ?, ?"#
+    );
+
+    // array slices
+    assert_eq!(
+        rewrite("SELECT a[1:n], a[:n], :id, :n FROM t", true).0,
+        "SELECT a[1:n], a[:n], $1, $2 FROM t"
+    );
+    assert_eq!(
+        rewrite("SELECT (a)[1:n], a[1][:n], :id, :n FROM t", true).0,
+        "SELECT (a)[1:n], a[1][:n], $1, $2 FROM t"
+    );
+
+    // array constructors, which are not subscripts
+    assert_eq!(
+        rewrite("SELECT ARRAY[:id, :n], array[:n][1:2]", true).0,
+        "SELECT ARRAY[$1, $2], array[$2][1:2]"
+    );
+    assert_eq!(
+        rewrite(
+            "SELECT * FROM t WHERE id = ANY(ARRAY[:id]) AND n = :n",
+            true
+        )
+        .0,
+        "SELECT * FROM t WHERE id = ANY(ARRAY[$1]) AND n = $2"
+    );
+
+    // comments
+    assert_eq!(
+        rewrite("SELECT :id -- not :n\n, /* nor :n */ :n", true).0,
+        "SELECT $1 -- not :n\n, /* nor :n */ $2"
+    );
+
+    // dollar-quoted strings, which are not `$N` parameters
+    assert_eq!(
+        rewrite("SELECT $$:n$$, $body$ :id $$ :n $body$, :id, :n", true).0,
+        "SELECT $$:n$$, $body$ :id $$ :n $body$, $1, $2"
+    );
+
+    // backslash escapes in strings, always in `E'..'` strings
+    assert_eq!(
+        rewrite(r"SELECT E'\' :n', 'a\', :id, :n", true).0,
+        r"SELECT E'\' :n', 'a\', $1, $2"
+    );
+    assert_eq!(
+        rewrite_named_params(r#"SELECT '\' :n', "\" :n", :id, :n"#, &names, false, true)
+            .unwrap()
+            .0,
+        r#"SELECT '\' :n', "\" :n", ?, ?"#
+    );
+
+    assert!(rewrite_named_params("SELECT :id", &names, true, false).is_err());
+    assert!(rewrite_named_params("SELECT :id, :n, :x", &names, true, false).is_err());
+}
@@ -8,6 +8,7 @@ use syn::Type;
 
 pub use input::QueryMacroInput;
 use quote::{format_ident, quote};
+use sqlx_core::config::{find_project_file, Config};
 use sqlx_core::connection::Connection;
 use sqlx_core::database::Database;
 use sqlx_core::{column::Column, describe::Describe, type_info::TypeInfo};
@@ -73,7 +74,6 @@ struct Metadata {
     #[allow(unused)]
     manifest_dir: PathBuf,
     offline: bool,
-    database_url: Option<String>,
     workspace_root: Arc<Mutex<Option<PathBuf>>>,
 }
 
@@ -140,12 +140,9 @@ static METADATA: Lazy<Metadata> = Lazy::new(|| {
         .map(|s| s.eq_ignore_ascii_case("true") || s == "1")
         .unwrap_or(false);
 
-    let database_url = env("DATABASE_URL").ok();
-
     Metadata {
         manifest_dir,
         offline,
-        database_url,
         workspace_root: Arc::new(Mutex::new(None)),
     }
 });
@@ -154,44 +151,27 @@ pub fn expand_input<'a>(
     input: QueryMacroInput,
     drivers: impl IntoIterator<Item = &'a QueryDriver>,
 ) -> crate::Result<TokenStream> {
-    let data_source = match &*METADATA {
-        Metadata {
-            offline: false,
-            database_url: Some(db_url),
-            ..
-        } => QueryDataSource::live(db_url)?,
+    // the environment must be loaded from `.env` before the URL of a database is read
+    let metadata = &*METADATA;
+    let config = config()?;
 
-        _ => {
-            // Try load the cached query metadata file.
-            let filename = format!("query-{}.json", hash_string(&input.sql));
-
-            // Check SQLX_OFFLINE_DIR, then local .sqlx, then workspace .sqlx.
-            let data_file_path = if let Some(sqlx_offline_dir_path) = env("SQLX_OFFLINE_DIR")
-                .ok()
-                .map(PathBuf::from)
-                .map(|path| path.join(&filename))
-                .filter(|path| path.exists())
-            {
-                sqlx_offline_dir_path
-            } else if let Some(local_path) =
-                Some(METADATA.manifest_dir.join(".sqlx").join(&filename))
-                    .filter(|path| path.exists())
-            {
-                local_path
-            } else if let Some(workspace_path) =
-                Some(METADATA.workspace_root().join(".sqlx").join(&filename))
-                    .filter(|path| path.exists())
-            {
-                workspace_path
-            } else {
-                return Err(
-                    "`DATABASE_URL` must be set, or `cargo sqlx prepare` must have been run \
-                     and .sqlx must exist, to use query macros"
-                        .into(),
-                );
-            };
+    let database_url = match &input.database {
+        Some(name) => config.database(name)?.url(|var| env(var).ok()),
+        None => env(config.common.database_url_var()).ok(),
+    };
+
+    let data_source = match &database_url {
+        Some(db_url) if !metadata.offline => QueryDataSource::live(db_url)?,
 
-            QueryDataSource::Cached(DynQueryData::from_data_file(&data_file_path, &input.sql)?)
+        _ => {
+            // the cached data of the query with every optional clause selects the driver; its
+            // parameters are numbered as written, whatever the database
+            let sql = input
+                .sql_variants(false)?
+                .pop()
+                .expect("at least one variant");
+
+            QueryDataSource::Cached(cached_query_data(&sql, input.database.as_deref())?)
         }
     };
 
@@ -218,22 +198,145 @@ pub fn expand_input<'a>(
     }
 }
 
+/// Load the query data saved by `cargo sqlx prepare` for `sql`.
+///
+/// The data of a database configured in `sqlx.toml` is kept in a directory named after it.
+fn cached_query_data(sql: &str, database: Option<&str>) -> crate::Result<DynQueryData> {
+    let filename = format!("query-{}.json", hash_string(sql));
+
+    let data_path = |dir: PathBuf| match database {
+        Some(name) => dir.join(name).join(&filename),
+        None => dir.join(&filename),
+    };
+
+    let config = config()?;
+    let exists = |path: &PathBuf| path.exists();
+
+    // Check SQLX_OFFLINE_DIR, then the directory configured in sqlx.toml, or else local .sqlx
+    // and then workspace .sqlx.
+    let data_file_path = env("SQLX_OFFLINE_DIR")
+        .ok()
+        .map(|dir| data_path(PathBuf::from(dir)))
+        .filter(exists)
+        .or_else(|| match &config.macros.offline_dir {
+            Some(dir) => Some(data_path(dir.clone())).filter(exists),
+            None => Some(data_path(METADATA.manifest_dir.join(".sqlx")))
+                .filter(exists)
+                .or_else(|| {
+                    Some(data_path(METADATA.workspace_root().join(".sqlx"))).filter(exists)
+                }),
+        });
+
+    let Some(data_file_path) = data_file_path else {
+        let offline_dir = match &config.macros.offline_dir {
+            Some(dir) => dir.display().to_string(),
+            None => ".sqlx".into(),
+        };
+
+        return Err(match database {
+            Some(name) => format!(
+                "the URL of database {:?} must be set in {}, or `cargo sqlx prepare` must have \
+                 been run and {} must exist, to use query macros",
+                name,
+                Config::FILENAME,
+                offline_dir
+            ),
+            None => format!(
+                "`{}` must be set, or `cargo sqlx prepare` must have been run and {} must exist, \
+                 to use query macros",
+                config.common.database_url_var(),
+                offline_dir
+            ),
+        }
+        .into());
+    };
+
+    DynQueryData::from_data_file(&data_file_path, sql)
+}
+
+/// The project configuration in `sqlx.toml`, if there is one.
+static CONFIG: Lazy<Result<Config, String>> = Lazy::new(|| match project_file(Config::FILENAME) {
+    Some(path) => {
+        Config::read_from(&path).map_err(|e| format!("failed to load {}: {}", path.display(), e))
+    }
+    None => Ok(Config::default()),
+});
+
+/// Get the project configuration in `sqlx.toml`, or the default one if there is no such file.
+pub(crate) fn config() -> crate::Result<&'static Config> {
+    CONFIG.as_ref().map_err(|e| e.clone().into())
+}
+
+/// Include `sqlx.toml`, if there is one, in the expansion so the crate is recompiled when it
+/// changes.
+pub(crate) fn quote_project_files() -> crate::Result<TokenStream> {
+    let Some(path) = project_file(Config::FILENAME) else {
+        return Ok(TokenStream::new());
+    };
+
+    let path = path.to_str().ok_or_else(|| {
+        format!(
+            "{} path cannot be represented as a string: {:?}",
+            Config::FILENAME,
+            path
+        )
+    })?;
+
+    Ok(quote! {
+        const _: &[::std::primitive::u8] = include_bytes!(#path);
+    })
+}
+
+/// Find the file `name` at the root of the crate, or else of the workspace.
+fn project_file(name: &str) -> Option<PathBuf> {
+    find_project_file(name, &METADATA.manifest_dir, || {
+        Some(METADATA.workspace_root())
+    })
+}
+
 fn expand_with<DB: DatabaseExt>(
-    input: QueryMacroInput,
+    mut input: QueryMacroInput,
     data_source: QueryDataSource,
 ) -> crate::Result<TokenStream>
 where
     Describe<DB>: DescribeExt,
 {
-    let (query_data, offline): (QueryData<DB>, bool) = match data_source {
-        QueryDataSource::Cached(dyn_data) => (QueryData::from_dyn_data(dyn_data)?, true),
+    // the data of each variant is saved under its source, and the resolved SQL is executed
+    let mut variants = input.sql_variants(DB::BACKSLASH_ESCAPES)?;
+    let queries = input.resolve_named_params(DB::NUMBERED_PARAMS, DB::BACKSLASH_ESCAPES)?;
+
+    // one for each combination of the optional clauses, the last including all of them
+    let (query_data, offline): (Vec<QueryData<DB>>, bool) = match data_source {
+        QueryDataSource::Cached(dyn_data) => {
+            variants.pop();
+
+            let mut query_data = variants
+                .iter()
+                .map(|sql| {
+                    let data = cached_query_data(sql, input.database.as_deref())?;
+                    QueryData::from_dyn_data(data)
+                })
+                .collect::<crate::Result<Vec<_>>>()?;
+
+            query_data.push(QueryData::from_dyn_data(dyn_data)?);
+
+            (query_data, true)
+        }
         QueryDataSource::Live { database_url, .. } => {
-            let describe = DB::describe_blocking(&input.sql, &database_url)?;
-            (QueryData::from_describe(&input.sql, describe), false)
+            let query_data = variants
+                .iter()
+                .zip(&queries)
+                .map(|(source, sql)| {
+                    let describe = DB::describe_blocking(sql, database_url)?;
+                    Ok(QueryData::from_describe(source, describe))
+                })
+                .collect::<crate::Result<Vec<_>>>()?;
+
+            (query_data, false)
         }
     };
 
-    expand_with_data(input, query_data, offline)
+    expand_with_data(input, &queries, query_data, offline)
 }
 
 // marker trait for `Describe` that lets us conditionally require it to be `Serialize + Deserialize`
@@ -246,40 +349,73 @@ impl<DB: Database> DescribeExt for Describe<DB> where
 
 fn expand_with_data<DB: DatabaseExt>(
     input: QueryMacroInput,
-    data: QueryData<DB>,
+    queries: &[String],
+    data: Vec<QueryData<DB>>,
     offline: bool,
 ) -> crate::Result<TokenStream>
 where
     Describe<DB>: DescribeExt,
 {
     // validate at the minimum that our args match the query's input parameters
-    let num_parameters = match data.describe.parameters() {
-        Some(Either::Left(params)) => Some(params.len()),
-        Some(Either::Right(num)) => Some(num),
+    for (mask, variant) in data.iter().enumerate() {
+        let num_parameters = match variant.describe.parameters() {
+            Some(Either::Left(params)) => Some(params.len()),
+            Some(Either::Right(num)) => Some(num),
+
+            None => None,
+        };
+
+        let num_args = input.param_args.len() + mask.count_ones() as usize;
+
+        if let Some(num) = num_parameters {
+            if num != num_args {
+                return Err(if input.optional_clauses.is_empty() {
+                    format!("expected {} parameters, got {}", num, num_args)
+                } else {
+                    format!(
+                        "expected {} parameters, got {} for query: {}",
+                        num, num_args, variant.query
+                    )
+                }
+                .into());
+            }
+        }
+    }
 
-        None => None,
-    };
+    // select the SQL to execute, if it is not the source of the query
+    let select_sql = if !input.rewrites_sql() {
+        TokenStream::new()
+    } else if input.optional_clauses.is_empty() {
+        let sql = &queries[0];
 
-    if let Some(num) = num_parameters {
-        if num != input.arg_exprs.len() {
-            return Err(
-                format!("expected {} parameters, got {}", num, input.arg_exprs.len()).into(),
-            );
+        quote! {
+            let query_sql = #sql;
         }
-    }
+    } else {
+        let variant_sql = queries;
+        let clause_bit = (0..input.optional_clauses.len()).map(|i| {
+            let clause_name = format_ident!("clause{}", i);
+            quote!(if ::std::option::Option::is_some(#clause_name) { 1 << #i } else { 0 })
+        });
+
+        quote! {
+            let query_sql = [#(#variant_sql),*][0 #(| #clause_bit)*];
+        }
+    };
 
-    let args_tokens = args::quote_args(&input, &data.describe)?;
+    // the record is the same for every combination of the optional clauses, so a column is
+    // nullable if it is in any of them
+    let (full, variants) = data.split_last().expect("at least one variant");
+    let describe = &full.describe;
+    let nullable = merge_nullability(describe, variants)?;
+
+    let args_tokens = args::quote_args(&input, describe)?;
 
     let query_args = format_ident!("query_args");
 
-    let output = if data
-        .describe
-        .columns()
-        .iter()
-        .all(|it| it.type_info().is_void())
-    {
+    let output = if describe.columns().iter().all(|it| it.type_info().is_void()) {
         let db_path = DB::db_path();
-        let sql = &input.sql;
+        let sql = output::quote_sql(&input);
 
         quote! {
             ::sqlx::query_with::<#db_path, _>(#sql, #query_args)
@@ -287,7 +423,7 @@ where
     } else {
         match input.record_type {
             RecordType::Generated => {
-                let columns = output::columns_to_rust::<DB>(&data.describe)?;
+                let columns = output::columns_to_rust::<DB>(describe, &nullable)?;
 
                 let record_name: Type = syn::parse_str("Record").unwrap();
 
@@ -326,24 +462,30 @@ where
                 record_tokens
             }
             RecordType::Given(ref out_ty) => {
-                let columns = output::columns_to_rust::<DB>(&data.describe)?;
+                let columns = output::columns_to_rust::<DB>(describe, &nullable)?;
 
                 output::quote_query_as::<DB>(&input, out_ty, &query_args, &columns)
             }
             RecordType::Scalar => {
-                output::quote_query_scalar::<DB>(&input, &query_args, &data.describe)?
+                output::quote_query_scalar::<DB>(&input, &query_args, describe, &nullable)?
             }
         }
     };
 
+    let project_files = quote_project_files()?;
+
     let ret_tokens = quote! {
         {
             #[allow(clippy::all)]
             {
                 use ::sqlx::Arguments as _;
 
+                #project_files
+
                 #args_tokens
 
+                #select_sql
+
                 #output
             }
         }
@@ -376,7 +518,18 @@ where
                     }
 
                     // .sqlx exists and is a directory, store data.
-                    data.save_in(path)?;
+                    let path = match &input.database {
+                        Some(name) => {
+                            let path = path.join(name);
+                            fs::create_dir_all(&path)?;
+                            path
+                        }
+                        None => path,
+                    };
+
+                    for variant in &data {
+                        variant.save_in(&path)?;
+                    }
                 }
             }
         }
@@ -385,6 +538,45 @@ where
     Ok(ret_tokens)
 }
 
+/// Check that every combination of the optional clauses returns the same columns as the query
+/// with all of them, and merge the nullability of each column.
+fn merge_nullability<DB: DatabaseExt>(
+    full: &Describe<DB>,
+    variants: &[QueryData<DB>],
+) -> crate::Result<Vec<Option<bool>>> {
+    let mut nullable: Vec<_> = (0..full.columns().len())
+        .map(|i| full.nullable(i))
+        .collect();
+
+    for variant in variants {
+        let columns = variant.describe.columns();
+
+        let same_columns = columns.len() == full.columns().len()
+            && columns
+                .iter()
+                .zip(full.columns())
+                .all(|(a, b)| a.name() == b.name() && a.type_info() == b.type_info());
+
+        if !same_columns {
+            return Err(format!(
+                "the columns of the query depend on its optional clauses: {}",
+                variant.query
+            )
+            .into());
+        }
+
+        for (i, nullable) in nullable.iter_mut().enumerate() {
+            *nullable = match (*nullable, variant.describe.nullable(i)) {
+                (Some(false), Some(false)) => Some(false),
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                _ => None,
+            };
+        }
+    }
+
+    Ok(nullable)
+}
+
 /// Get the value of an environment variable, telling the compiler about it if applicable.
 fn env(name: &str) -> Result<String, std::env::VarError> {
     #[cfg(procmacro2_semver_exempt)]
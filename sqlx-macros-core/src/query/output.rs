@@ -4,6 +4,7 @@ use syn::Type;
 
 use sqlx_core::column::Column;
 use sqlx_core::describe::Describe;
+use sqlx_core::type_info::TypeInfo;
 
 use crate::database::DatabaseExt;
 
@@ -75,13 +76,21 @@ impl Display for DisplayColumn<'_> {
     }
 }
 
-pub fn columns_to_rust<DB: DatabaseExt>(describe: &Describe<DB>) -> crate::Result<Vec<RustColumn>> {
+/// Map the columns of `describe` to Rust, with the nullability of each in `nullable`.
+pub fn columns_to_rust<DB: DatabaseExt>(
+    describe: &Describe<DB>,
+    nullable: &[Option<bool>],
+) -> crate::Result<Vec<RustColumn>> {
     (0..describe.columns().len())
-        .map(|i| column_to_rust(describe, i))
+        .map(|i| column_to_rust(describe, nullable, i))
         .collect::<crate::Result<Vec<_>>>()
 }
 
-fn column_to_rust<DB: DatabaseExt>(describe: &Describe<DB>, i: usize) -> crate::Result<RustColumn> {
+fn column_to_rust<DB: DatabaseExt>(
+    describe: &Describe<DB>,
+    nullable: &[Option<bool>],
+    i: usize,
+) -> crate::Result<RustColumn> {
     let column = &describe.columns()[i];
 
     // add raw prefix to all identifiers
@@ -93,7 +102,7 @@ fn column_to_rust<DB: DatabaseExt>(describe: &Describe<DB>, i: usize) -> crate::
     let nullable = match nullability {
         ColumnNullabilityOverride::NonNull => false,
         ColumnNullabilityOverride::Nullable => true,
-        ColumnNullabilityOverride::None => describe.nullable(i).unwrap_or(true),
+        ColumnNullabilityOverride::None => nullable.get(i).copied().flatten().unwrap_or(true),
     };
     let type_ = match (type_, nullable) {
         (ColumnTypeOverride::Exact(type_), false) => ColumnType::Exact(type_.to_token_stream()),
@@ -163,13 +172,7 @@ pub fn quote_query_as<DB: DatabaseExt>(
     let db_path = DB::db_path();
     let row_path = DB::row_path();
 
-    // if this query came from a file, use `include_str!()` to tell the compiler where it came from
-    let sql = if let Some(ref path) = &input.file_path {
-        quote::quote_spanned! { input.src_span => include_str!(#path) }
-    } else {
-        let sql = &input.sql;
-        quote! { #sql }
-    };
+    let sql = quote_sql(input);
 
     quote! {
         ::sqlx::query_with::<#db_path, _>(#sql, #bind_args).try_map(|row: #row_path| {
@@ -186,6 +189,7 @@ pub fn quote_query_scalar<DB: DatabaseExt>(
     input: &QueryMacroInput,
     bind_args: &Ident,
     describe: &Describe<DB>,
+    nullable: &[Option<bool>],
 ) -> crate::Result<TokenStream> {
     let columns = describe.columns();
 
@@ -198,11 +202,11 @@ pub fn quote_query_scalar<DB: DatabaseExt>(
     }
 
     // attempt to parse a column override, otherwise fall back to the inferred type of the column
-    let ty = if let Ok(rust_col) = column_to_rust(describe, 0) {
+    let ty = if let Ok(rust_col) = column_to_rust(describe, nullable, 0) {
         rust_col.type_.to_token_stream()
     } else if input.checked {
         let ty = get_column_type::<DB>(0, &columns[0]);
-        if describe.nullable(0).unwrap_or(true) {
+        if nullable.first().copied().flatten().unwrap_or(true) {
             quote! { ::std::option::Option<#ty> }
         } else {
             ty
@@ -212,16 +216,52 @@ pub fn quote_query_scalar<DB: DatabaseExt>(
     };
 
     let db = DB::db_path();
-    let query = &input.sql;
+    let query = quote_sql(input);
 
     Ok(quote! {
         ::sqlx::query_scalar_with::<#db, #ty, _>(#query, #bind_args)
     })
 }
 
+/// The SQL to execute: `query_sql` if the query has optional clauses or named parameters,
+/// or its source otherwise.
+pub(super) fn quote_sql(input: &QueryMacroInput) -> TokenStream {
+    if input.rewrites_sql() {
+        match &input.file_path {
+            // the query executed is rewritten from the file, which must still be tracked
+            Some(path) => {
+                let include = quote::quote_spanned! { input.src_span => include_str!(#path) };
+
+                quote! {{
+                    const _: &::std::primitive::str = #include;
+                    query_sql
+                }}
+            }
+            None => quote! { query_sql },
+        }
+    } else if let Some(ref path) = &input.file_path {
+        // if this query came from a file, use `include_str!()` to tell the compiler where it came from
+        quote::quote_spanned! { input.src_span => include_str!(#path) }
+    } else {
+        let sql = &input.sql;
+        quote! { #sql }
+    }
+}
+
 fn get_column_type<DB: DatabaseExt>(i: usize, column: &DB::Column) -> TokenStream {
     let type_info = &*column.type_info();
 
+    // a type mapped in `sqlx.toml` takes precedence over the default one
+    if let Some(type_) = crate::database::type_override::<DB>(type_info.name()) {
+        return type_.parse().unwrap_or_else(|_| {
+            let message = format!(
+                "Rust type {:?} mapped to {} in sqlx.toml is not parsable",
+                type_, type_info
+            );
+            syn::Error::new(Span::call_site(), message).to_compile_error()
+        });
+    }
+
     <DB as DatabaseExt>::return_type_for_id(&type_info).map_or_else(
         || {
             let message =
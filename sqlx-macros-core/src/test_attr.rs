@@ -1,4 +1,4 @@
-use proc_macro2::{Span, TokenStream};
+use proc_macro2::TokenStream;
 use quote::quote;
 use syn::LitStr;
 
@@ -83,27 +83,34 @@ fn expand_advanced(args: syn::AttributeArgs, input: syn::ItemFn) -> crate::Resul
         }
     });
 
-    let migrations = match args.migrations {
+    let migrator = match args.migrations {
         MigrationsOpt::ExplicitPath(path) => {
-            let migrator = crate::migrate::expand_migrator_from_lit_dir(path)?;
-            quote! { args.migrator(&#migrator); }
+            Some(crate::migrate::expand_migrator_from_lit_dir(path)?)
         }
         MigrationsOpt::InferredPath if !inputs.is_empty() => {
-            let migrations_path = crate::common::resolve_path("./migrations", Span::call_site())?;
+            let migrations_path = crate::migrate::default_migrations_dir()?;
 
             if migrations_path.is_dir() {
-                let migrator = crate::migrate::expand_migrator(&migrations_path)?;
-                quote! { args.migrator(&#migrator); }
+                Some(crate::migrate::expand_migrator(&migrations_path)?)
             } else {
-                quote! {}
+                None
             }
         }
-        MigrationsOpt::ExplicitMigrator(path) => {
-            quote! { args.migrator(&#path); }
-        }
-        _ => quote! {},
+        MigrationsOpt::ExplicitMigrator(path) => Some(quote! { #path }),
+        _ => None,
     };
 
+    // the migrator is built by a `const fn`, so a reference to it is only `'static` in the
+    // initializer of a static
+    let migrations = migrator.map(|migrator| {
+        quote! {
+            args.migrator({
+                static __SQLX_MIGRATOR: &::sqlx::migrate::Migrator = &#migrator;
+                __SQLX_MIGRATOR
+            });
+        }
+    });
+
     Ok(quote! {
         #[::core::prelude::v1::test]
         #(#attrs)*
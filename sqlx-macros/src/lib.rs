@@ -58,13 +58,29 @@ pub fn derive_from_row(input: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro_derive(Insertable, attributes(sqlx))]
+pub fn derive_insertable(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+
+    match derives::expand_derive_insertable(&input) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 #[cfg(feature = "migrate")]
 #[proc_macro]
 pub fn migrate(input: TokenStream) -> TokenStream {
     use syn::LitStr;
 
-    let input = syn::parse_macro_input!(input as LitStr);
-    match migrate::expand_migrator_from_lit_dir(input) {
+    let input = syn::parse_macro_input!(input as Option<LitStr>);
+
+    let res = match input {
+        Some(dir) => migrate::expand_migrator_from_lit_dir(dir),
+        None => migrate::expand_migrator_from_config(),
+    };
+
+    match res {
         Ok(ts) => ts.into(),
         Err(e) => {
             if let Some(parse_err) = e.downcast_ref::<syn::Error>() {
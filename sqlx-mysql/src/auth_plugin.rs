@@ -0,0 +1,71 @@
+use std::fmt::{self, Debug, Formatter};
+
+use crate::error::BoxDynError;
+
+/// A handler for a MySQL authentication plugin that SQLx does not implement natively.
+///
+/// Handlers are registered with [`MySqlConnectOptions::auth_plugin()`][crate::MySqlConnectOptions::auth_plugin]
+/// and are consulted whenever the server names a plugin, either as its default plugin in the
+/// initial handshake or in an authentication switch request.
+/// A registered handler takes precedence over the built-in implementation of the same name.
+///
+/// See the [MySQL documentation](https://dev.mysql.com/doc/dev/mysql-server/latest/page_protocol_connection_phase_authentication_methods.html)
+/// for details on the authentication flow.
+///
+/// # Example
+///
+/// ```rust
+/// # use sqlx_core::error::BoxDynError;
+/// # use sqlx_mysql::{MySqlAuthPlugin, MySqlConnectOptions};
+/// struct TokenAuth;
+///
+/// impl MySqlAuthPlugin for TokenAuth {
+///     fn name(&self) -> &str {
+///         "my_token_plugin"
+///     }
+///
+///     fn scramble(&self, password: Option<&str>, _nonce: &[u8]) -> Result<Vec<u8>, BoxDynError> {
+///         Ok(password.unwrap_or_default().as_bytes().to_vec())
+///     }
+/// }
+///
+/// let options = MySqlConnectOptions::new().auth_plugin(TokenAuth);
+/// ```
+pub trait MySqlAuthPlugin: Send + Sync + 'static {
+    /// The name of the plugin, as sent by the server (e.g. `authentication_ldap_simple`).
+    fn name(&self) -> &str;
+
+    /// Compute the response to an authentication switch request from the
+    /// configured password and the plugin data (usually a nonce) sent by the server.
+    fn scramble(&self, password: Option<&str>, nonce: &[u8]) -> Result<Vec<u8>, BoxDynError>;
+
+    /// Handle a packet sent by the server that is not `OK`, `ERR` or another switch request.
+    ///
+    /// Return `Some(response)` to send a response to the server, or `None` to wait
+    /// for the next packet.
+    ///
+    /// The default implementation returns an error, as most plugins complete in a single
+    /// round-trip.
+    fn handle(
+        &self,
+        password: Option<&str>,
+        packet: &[u8],
+    ) -> Result<Option<Vec<u8>>, BoxDynError> {
+        let _ = password;
+
+        Err(format!(
+            "unexpected packet 0x{:02x} for auth plugin '{}' during authentication",
+            packet.first().copied().unwrap_or_default(),
+            self.name()
+        )
+        .into())
+    }
+}
+
+impl Debug for dyn MySqlAuthPlugin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MySqlAuthPlugin")
+            .field("name", &self.name())
+            .finish_non_exhaustive()
+    }
+}
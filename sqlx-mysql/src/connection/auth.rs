@@ -1,24 +1,28 @@
 use bytes::buf::Chain;
 use bytes::Bytes;
+use curve25519_dalek::{EdwardsPoint, Scalar};
 use digest::{Digest, OutputSizeUser};
 use generic_array::GenericArray;
 use rand::thread_rng;
 use rsa::{pkcs8::DecodePublicKey, Oaep, PaddingScheme, PublicKey, RsaPublicKey};
 use sha1::Sha1;
-use sha2::Sha256;
+use sha2::{Sha256, Sha512};
 
 use crate::connection::stream::MySqlStream;
 use crate::error::Error;
 use crate::protocol::auth::AuthPlugin;
 use crate::protocol::Packet;
+use crate::MySqlConnectOptions;
 
 impl AuthPlugin {
     pub(super) async fn scramble(
         self,
         stream: &mut MySqlStream,
-        password: &str,
+        options: &MySqlConnectOptions,
         nonce: &Chain<Bytes, Bytes>,
     ) -> Result<Vec<u8>, Error> {
+        let password = options.password.as_deref().unwrap_or_default();
+
         match self {
             // https://mariadb.com/kb/en/caching_sha2_password-authentication-plugin/
             AuthPlugin::CachingSha2Password => Ok(scramble_sha256(password, nonce).to_vec()),
@@ -27,6 +31,28 @@ impl AuthPlugin {
 
             // https://mariadb.com/kb/en/sha256_password-plugin/
             AuthPlugin::Sha256Password => encrypt_rsa(stream, 0x01, password, nonce).await,
+
+            // https://dev.mysql.com/doc/refman/8.0/en/cleartext-pluggable-authentication.html
+            AuthPlugin::MySqlClearPassword => {
+                if !stream.is_tls && !options.enable_cleartext_plugin {
+                    return Err(err_protocol!(
+                        "server requested the 'mysql_clear_password' auth plugin on a connection \
+                         without TLS; enable TLS or set `MySqlConnectOptions::enable_cleartext_plugin()`"
+                    ));
+                }
+
+                Ok(to_asciz(password))
+            }
+
+            // https://mariadb.com/kb/en/authentication-plugin-ed25519/
+            AuthPlugin::Ed25519 => {
+                let (a, b) = (nonce.first_ref(), nonce.last_ref());
+                let mut message = Vec::with_capacity(a.len() + b.len());
+                message.extend_from_slice(a);
+                message.extend_from_slice(b);
+
+                Ok(sign_ed25519(password, &message).to_vec())
+            }
         }
     }
 
@@ -34,9 +60,11 @@ impl AuthPlugin {
         self,
         stream: &mut MySqlStream,
         packet: Packet<Bytes>,
-        password: &str,
+        options: &MySqlConnectOptions,
         nonce: &Chain<Bytes, Bytes>,
     ) -> Result<bool, Error> {
+        let password = options.password.as_deref().unwrap_or_default();
+
         match self {
             AuthPlugin::CachingSha2Password if packet[0] == 0x01 => {
                 match packet[1] {
@@ -96,6 +124,41 @@ fn scramble_sha1(
     pw_hash
 }
 
+fn sign_ed25519(password: &str, message: &[u8]) -> [u8; 64] {
+    // MariaDB uses the SHA-512 hash of the password as the expanded secret key, instead of
+    // hashing a 32-byte seed as in RFC 8032; otherwise this is a standard Ed25519 signature.
+    // https://github.com/MariaDB/server/blob/10.4/plugin/auth_ed25519/ref10/sign.c
+
+    let az = Sha512::digest(password);
+
+    let mut a = [0u8; 32];
+    a.copy_from_slice(&az[..32]);
+    a[0] &= 248;
+    a[31] &= 127;
+    a[31] |= 64;
+
+    let a = Scalar::from_bytes_mod_order(a);
+    let public_key = EdwardsPoint::mul_base(&a).compress();
+
+    let r = Scalar::from_hash(Sha512::new().chain_update(&az[32..]).chain_update(message));
+    let r_point = EdwardsPoint::mul_base(&r).compress();
+
+    let k = Scalar::from_hash(
+        Sha512::new()
+            .chain_update(r_point.as_bytes())
+            .chain_update(public_key.as_bytes())
+            .chain_update(message),
+    );
+
+    let s = r + k * a;
+
+    let mut signature = [0u8; 64];
+    signature[..32].copy_from_slice(r_point.as_bytes());
+    signature[32..].copy_from_slice(s.as_bytes());
+
+    signature
+}
+
 fn scramble_sha256(
     password: &str,
     nonce: &Chain<Bytes, Bytes>,
@@ -189,3 +252,37 @@ fn parse_rsa_pub_key(key: &[u8]) -> Result<RsaPublicKey, Error> {
 
     RsaPublicKey::from_public_key_pem(&pem).map_err(Error::protocol)
 }
+
+#[test]
+fn test_sign_ed25519() {
+    use base64::Engine;
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+
+    // the public key for the password "secret", as returned by MariaDB's `ed25519_password("secret")`
+    // https://mariadb.com/kb/en/authentication-plugin-ed25519/
+    let public_key = base64::engine::general_purpose::STANDARD_NO_PAD
+        .decode("ZIgUREUg5PVgQ6LskhXmO+eZLS0nC8be6HPjYWR4YJY")
+        .unwrap();
+    let public_key = CompressedEdwardsY::from_slice(&public_key)
+        .unwrap()
+        .decompress()
+        .unwrap();
+
+    let message = b"0123456789abcdef0123456789abcdef";
+    let signature = sign_ed25519("secret", message);
+
+    let r = CompressedEdwardsY::from_slice(&signature[..32])
+        .unwrap()
+        .decompress()
+        .unwrap();
+    let s = Scalar::from_canonical_bytes(signature[32..].try_into().unwrap()).unwrap();
+    let k = Scalar::from_hash(
+        Sha512::new()
+            .chain_update(&signature[..32])
+            .chain_update(public_key.compress().as_bytes())
+            .chain_update(message),
+    );
+
+    // verify: [S]B = R + [k]A
+    assert_eq!(EdwardsPoint::mul_base(&s), r + k * public_key);
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use bytes::buf::Buf;
 use bytes::Bytes;
 use futures_core::future::BoxFuture;
@@ -7,11 +9,12 @@ use crate::common::StatementCache;
 use crate::connection::{tls, MySqlStream, MAX_PACKET_SIZE};
 use crate::error::Error;
 use crate::net::{Socket, WithSocket};
+use crate::protocol::auth::AuthPlugin;
 use crate::protocol::connect::{
     AuthSwitchRequest, AuthSwitchResponse, Handshake, HandshakeResponse,
 };
 use crate::protocol::Capabilities;
-use crate::{MySqlConnectOptions, MySqlConnection};
+use crate::{MySqlAuthPlugin, MySqlConnectOptions, MySqlConnection};
 
 impl MySqlConnection {
     pub(crate) async fn establish(options: &MySqlConnectOptions) -> Result<Self, Error> {
@@ -29,6 +32,8 @@ impl MySqlConnection {
             transaction_depth: 0,
             cache_statement: StatementCache::new(options.statement_cache_capacity),
             log_settings: options.log_settings.clone(),
+            #[cfg(feature = "migrate")]
+            migrations_table: sqlx_core::migrate::Migrator::DEFAULT_TABLE_NAME.into(),
         })
     }
 }
@@ -69,46 +74,42 @@ impl<'a> DoHandshake<'a> {
         // https://mariadb.com/kb/en/connection/
 
         let handshake: Handshake = stream.recv_packet().await?.decode()?;
+        let (server_flavor, server_version) = handshake.server_flavor_and_version();
+
+        let mut plugin: Option<AuthPlugin> = None;
+        let mut custom_plugin: Option<Arc<dyn MySqlAuthPlugin>> = None;
+        let mut nonce = handshake.auth_plugin_data;
+
+        // a registered handler takes precedence over the built-in implementation of the same
+        // name, so the server's default plugin may be one we only know about through the registry
+        if let Some(name) = &handshake.auth_plugin {
+            if let Some(custom) = options.auth_plugins.iter().find(|p| p.name() == name) {
+                custom_plugin = Some(Arc::clone(custom));
+            } else {
+                plugin = Some(name.parse()?);
+            }
+        }
 
-        let mut plugin = handshake.auth_plugin;
-        let nonce = handshake.auth_plugin_data;
-
-        // FIXME: server version parse is a bit ugly
-        // expecting MAJOR.MINOR.PATCH
-
-        let mut server_version = handshake.server_version.split('.');
-
-        let server_version_major: u16 = server_version
-            .next()
-            .unwrap_or_default()
-            .parse()
-            .unwrap_or(0);
-
-        let server_version_minor: u16 = server_version
-            .next()
-            .unwrap_or_default()
-            .parse()
-            .unwrap_or(0);
-
-        let server_version_patch: u16 = server_version
-            .next()
-            .unwrap_or_default()
-            .parse()
-            .unwrap_or(0);
-
-        stream.server_version = (
-            server_version_major,
-            server_version_minor,
-            server_version_patch,
-        );
+        stream.server_flavor = server_flavor;
+        stream.server_version = server_version;
 
         stream.capabilities &= handshake.server_capabilities;
         stream.capabilities |= Capabilities::PROTOCOL_41;
 
         let mut stream = tls::maybe_upgrade(stream, self.options).await?;
 
-        let auth_response = if let (Some(plugin), Some(password)) = (plugin, &options.password) {
-            Some(plugin.scramble(&mut stream, password, &nonce).await?)
+        let auth_response = if let Some(custom) = &custom_plugin {
+            let mut data = Vec::with_capacity(nonce.remaining());
+            data.extend_from_slice(nonce.first_ref());
+            data.extend_from_slice(nonce.last_ref());
+
+            Some(
+                custom
+                    .scramble(options.password.as_deref(), &data)
+                    .map_err(|e| err_protocol!("auth plugin '{}' failed: {}", custom.name(), e))?,
+            )
+        } else if let (Some(plugin), Some(_)) = (plugin, &options.password) {
+            Some(plugin.scramble(&mut stream, options, &nonce).await?)
         } else {
             None
         };
@@ -118,8 +119,12 @@ impl<'a> DoHandshake<'a> {
             max_packet_size: MAX_PACKET_SIZE,
             username: &options.username,
             database: options.database.as_deref(),
-            auth_plugin: plugin,
+            auth_plugin: custom_plugin
+                .as_ref()
+                .map(|custom| custom.name())
+                .or_else(|| plugin.map(AuthPlugin::name)),
             auth_response: auth_response.as_deref(),
+            connect_attrs: &options.connect_attrs,
         });
 
         stream.flush().await?;
@@ -128,7 +133,7 @@ impl<'a> DoHandshake<'a> {
             let packet = stream.recv_packet().await?;
             match packet[0] {
                 0x00 => {
-                    let _ok = packet.ok()?;
+                    let _ok = packet.ok(stream.capabilities)?;
 
                     break;
                 }
@@ -136,25 +141,51 @@ impl<'a> DoHandshake<'a> {
                 0xfe => {
                     let switch: AuthSwitchRequest = packet.decode()?;
 
-                    plugin = Some(switch.plugin);
-                    let nonce = switch.data.chain(Bytes::new());
+                    nonce = switch.data.clone().chain(Bytes::new());
+
+                    let response = if let Some(custom) = options
+                        .auth_plugins
+                        .iter()
+                        .find(|p| p.name() == switch.plugin)
+                    {
+                        plugin = None;
+
+                        let response = custom
+                            .scramble(options.password.as_deref(), &switch.data)
+                            .map_err(|e| {
+                            err_protocol!("auth plugin '{}' failed: {}", switch.plugin, e)
+                        })?;
+
+                        custom_plugin = Some(Arc::clone(custom));
 
-                    let response = switch
-                        .plugin
-                        .scramble(
-                            &mut stream,
-                            options.password.as_deref().unwrap_or_default(),
-                            &nonce,
-                        )
-                        .await?;
+                        response
+                    } else {
+                        let switch_plugin: AuthPlugin = switch.plugin.parse()?;
+
+                        plugin = Some(switch_plugin);
+                        custom_plugin = None;
+
+                        switch_plugin.scramble(&mut stream, options, &nonce).await?
+                    };
 
                     stream.write_packet(AuthSwitchResponse(response));
                     stream.flush().await?;
                 }
 
                 id => {
-                    if let (Some(plugin), Some(password)) = (plugin, &options.password) {
-                        if plugin.handle(&mut stream, packet, password, &nonce).await? {
+                    if let Some(custom) = &custom_plugin {
+                        let response = custom
+                            .handle(options.password.as_deref(), &packet)
+                            .map_err(|e| {
+                                err_protocol!("auth plugin '{}' failed: {}", custom.name(), e)
+                            })?;
+
+                        if let Some(response) = response {
+                            stream.write_packet(&*response);
+                            stream.flush().await?;
+                        }
+                    } else if let (Some(plugin), Some(_)) = (plugin, &options.password) {
+                        if plugin.handle(&mut stream, packet, options, &nonce).await? {
                             // plugin signaled authentication is ok
                             break;
                         }
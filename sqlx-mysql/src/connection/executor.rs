@@ -116,7 +116,11 @@ impl MySqlConnection {
                     })
                     .await?;
 
-                (metadata.column_names, MySqlValueFormat::Binary, false)
+                // statements such as `CALL` report no columns when prepared,
+                // so their result sets must bring their own metadata
+                let needs_metadata = metadata.columns.is_empty();
+
+                (metadata.column_names, MySqlValueFormat::Binary, needs_metadata)
             } else {
                 // https://dev.mysql.com/doc/internals/en/com-query.html
                 self.stream.send_packet(Query(sql)).await?;
@@ -132,13 +136,15 @@ impl MySqlConnection {
                 if packet[0] == 0x00 || packet[0] == 0xff {
                     // first packet in a query response is OK or ERR
                     // this indicates either a successful query with no rows at all or a failed query
-                    let ok = packet.ok()?;
+                    let ok = packet.ok(self.stream.capabilities)?;
 
                     let rows_affected = ok.affected_rows;
                     logger.increase_rows_affected(rows_affected);
                     let done = MySqlQueryResult {
                         rows_affected,
                         last_insert_id: ok.last_insert_id,
+                        session_state_changes: ok.session_state_changes,
+                        ..Default::default()
                     };
 
                     r#yield!(Either::Left(done));
@@ -171,12 +177,16 @@ impl MySqlConnection {
                 loop {
                     let packet = self.stream.recv_packet().await?;
 
-                    if packet[0] == 0xfe && packet.len() < 9 {
+                    if packet.is_eof(self.stream.capabilities) {
                         let eof = packet.eof(self.stream.capabilities)?;
 
+                        // mark the end of this result set, along with its column metadata
                         r#yield!(Either::Left(MySqlQueryResult {
                             rows_affected: 0,
                             last_insert_id: 0,
+                            columns: Arc::clone(&columns),
+                            out_params: eof.status.contains(Status::SERVER_PS_OUT_PARAMS),
+                            session_state_changes: eof.session_state_changes,
                         }));
 
                         if eof.status.contains(Status::SERVER_MORE_RESULTS_EXISTS) {
@@ -210,6 +220,62 @@ impl MySqlConnection {
     }
 }
 
+impl MySqlConnection {
+    /// Execute a `CALL` to a stored procedure and return the row holding the values of its
+    /// `OUT` and `INOUT` parameters, discarding any other result sets it produces.
+    ///
+    /// The server only sends `OUT` parameters for a `CALL` executed as a prepared statement,
+    /// so every parameter of the procedure must be bound as an argument
+    /// (use `NULL` for plain `OUT` parameters). Returns `None` if the server did not send them.
+    ///
+    /// To read the other result sets as well, use [`fetch_many()`][Executor::fetch_many]
+    /// and check [`MySqlQueryResult::is_out_params()`] on the result that ends each result set.
+    ///
+    /// ```rust,no_run
+    /// # async fn example(conn: &mut sqlx_mysql::MySqlConnection) -> Result<(), sqlx_core::error::Error> {
+    /// use sqlx_core::row::Row;
+    ///
+    /// // CREATE PROCEDURE add_one(IN x INT, OUT y INT) BEGIN SET y = x + 1; END
+    /// let query = sqlx_core::query::query("CALL add_one(?, ?)")
+    ///     .bind(41_i32)
+    ///     .bind(None::<i32>);
+    ///
+    /// if let Some(row) = conn.fetch_out_params(query).await? {
+    ///     let y: i32 = row.try_get("y")?;
+    ///     assert_eq!(y, 42);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_out_params<'q, E>(&mut self, query: E) -> Result<Option<MySqlRow>, Error>
+    where
+        E: Execute<'q, MySql> + 'q,
+    {
+        let mut s = self.fetch_many(query);
+
+        let mut last_row = None;
+        let mut out_params = None;
+
+        while let Some(v) = s.try_next().await? {
+            match v {
+                Either::Left(result) if result.is_out_params() => {
+                    out_params = last_row.take();
+                }
+
+                Either::Left(_) => {
+                    last_row = None;
+                }
+
+                Either::Right(row) => {
+                    last_row = Some(row);
+                }
+            }
+        }
+
+        Ok(out_params)
+    }
+}
+
 impl<'c> Executor<'c> for &'c mut MySqlConnection {
     type Database = MySql;
 
@@ -35,6 +35,32 @@ pub struct MySqlConnection {
     cache_statement: StatementCache<(u32, MySqlStatementMetadata)>,
 
     log_settings: LogSettings,
+
+    // table applied migrations are recorded in, set by the `Migrator`
+    #[cfg(feature = "migrate")]
+    pub(crate) migrations_table: std::borrow::Cow<'static, str>,
+}
+
+/// The flavor of the server a [`MySqlConnection`] is connected to.
+///
+/// See [`MySqlConnection::server_flavor()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MySqlServerFlavor {
+    /// MySQL, or a server compatible with it (e.g. Percona Server, TiDB).
+    MySql,
+
+    /// MariaDB, which reports its native `UUID`, `INET4` and `INET6` column types
+    /// through extended type metadata.
+    MariaDb,
+}
+
+impl MySqlConnection {
+    /// The flavor of the server this connection is connected to,
+    /// as detected from the version string it sent in the handshake.
+    pub fn server_flavor(&self) -> MySqlServerFlavor {
+        self.stream.server_flavor
+    }
 }
 
 impl Debug for MySqlConnection {
@@ -10,12 +10,13 @@ use crate::io::{Decode, Encode};
 use crate::net::{BufferedSocket, Socket};
 use crate::protocol::response::{EofPacket, ErrPacket, OkPacket, Status};
 use crate::protocol::{Capabilities, Packet};
-use crate::{MySqlConnectOptions, MySqlDatabaseError};
+use crate::{MySqlConnectOptions, MySqlDatabaseError, MySqlServerFlavor};
 
 pub struct MySqlStream<S = Box<dyn Socket>> {
     // Wrapping the socket in `Box` allows us to unsize in-place.
     pub(crate) socket: BufferedSocket<S>,
     pub(crate) server_version: (u16, u16, u16),
+    pub(crate) server_flavor: MySqlServerFlavor,
     pub(super) capabilities: Capabilities,
     pub(crate) sequence_id: u8,
     pub(crate) waiting: VecDeque<Waiting>,
@@ -51,16 +52,23 @@ impl<S: Socket> MySqlStream<S> {
             | Capabilities::MULTI_RESULTS
             | Capabilities::PLUGIN_AUTH
             | Capabilities::PS_MULTI_RESULTS
+            | Capabilities::SESSION_TRACK
+            | Capabilities::MARIADB_CLIENT_EXTENDED_TYPE_INFO
             | Capabilities::SSL;
 
         if options.database.is_some() {
             capabilities |= Capabilities::CONNECT_WITH_DB;
         }
 
+        if !options.connect_attrs.is_empty() {
+            capabilities |= Capabilities::CONNECT_ATTRS;
+        }
+
         Self {
             waiting: VecDeque::new(),
             capabilities,
             server_version: (0, 0, 0),
+            server_flavor: MySqlServerFlavor::MySql,
             sequence_id: 0,
             collation,
             charset,
@@ -78,7 +86,7 @@ impl<S: Socket> MySqlStream<S> {
             while self.waiting.front() == Some(&Waiting::Row) {
                 let packet = self.recv_packet().await?;
 
-                if !packet.is_empty() && packet[0] == 0xfe && packet.len() < 9 {
+                if packet.is_eof(self.capabilities) {
                     let eof = packet.eof(self.capabilities)?;
 
                     if eof.status.contains(Status::SERVER_MORE_RESULTS_EXISTS) {
@@ -93,7 +101,7 @@ impl<S: Socket> MySqlStream<S> {
                 let packet = self.recv_packet().await?;
 
                 if !packet.is_empty() && (packet[0] == 0x00 || packet[0] == 0xff) {
-                    let ok = packet.ok()?;
+                    let ok = packet.ok(self.capabilities)?;
 
                     if !ok.status.contains(Status::SERVER_MORE_RESULTS_EXISTS) {
                         self.waiting.pop_front();
@@ -169,7 +177,9 @@ impl<S: Socket> MySqlStream<S> {
     }
 
     pub(crate) async fn recv_ok(&mut self) -> Result<OkPacket, Error> {
-        self.recv_packet().await?.ok()
+        let capabilities = self.capabilities;
+
+        self.recv_packet().await?.ok(capabilities)
     }
 
     pub(crate) async fn maybe_recv_eof(&mut self) -> Result<Option<EofPacket>, Error> {
@@ -196,6 +206,7 @@ impl<S: Socket> MySqlStream<S> {
         MySqlStream {
             socket: self.socket.boxed(),
             server_version: self.server_version,
+            server_flavor: self.server_flavor,
             capabilities: self.capabilities,
             sequence_id: self.sequence_id,
             waiting: self.waiting,
@@ -5,11 +5,12 @@ use crate::net::tls::TlsConfig;
 use crate::net::{tls, BufferedSocket, Socket, WithSocket};
 use crate::protocol::connect::SslRequest;
 use crate::protocol::Capabilities;
-use crate::{MySqlConnectOptions, MySqlSslMode};
+use crate::{MySqlConnectOptions, MySqlServerFlavor, MySqlSslMode};
 use std::collections::VecDeque;
 
 struct MapStream {
     server_version: (u16, u16, u16),
+    server_flavor: MySqlServerFlavor,
     capabilities: Capabilities,
     sequence_id: u8,
     waiting: VecDeque<Waiting>,
@@ -81,6 +82,7 @@ pub(super) async fn maybe_upgrade<S: Socket>(
         tls_config,
         MapStream {
             server_version: stream.server_version,
+            server_flavor: stream.server_flavor,
             capabilities: stream.capabilities,
             sequence_id: stream.sequence_id,
             waiting: stream.waiting,
@@ -98,6 +100,7 @@ impl WithSocket for MapStream {
         MySqlStream {
             socket: BufferedSocket::new(Box::new(socket)),
             server_version: self.server_version,
+            server_flavor: self.server_flavor,
             capabilities: self.capabilities,
             sequence_id: self.sequence_id,
             waiting: self.waiting,
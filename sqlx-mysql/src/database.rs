@@ -6,6 +6,7 @@ use crate::{
 pub(crate) use sqlx_core::database::{
     Database, HasArguments, HasStatement, HasStatementCache, HasValueRef,
 };
+use sqlx_core::insertable::{quote_identifier_with, InsertDialect};
 
 /// MySQL database driver.
 #[derive(Debug)]
@@ -52,3 +53,58 @@ impl<'q> HasStatement<'q> for MySql {
 }
 
 impl HasStatementCache for MySql {}
+
+impl InsertDialect for MySql {
+    fn quote_identifier(name: &str) -> String {
+        quote_identifier_with(name, '`')
+    }
+
+    /// Uses `ON DUPLICATE KEY UPDATE`.
+    fn upsert_sql(
+        table: &str,
+        columns: &[&str],
+        keys: &[&str],
+        updated: &[&str],
+    ) -> (String, String) {
+        let insert = Self::insert_sql(table, columns);
+
+        let assignments = if updated.is_empty() {
+            // assigning a key to itself leaves the existing row unchanged; unlike
+            // `INSERT IGNORE`, this doesn't turn errors other than the duplicate key into warnings
+            keys.iter()
+                .take(1)
+                .map(|key| {
+                    let key = Self::quote_identifier(key);
+                    format!("{0} = {0}", key)
+                })
+                .collect::<Vec<_>>()
+        } else {
+            updated
+                .iter()
+                .map(|column| {
+                    let column = Self::quote_identifier(column);
+                    format!("{0} = VALUES({0})", column)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let on_duplicate_key = format!(" ON DUPLICATE KEY UPDATE {}", assignments.join(", "));
+
+        (insert, on_duplicate_key)
+    }
+}
+
+#[test]
+fn test_upsert_sql() {
+    let (insert, on_duplicate_key) =
+        MySql::upsert_sql("app.users", &["id", "name`"], &["id"], &["name`"]);
+    assert_eq!(insert, "INSERT INTO `app`.`users` (`id`, `name```) ");
+    assert_eq!(
+        on_duplicate_key,
+        " ON DUPLICATE KEY UPDATE `name``` = VALUES(`name```)"
+    );
+
+    let (insert, on_duplicate_key) = MySql::upsert_sql("tags", &["name"], &["name"], &[]);
+    assert_eq!(insert, "INSERT INTO `tags` (`name`) ");
+    assert_eq!(on_duplicate_key, " ON DUPLICATE KEY UPDATE `name` = `name`");
+}
@@ -11,6 +11,7 @@ pub(crate) use sqlx_core::driver_prelude::*;
 pub mod any;
 
 mod arguments;
+mod auth_plugin;
 mod collation;
 mod column;
 mod connection;
@@ -34,12 +35,13 @@ mod migrate;
 mod testing;
 
 pub use arguments::MySqlArguments;
+pub use auth_plugin::MySqlAuthPlugin;
 pub use column::MySqlColumn;
-pub use connection::MySqlConnection;
+pub use connection::{MySqlConnection, MySqlServerFlavor};
 pub use database::MySql;
 pub use error::MySqlDatabaseError;
 pub use options::{MySqlConnectOptions, MySqlSslMode};
-pub use query_result::MySqlQueryResult;
+pub use query_result::{MySqlQueryResult, MySqlSessionStateChange};
 pub use row::MySqlRow;
 pub use statement::MySqlStatement;
 pub use transaction::MySqlTransactionManager;
@@ -12,6 +12,7 @@ use crate::query::query;
 use crate::query_as::query_as;
 use crate::query_scalar::query_scalar;
 use crate::{MySql, MySqlConnectOptions, MySqlConnection};
+use sqlx_core::insertable::InsertDialect;
 
 fn parse_for_maintenance(url: &str) -> Result<(MySqlConnectOptions, String), Error> {
     let mut options = MySqlConnectOptions::from_str(url)?;
@@ -75,12 +76,19 @@ impl MigrateDatabase for MySql {
 }
 
 impl Migrate for MySqlConnection {
+    fn set_migrations_table(&mut self, table_name: &str) -> Result<(), MigrateError> {
+        self.migrations_table = table_name.to_owned().into();
+        Ok(())
+    }
+
     fn ensure_migrations_table(&mut self) -> BoxFuture<'_, Result<(), MigrateError>> {
         Box::pin(async move {
+            let table_name = MySql::quote_identifier(&self.migrations_table);
+
             // language=MySQL
-            self.execute(
+            self.execute(&*format!(
                 r#"
-CREATE TABLE IF NOT EXISTS _sqlx_migrations (
+CREATE TABLE IF NOT EXISTS {} (
     version BIGINT PRIMARY KEY,
     description TEXT NOT NULL,
     installed_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
@@ -89,7 +97,8 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
     execution_time BIGINT NOT NULL
 );
                 "#,
-            )
+                table_name
+            ))
             .await?;
 
             Ok(())
@@ -98,10 +107,13 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
 
     fn dirty_version(&mut self) -> BoxFuture<'_, Result<Option<i64>, MigrateError>> {
         Box::pin(async move {
+            let table_name = MySql::quote_identifier(&self.migrations_table);
+
             // language=SQL
-            let row: Option<(i64,)> = query_as(
-                "SELECT version FROM _sqlx_migrations WHERE success = false ORDER BY version LIMIT 1",
-            )
+            let row: Option<(i64,)> = query_as(&format!(
+                "SELECT version FROM {} WHERE success = false ORDER BY version LIMIT 1",
+                table_name
+            ))
             .fetch_optional(self)
             .await?;
 
@@ -113,11 +125,15 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
         &mut self,
     ) -> BoxFuture<'_, Result<Vec<AppliedMigration>, MigrateError>> {
         Box::pin(async move {
+            let table_name = MySql::quote_identifier(&self.migrations_table);
+
             // language=SQL
-            let rows: Vec<(i64, Vec<u8>)> =
-                query_as("SELECT version, checksum FROM _sqlx_migrations ORDER BY version")
-                    .fetch_all(self)
-                    .await?;
+            let rows: Vec<(i64, Vec<u8>)> = query_as(&format!(
+                "SELECT version, checksum FROM {} ORDER BY version",
+                table_name
+            ))
+            .fetch_all(self)
+            .await?;
 
             let migrations = rows
                 .into_iter()
@@ -172,6 +188,8 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
         migration: &'m Migration,
     ) -> BoxFuture<'m, Result<Duration, MigrateError>> {
         Box::pin(async move {
+            let table_name = MySql::quote_identifier(&self.migrations_table);
+
             // Use a single transaction for the actual migration script and the essential bookeeping so we never
             // execute migrations twice. See https://github.com/launchbadge/sqlx/issues/1966.
             // The `execution_time` however can only be measured for the whole transaction. This value _only_ exists for
@@ -187,12 +205,13 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
             // `success=FALSE` and later modify the flag.
             //
             // language=MySQL
-            let _ = query(
+            let _ = query(&format!(
                 r#"
-    INSERT INTO _sqlx_migrations ( version, description, success, checksum, execution_time )
+    INSERT INTO {} ( version, description, success, checksum, execution_time )
     VALUES ( ?, ?, FALSE, ?, -1 )
                 "#,
-            )
+                table_name
+            ))
             .bind(migration.version)
             .bind(&*migration.description)
             .bind(&*migration.checksum)
@@ -202,13 +221,14 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
             let _ = tx.execute(&*migration.sql).await?;
 
             // language=MySQL
-            let _ = query(
+            let _ = query(&format!(
                 r#"
-    UPDATE _sqlx_migrations
+    UPDATE {}
     SET success = TRUE
     WHERE version = ?
                 "#,
-            )
+                table_name
+            ))
             .bind(migration.version)
             .execute(&mut *tx)
             .await?;
@@ -221,13 +241,14 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
 
             let elapsed = start.elapsed();
 
-            let _ = query(
+            let _ = query(&format!(
                 r#"
-    UPDATE _sqlx_migrations
+    UPDATE {}
     SET execution_time = ?
     WHERE version = ?
                 "#,
-            )
+                table_name
+            ))
             .bind(elapsed.as_nanos() as i64)
             .bind(migration.version)
             .execute(self)
@@ -242,6 +263,8 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
         migration: &'m Migration,
     ) -> BoxFuture<'m, Result<Duration, MigrateError>> {
         Box::pin(async move {
+            let table_name = MySql::quote_identifier(&self.migrations_table);
+
             // Use a single transaction for the actual migration script and the essential bookeeping so we never
             // execute migrations twice. See https://github.com/launchbadge/sqlx/issues/1966.
             let mut tx = self.begin().await?;
@@ -254,13 +277,14 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
             // `success=FALSE` and later remove the migration altogether.
             //
             // language=MySQL
-            let _ = query(
+            let _ = query(&format!(
                 r#"
-    UPDATE _sqlx_migrations
+    UPDATE {}
     SET success = FALSE
     WHERE version = ?
                 "#,
-            )
+                table_name
+            ))
             .bind(migration.version)
             .execute(&mut *tx)
             .await?;
@@ -268,7 +292,7 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
             tx.execute(&*migration.sql).await?;
 
             // language=SQL
-            let _ = query(r#"DELETE FROM _sqlx_migrations WHERE version = ?"#)
+            let _ = query(&format!(r#"DELETE FROM {} WHERE version = ?"#, table_name))
                 .bind(migration.version)
                 .execute(&mut *tx)
                 .await?;
@@ -1,10 +1,13 @@
+use std::env;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 mod connect;
 mod parse;
 mod ssl_mode;
 
-use crate::{connection::LogSettings, net::tls::CertificateInput};
+use crate::{connection::LogSettings, net::tls::CertificateInput, MySqlAuthPlugin};
+use sqlx_core::IndexMap;
 pub use ssl_mode::MySqlSslMode;
 
 /// Options and flags which can be used to configure a MySQL connection.
@@ -26,6 +29,7 @@ pub use ssl_mode::MySqlSslMode;
 /// | `ssl-ca` | `None` | Sets the name of a file containing a list of trusted SSL Certificate Authorities. |
 /// | `statement-cache-capacity` | `100` | The maximum number of prepared statements stored in the cache. Set to `0` to disable. |
 /// | `socket` | `None` | Path to the unix domain socket, which will be used instead of TCP if set. |
+/// | `enable-cleartext-plugin` | `false` | Allow the `mysql_clear_password` plugin to send the password over a connection that is not using TLS. |
 ///
 /// # Example
 ///
@@ -68,6 +72,9 @@ pub struct MySqlConnectOptions {
     pub(crate) collation: Option<String>,
     pub(crate) log_settings: LogSettings,
     pub(crate) pipes_as_concat: bool,
+    pub(crate) enable_cleartext_plugin: bool,
+    pub(crate) auth_plugins: Vec<Arc<dyn MySqlAuthPlugin>>,
+    pub(crate) connect_attrs: IndexMap<String, String>,
 }
 
 impl Default for MySqlConnectOptions {
@@ -79,6 +86,24 @@ impl Default for MySqlConnectOptions {
 impl MySqlConnectOptions {
     /// Creates a new, default set of options ready for configuration
     pub fn new() -> Self {
+        let mut connect_attrs = IndexMap::new();
+
+        // https://dev.mysql.com/doc/refman/8.0/en/performance-schema-connection-attribute-tables.html#performance-schema-connection-attributes-available
+        connect_attrs.insert("_client_name".into(), "sqlx".into());
+        connect_attrs.insert("_client_version".into(), env!("CARGO_PKG_VERSION").into());
+        connect_attrs.insert("_os".into(), env::consts::OS.into());
+        connect_attrs.insert("_platform".into(), env::consts::ARCH.into());
+        connect_attrs.insert("_pid".into(), std::process::id().to_string());
+
+        if let Some(program_name) = env::current_exe()
+            .ok()
+            .as_deref()
+            .and_then(Path::file_stem)
+            .and_then(|name| name.to_str())
+        {
+            connect_attrs.insert("program_name".into(), program_name.into());
+        }
+
         Self {
             port: 3306,
             host: String::from("localhost"),
@@ -95,6 +120,9 @@ impl MySqlConnectOptions {
             statement_cache_capacity: 100,
             log_settings: Default::default(),
             pipes_as_concat: true,
+            enable_cleartext_plugin: false,
+            auth_plugins: Vec::new(),
+            connect_attrs,
         }
     }
 
@@ -258,4 +286,59 @@ impl MySqlConnectOptions {
         self.pipes_as_concat = flag_val;
         self
     }
+
+    /// Allow the `mysql_clear_password` authentication plugin to send the password
+    /// in cleartext over a connection that is not secured with TLS.
+    ///
+    /// The plugin is required by some authentication schemes, such as AWS RDS IAM
+    /// authentication tokens or LDAP/PAM. Over a TLS connection it is always allowed;
+    /// otherwise the connection attempt fails unless this flag is set.
+    ///
+    /// The default value is `false`.
+    pub fn enable_cleartext_plugin(mut self, enable: bool) -> Self {
+        self.enable_cleartext_plugin = enable;
+        self
+    }
+
+    /// Register a handler for an authentication plugin that SQLx does not implement natively.
+    ///
+    /// The handler is used when the server requests to switch to the plugin named by
+    /// [`MySqlAuthPlugin::name()`]. If a handler with the same name was already registered,
+    /// it will be replaced.
+    ///
+    /// See [`MySqlAuthPlugin`] for details.
+    pub fn auth_plugin(mut self, plugin: impl MySqlAuthPlugin) -> Self {
+        self.auth_plugins.retain(|p| p.name() != plugin.name());
+        self.auth_plugins.push(Arc::new(plugin));
+        self
+    }
+
+    /// Sets a connection attribute that is sent to the server during the handshake.
+    ///
+    /// Connection attributes can be inspected by the server, e.g. in the
+    /// `performance_schema.session_connect_attrs` table, to identify which application
+    /// owns a connection. If an attribute with the same key was already set, it will be replaced.
+    ///
+    /// By default, `_client_name`, `_client_version`, `_os`, `_platform`, `_pid`
+    /// and `program_name` are set. Attributes are only sent if the server supports them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use sqlx_core::mysql::MySqlConnectOptions;
+    /// let options = MySqlConnectOptions::new()
+    ///     .connect_attr("program_name", "my-app");
+    /// ```
+    pub fn connect_attr(mut self, key: &str, value: &str) -> Self {
+        self.connect_attrs.insert(key.to_owned(), value.to_owned());
+        self
+    }
+
+    /// Removes all connection attributes, including the default ones.
+    ///
+    /// See [`connect_attr()`][Self::connect_attr] for details.
+    pub fn clear_connect_attrs(mut self) -> Self {
+        self.connect_attrs.clear();
+        self
+    }
 }
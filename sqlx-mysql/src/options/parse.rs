@@ -72,6 +72,11 @@ impl MySqlConnectOptions {
                     options = options.socket(&*value);
                 }
 
+                "enable-cleartext-plugin" => {
+                    options =
+                        options.enable_cleartext_plugin(value.parse().map_err(Error::config)?);
+                }
+
                 _ => {}
             }
         }
@@ -7,6 +7,8 @@ pub enum AuthPlugin {
     MySqlNativePassword,
     CachingSha2Password,
     Sha256Password,
+    MySqlClearPassword,
+    Ed25519,
 }
 
 impl AuthPlugin {
@@ -15,6 +17,8 @@ impl AuthPlugin {
             AuthPlugin::MySqlNativePassword => "mysql_native_password",
             AuthPlugin::CachingSha2Password => "caching_sha2_password",
             AuthPlugin::Sha256Password => "sha256_password",
+            AuthPlugin::MySqlClearPassword => "mysql_clear_password",
+            AuthPlugin::Ed25519 => "client_ed25519",
         }
     }
 }
@@ -27,6 +31,8 @@ impl FromStr for AuthPlugin {
             "mysql_native_password" => Ok(AuthPlugin::MySqlNativePassword),
             "caching_sha2_password" => Ok(AuthPlugin::CachingSha2Password),
             "sha256_password" => Ok(AuthPlugin::Sha256Password),
+            "mysql_clear_password" => Ok(AuthPlugin::MySqlClearPassword),
+            "client_ed25519" => Ok(AuthPlugin::Ed25519),
 
             _ => Err(err_protocol!("unknown authentication plugin: {}", s)),
         }
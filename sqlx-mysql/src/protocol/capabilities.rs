@@ -82,5 +82,8 @@ bitflags::bitflags! {
 
         // Don't reset the options after an unsuccessful connect
         const REMEMBER_OPTIONS = (1 << 31);
+
+        // [MariaDB] Add extended metadata information (type and format names) to column definitions
+        const MARIADB_CLIENT_EXTENDED_TYPE_INFO = (1 << 35);
     }
 }
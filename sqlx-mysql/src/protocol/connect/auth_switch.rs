@@ -10,7 +10,8 @@ use crate::protocol::Capabilities;
 
 #[derive(Debug)]
 pub struct AuthSwitchRequest {
-    pub plugin: AuthPlugin,
+    /// Name of the plugin; may be one we don't implement but the user has registered a handler for.
+    pub plugin: String,
     pub data: Bytes,
 }
 
@@ -24,17 +25,31 @@ impl Decode<'_> for AuthSwitchRequest {
             ));
         }
 
-        let plugin = buf.get_str_nul()?.parse()?;
+        let plugin = buf.get_str_nul()?;
 
-        // See: https://github.com/mysql/mysql-server/blob/ea7d2e2d16ac03afdd9cb72a972a95981107bf51/sql/auth/sha2_password.cc#L942
-        if buf.len() != 21 {
-            return Err(err_protocol!(
-                "expected 21 bytes but found {} bytes",
-                buf.len()
-            ));
-        }
-        let data = buf.get_bytes(20);
-        buf.advance(1); // NUL-terminator
+        let data = match plugin.parse() {
+            Ok(
+                AuthPlugin::MySqlNativePassword
+                | AuthPlugin::CachingSha2Password
+                | AuthPlugin::Sha256Password,
+            ) => {
+                // See: https://github.com/mysql/mysql-server/blob/ea7d2e2d16ac03afdd9cb72a972a95981107bf51/sql/auth/sha2_password.cc#L942
+                if buf.len() != 21 {
+                    return Err(err_protocol!(
+                        "expected 21 bytes but found {} bytes",
+                        buf.len()
+                    ));
+                }
+                let data = buf.get_bytes(20);
+                buf.advance(1); // NUL-terminator
+
+                data
+            }
+
+            // every other plugin defines the layout of its own data
+            // e.g. `client_ed25519` sends a 32-byte nonce with no NUL-terminator
+            _ => buf,
+        };
 
         Ok(Self { plugin, data })
     }
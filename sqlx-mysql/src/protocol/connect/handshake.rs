@@ -3,9 +3,9 @@ use bytes::{Buf, Bytes};
 
 use crate::error::Error;
 use crate::io::{BufExt, Decode};
-use crate::protocol::auth::AuthPlugin;
 use crate::protocol::response::Status;
 use crate::protocol::Capabilities;
+use crate::MySqlServerFlavor;
 
 // https://dev.mysql.com/doc/internals/en/connection-phase-packets.html#packet-Protocol::Handshake
 // https://mariadb.com/kb/en/connection/#initial-handshake-packet
@@ -22,7 +22,9 @@ pub(crate) struct Handshake {
     pub(crate) server_default_collation: u8,
     #[allow(unused)]
     pub(crate) status: Status,
-    pub(crate) auth_plugin: Option<AuthPlugin>,
+    /// Name of the default auth plugin of the server; may be one we don't implement
+    /// but the user has registered a handler for.
+    pub(crate) auth_plugin: Option<String>,
     pub(crate) auth_plugin_data: Chain<Bytes, Bytes>,
 }
 
@@ -71,7 +73,7 @@ impl Decode<'_> for Handshake {
         };
 
         let auth_plugin = if capabilities.contains(Capabilities::PLUGIN_AUTH) {
-            Some(buf.get_str_nul()?.parse()?)
+            Some(buf.get_str_nul()?)
         } else {
             None
         };
@@ -89,6 +91,64 @@ impl Decode<'_> for Handshake {
     }
 }
 
+impl Handshake {
+    /// Detect the flavor and the `(major, minor, patch)` version of the server
+    /// from the `server_version` string.
+    pub(crate) fn server_flavor_and_version(&self) -> (MySqlServerFlavor, (u16, u16, u16)) {
+        parse_server_version(&self.server_version)
+    }
+}
+
+fn parse_server_version(server_version: &str) -> (MySqlServerFlavor, (u16, u16, u16)) {
+    let (flavor, version) = if server_version.contains("MariaDB") {
+        // MariaDB before 11.0 prefixes the version with `5.5.5-`
+        // for compatibility with MySQL replication
+        // e.g. `5.5.5-10.4.7-MariaDB-1:10.4.7+maria~bionic`
+        (
+            MySqlServerFlavor::MariaDb,
+            server_version
+                .strip_prefix("5.5.5-")
+                .unwrap_or(server_version),
+        )
+    } else {
+        (MySqlServerFlavor::MySql, server_version)
+    };
+
+    // expecting MAJOR.MINOR.PATCH, possibly followed by a suffix such as `-log`
+    let mut parts = version
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0));
+
+    let major = parts.next().unwrap_or(0);
+    let minor = parts.next().unwrap_or(0);
+    let patch = parts.next().unwrap_or(0);
+
+    (flavor, (major, minor, patch))
+}
+
+#[test]
+fn test_parse_server_version() {
+    assert_eq!(
+        parse_server_version("8.0.18"),
+        (MySqlServerFlavor::MySql, (8, 0, 18))
+    );
+    assert_eq!(
+        parse_server_version("5.7.30-log"),
+        (MySqlServerFlavor::MySql, (5, 7, 30))
+    );
+    assert_eq!(
+        parse_server_version("5.5.5-10.4.7-MariaDB-1:10.4.7+maria~bionic"),
+        (MySqlServerFlavor::MariaDb, (10, 4, 7))
+    );
+    assert_eq!(
+        parse_server_version("11.0.2-MariaDB-1:11.0.2+maria~ubu2204"),
+        (MySqlServerFlavor::MariaDb, (11, 0, 2))
+    );
+}
+
 #[test]
 fn test_decode_handshake_mysql_8_0_18() {
     const HANDSHAKE_MYSQL_8_0_18: &[u8] = b"\n8.0.18\x00\x19\x00\x00\x00\x114aB0c\x06g\x00\xff\xff\xff\x02\x00\xff\xc7\x15\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00tL\x03s\x0f[4\rl4. \x00caching_sha2_password\x00";
@@ -132,10 +192,7 @@ fn test_decode_handshake_mysql_8_0_18() {
     assert_eq!(p.server_default_collation, 255);
     assert!(p.status.contains(Status::SERVER_STATUS_AUTOCOMMIT));
 
-    assert!(matches!(
-        p.auth_plugin,
-        Some(AuthPlugin::CachingSha2Password)
-    ));
+    assert_eq!(p.auth_plugin.as_deref(), Some("caching_sha2_password"));
 
     assert_eq!(
         &*p.auth_plugin_data.into_iter().collect::<Vec<_>>(),
@@ -155,6 +212,10 @@ fn test_decode_handshake_mariadb_10_4_7() {
         &*p.server_version,
         "5.5.5-10.4.7-MariaDB-1:10.4.7+maria~bionic"
     );
+    assert_eq!(
+        p.server_flavor_and_version(),
+        (MySqlServerFlavor::MariaDb, (10, 4, 7))
+    );
 
     p.server_capabilities.toggle(
         Capabilities::FOUND_ROWS
@@ -185,13 +246,19 @@ fn test_decode_handshake_mariadb_10_4_7() {
 
     assert_eq!(p.server_default_collation, 8);
     assert!(p.status.contains(Status::SERVER_STATUS_AUTOCOMMIT));
-    assert!(matches!(
-        p.auth_plugin,
-        Some(AuthPlugin::MySqlNativePassword)
-    ));
+    assert_eq!(p.auth_plugin.as_deref(), Some("mysql_native_password"));
 
     assert_eq!(
         &*p.auth_plugin_data.into_iter().collect::<Vec<_>>(),
         &[116, 54, 76, 92, 106, 34, 100, 83, 85, 49, 52, 79, 112, 104, 57, 34, 60, 72, 53, 110,]
     );
 }
+
+#[test]
+fn test_decode_handshake_unknown_auth_plugin() {
+    const HANDSHAKE_CUSTOM_PLUGIN: &[u8] = b"\n5.5.5-10.4.7-MariaDB-1:10.4.7+maria~bionic\x00\x0b\x00\x00\x00t6L\\j\"dS\x00\xfe\xf7\x08\x02\x00\xff\x81\x15\x00\x00\x00\x00\x00\x00\x07\x00\x00\x00U14Oph9\"<H5n\x00my_token_plugin\x00";
+
+    let p = Handshake::decode(HANDSHAKE_CUSTOM_PLUGIN.into()).unwrap();
+
+    assert_eq!(p.auth_plugin.as_deref(), Some("my_token_plugin"));
+}
@@ -1,6 +1,7 @@
+use sqlx_core::IndexMap;
+
 use crate::io::MySqlBufMutExt;
 use crate::io::{BufMutExt, Encode};
-use crate::protocol::auth::AuthPlugin;
 use crate::protocol::connect::ssl_request::SslRequest;
 use crate::protocol::Capabilities;
 
@@ -20,11 +21,14 @@ pub struct HandshakeResponse<'a> {
     /// Name of the SQL account which client wants to log in
     pub username: &'a str,
 
-    /// Authentication method used by the client
-    pub auth_plugin: Option<AuthPlugin>,
+    /// Name of the authentication method used by the client
+    pub auth_plugin: Option<&'a str>,
 
     /// Opaque authentication response
     pub auth_response: Option<&'a [u8]>,
+
+    /// Key-value attributes describing the client
+    pub connect_attrs: &'a IndexMap<String, String>,
 }
 
 impl Encode<'_, Capabilities> for HandshakeResponse<'_> {
@@ -63,11 +67,46 @@ impl Encode<'_, Capabilities> for HandshakeResponse<'_> {
         }
 
         if capabilities.contains(Capabilities::PLUGIN_AUTH) {
-            if let Some(plugin) = &self.auth_plugin {
-                buf.put_str_nul(plugin.name());
+            if let Some(plugin) = self.auth_plugin {
+                buf.put_str_nul(plugin);
             } else {
                 buf.push(0);
             }
         }
+
+        if capabilities.contains(Capabilities::CONNECT_ATTRS) {
+            let mut attrs = Vec::new();
+
+            for (key, value) in self.connect_attrs {
+                attrs.put_str_lenenc(key);
+                attrs.put_str_lenenc(value);
+            }
+
+            buf.put_bytes_lenenc(&attrs);
+        }
     }
 }
+
+#[test]
+fn test_encode_handshake_response_connect_attrs() {
+    let mut connect_attrs = IndexMap::new();
+    connect_attrs.insert("_client_name".to_owned(), "sqlx".to_owned());
+
+    let response = HandshakeResponse {
+        database: None,
+        max_packet_size: 1024,
+        collation: 45,
+        username: "root",
+        auth_plugin: None,
+        auth_response: None,
+        connect_attrs: &connect_attrs,
+    };
+
+    let mut buf = Vec::new();
+    response.encode_with(
+        &mut buf,
+        Capabilities::PROTOCOL_41 | Capabilities::CONNECT_ATTRS,
+    );
+
+    assert!(buf.ends_with(b"root\0\0\x12\x0c_client_name\x04sqlx"));
+}
@@ -56,17 +56,32 @@ impl Packet<Bytes> {
         T::decode_with(self.0, context)
     }
 
-    pub(crate) fn ok(self) -> Result<OkPacket, Error> {
-        self.decode()
+    pub(crate) fn ok(self, capabilities: Capabilities) -> Result<OkPacket, Error> {
+        self.decode_with(capabilities)
+    }
+
+    /// Returns `true` if this packet marks the end of a result set.
+    pub(crate) fn is_eof(&self, capabilities: Capabilities) -> bool {
+        // https://dev.mysql.com/doc/dev/mysql-server/latest/page_protocol_basic_ok_packet.html
+        // with `DEPRECATE_EOF`, the result set is terminated by an OK_Packet with a 0xfe header
+        // which may carry session state information, so it is not limited to 9 bytes
+        let max_len = if capabilities.contains(Capabilities::DEPRECATE_EOF) {
+            0xFF_FF_FF
+        } else {
+            9
+        };
+
+        !self.0.is_empty() && self.0[0] == 0xfe && self.0.len() < max_len
     }
 
     pub(crate) fn eof(self, capabilities: Capabilities) -> Result<EofPacket, Error> {
         if capabilities.contains(Capabilities::DEPRECATE_EOF) {
-            let ok = self.ok()?;
+            let ok = self.ok(capabilities)?;
 
             Ok(EofPacket {
                 warnings: ok.warnings,
                 status: ok.status,
+                session_state_changes: ok.session_state_changes,
             })
         } else {
             self.decode_with(capabilities)
@@ -4,6 +4,7 @@ use crate::error::Error;
 use crate::io::Decode;
 use crate::protocol::response::Status;
 use crate::protocol::Capabilities;
+use crate::MySqlSessionStateChange;
 
 /// Marks the end of a result set, returning status and warnings.
 ///
@@ -15,6 +16,8 @@ use crate::protocol::Capabilities;
 pub struct EofPacket {
     pub warnings: u16,
     pub status: Status,
+    /// Only present if the result set was terminated by an OK_Packet (`DEPRECATE_EOF`).
+    pub session_state_changes: Vec<MySqlSessionStateChange>,
 }
 
 impl Decode<'_, Capabilities> for EofPacket {
@@ -30,6 +33,10 @@ impl Decode<'_, Capabilities> for EofPacket {
         let warnings = buf.get_u16_le();
         let status = Status::from_bits_truncate(buf.get_u16_le());
 
-        Ok(Self { status, warnings })
+        Ok(Self {
+            status,
+            warnings,
+            session_state_changes: Vec::new(),
+        })
     }
 }
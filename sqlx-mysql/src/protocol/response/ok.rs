@@ -4,6 +4,8 @@ use crate::error::Error;
 use crate::io::Decode;
 use crate::io::MySqlBufExt;
 use crate::protocol::response::Status;
+use crate::protocol::Capabilities;
+use crate::MySqlSessionStateChange;
 
 /// Indicates successful completion of a previous command sent by the client.
 #[derive(Debug)]
@@ -12,10 +14,11 @@ pub struct OkPacket {
     pub last_insert_id: u64,
     pub status: Status,
     pub warnings: u16,
+    pub session_state_changes: Vec<MySqlSessionStateChange>,
 }
 
-impl Decode<'_> for OkPacket {
-    fn decode_with(mut buf: Bytes, _: ()) -> Result<Self, Error> {
+impl Decode<'_, Capabilities> for OkPacket {
+    fn decode_with(mut buf: Bytes, capabilities: Capabilities) -> Result<Self, Error> {
         let header = buf.get_u8();
         if header != 0 && header != 0xfe {
             return Err(err_protocol!(
@@ -29,24 +32,104 @@ impl Decode<'_> for OkPacket {
         let status = Status::from_bits_truncate(buf.get_u16_le());
         let warnings = buf.get_u16_le();
 
+        let mut session_state_changes = Vec::new();
+
+        // the server may omit the trailing fields entirely if they are empty
+        if capabilities.contains(Capabilities::SESSION_TRACK) && buf.has_remaining() {
+            let _info = buf.get_bytes_lenenc();
+
+            if status.contains(Status::SERVER_SESSION_STATE_CHANGED) && buf.has_remaining() {
+                let mut state = buf.get_bytes_lenenc();
+
+                while state.has_remaining() {
+                    let kind = state.get_u8();
+                    let data = state.get_bytes_lenenc();
+
+                    if let Some(change) = decode_session_state_change(kind, data)? {
+                        session_state_changes.push(change);
+                    }
+                }
+            }
+        }
+
         Ok(Self {
             affected_rows,
             last_insert_id,
             status,
             warnings,
+            session_state_changes,
         })
     }
 }
 
+// https://dev.mysql.com/doc/dev/mysql-server/latest/mysql__com_8h.html#a2e6fd2b1bd16e8e6f9a6fc3b1bd2b5dc
+// https://mariadb.com/kb/en/ok_packet/#session-change-type
+fn decode_session_state_change(
+    kind: u8,
+    mut data: Bytes,
+) -> Result<Option<MySqlSessionStateChange>, Error> {
+    Ok(Some(match kind {
+        // SESSION_TRACK_SYSTEM_VARIABLES
+        0 => MySqlSessionStateChange::SystemVariable {
+            name: data.get_str_lenenc()?,
+            value: data.get_str_lenenc()?,
+        },
+
+        // SESSION_TRACK_SCHEMA
+        1 => MySqlSessionStateChange::Schema(data.get_str_lenenc()?),
+
+        // SESSION_TRACK_STATE_CHANGE
+        2 => MySqlSessionStateChange::StateChange(&*data.get_bytes_lenenc() == b"1"),
+
+        // SESSION_TRACK_GTIDS
+        3 => {
+            // encoding specification; `0` is the only one defined,
+            // which is a length-encoded string of GTIDs
+            let _spec = data.get_u8();
+
+            MySqlSessionStateChange::Gtids(data.get_str_lenenc()?)
+        }
+
+        // SESSION_TRACK_TRANSACTION_CHARACTERISTICS
+        4 => MySqlSessionStateChange::TransactionCharacteristics(data.get_str_lenenc()?),
+
+        // SESSION_TRACK_TRANSACTION_STATE
+        5 => MySqlSessionStateChange::TransactionState(data.get_str_lenenc()?),
+
+        // ignore types added in future server versions
+        _ => return Ok(None),
+    }))
+}
+
 #[test]
 fn test_decode_ok_packet() {
     const DATA: &[u8] = b"\x00\x00\x00\x02@\x00\x00";
 
-    let p = OkPacket::decode(DATA.into()).unwrap();
+    let p = OkPacket::decode_with(DATA.into(), Capabilities::SESSION_TRACK).unwrap();
 
     assert_eq!(p.affected_rows, 0);
     assert_eq!(p.last_insert_id, 0);
     assert_eq!(p.warnings, 0);
     assert!(p.status.contains(Status::SERVER_STATUS_AUTOCOMMIT));
     assert!(p.status.contains(Status::SERVER_SESSION_STATE_CHANGED));
+    assert!(p.session_state_changes.is_empty());
+}
+
+#[test]
+fn test_decode_ok_packet_with_session_state() {
+    const DATA: &[u8] = b"\x00\x00\x00\x02@\x00\x00\x00\x44\x01\x05\x04test\x00\x0f\x0aautocommit\x03OFF\x03\x2a\x00\x283e11fa47-71ca-11e1-9e33-c80aa9429562:1-5";
+
+    let p = OkPacket::decode_with(DATA.into(), Capabilities::SESSION_TRACK).unwrap();
+
+    assert_eq!(
+        p.session_state_changes,
+        vec![
+            MySqlSessionStateChange::Schema("test".into()),
+            MySqlSessionStateChange::SystemVariable {
+                name: "autocommit".into(),
+                value: "OFF".into()
+            },
+            MySqlSessionStateChange::Gtids("3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5".into()),
+        ]
+    );
 }
@@ -95,6 +95,32 @@ pub enum ColumnType {
     Geometry = 0xff,
 }
 
+// https://mariadb.com/kb/en/result-set-packets/#column-definition-packet
+
+/// Extended type information sent by MariaDB when `MARIADB_CLIENT_EXTENDED_TYPE_INFO` is set.
+///
+/// These types are transmitted as one of the basic column types (usually a string),
+/// so this is the only way to tell them apart.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "offline", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum ExtendedType {
+    Json,
+    Uuid,
+    Inet4,
+    Inet6,
+}
+
+impl ExtendedType {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            ExtendedType::Json => "JSON",
+            ExtendedType::Uuid => "UUID",
+            ExtendedType::Inet4 => "INET4",
+            ExtendedType::Inet6 => "INET6",
+        }
+    }
+}
+
 // https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_com_query_response_text_resultset_column_definition.html
 // https://mariadb.com/kb/en/resultset/#column-definition-packet
 // https://dev.mysql.com/doc/internals/en/com-query-response.html#packet-Protocol::ColumnDefinition41
@@ -117,6 +143,7 @@ pub(crate) struct ColumnDefinition {
     pub(crate) flags: ColumnFlags,
     #[allow(unused)]
     decimals: u8,
+    pub(crate) extended_type: Option<ExtendedType>,
 }
 
 impl ColumnDefinition {
@@ -133,13 +160,34 @@ impl ColumnDefinition {
 }
 
 impl Decode<'_, Capabilities> for ColumnDefinition {
-    fn decode_with(mut buf: Bytes, _: Capabilities) -> Result<Self, Error> {
+    fn decode_with(mut buf: Bytes, capabilities: Capabilities) -> Result<Self, Error> {
         let catalog = buf.get_bytes_lenenc();
         let schema = buf.get_bytes_lenenc();
         let table_alias = buf.get_bytes_lenenc();
         let table = buf.get_bytes_lenenc();
         let alias = buf.get_bytes_lenenc();
         let name = buf.get_bytes_lenenc();
+
+        let mut extended_type = None;
+
+        if capabilities.contains(Capabilities::MARIADB_CLIENT_EXTENDED_TYPE_INFO) {
+            let mut info = buf.get_bytes_lenenc();
+
+            while info.has_remaining() {
+                // 0x00: type name, 0x01: format name
+                let key = info.get_u8();
+                let value = info.get_bytes_lenenc();
+
+                extended_type = match (key, &*value) {
+                    (0, b"uuid") => Some(ExtendedType::Uuid),
+                    (0, b"inet4") => Some(ExtendedType::Inet4),
+                    (0, b"inet6") => Some(ExtendedType::Inet6),
+                    (1, b"json") => Some(ExtendedType::Json),
+                    _ => extended_type,
+                };
+            }
+        }
+
         let _next_len = buf.get_uint_lenenc(); // always 0x0c
         let char_set = buf.get_u16_le();
         let max_size = buf.get_u32_le();
@@ -159,6 +207,7 @@ impl Decode<'_, Capabilities> for ColumnDefinition {
             r#type: ColumnType::try_from_u16(type_id)?,
             flags: ColumnFlags::from_bits_truncate(flags),
             decimals,
+            extended_type,
         })
     }
 }
@@ -262,3 +311,17 @@ impl ColumnType {
         })
     }
 }
+
+#[test]
+fn test_decode_column_definition_mariadb_extended_type_info() {
+    const DATA: &[u8] = b"\x03def\x04test\x01t\x01t\x02id\x02id\x06\x00\x04uuid\x0c\x2d\x00\x90\x00\x00\x00\xfe\x00\x00\x00\x00\x00";
+
+    let def =
+        ColumnDefinition::decode_with(DATA.into(), Capabilities::MARIADB_CLIENT_EXTENDED_TYPE_INFO)
+            .unwrap();
+
+    assert_eq!(def.name().unwrap(), "id");
+    assert_eq!(def.r#type, ColumnType::String);
+    assert_eq!(def.max_size, 144);
+    assert_eq!(def.extended_type, Some(ExtendedType::Uuid));
+}
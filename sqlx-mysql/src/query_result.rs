@@ -1,9 +1,15 @@
 use std::iter::{Extend, IntoIterator};
+use std::sync::Arc;
+
+use crate::MySqlColumn;
 
 #[derive(Debug, Default)]
 pub struct MySqlQueryResult {
     pub(super) rows_affected: u64,
     pub(super) last_insert_id: u64,
+    pub(super) columns: Arc<Vec<MySqlColumn>>,
+    pub(super) out_params: bool,
+    pub(super) session_state_changes: Vec<MySqlSessionStateChange>,
 }
 
 impl MySqlQueryResult {
@@ -14,6 +20,81 @@ impl MySqlQueryResult {
     pub fn rows_affected(&self) -> u64 {
         self.rows_affected
     }
+
+    /// The columns of the result set that ended with this result.
+    ///
+    /// When a query produces multiple result sets (e.g. a `CALL` to a stored procedure
+    /// or multiple statements in one query string), [`fetch_many()`][crate::executor::Executor::fetch_many]
+    /// yields a `MySqlQueryResult` after the rows of each result set. This is empty if the
+    /// statement did not produce a result set at all, such as an `INSERT` or the final
+    /// status of a `CALL`.
+    pub fn columns(&self) -> &[MySqlColumn] {
+        &self.columns
+    }
+
+    /// Returns `true` if the result set that ended with this result contained the values
+    /// of the `OUT` and `INOUT` parameters of a stored procedure.
+    ///
+    /// The server only sends `OUT` parameters for a `CALL` executed as a prepared statement,
+    /// i.e. with bind arguments. See [`MySqlConnection::fetch_out_params()`][crate::MySqlConnection::fetch_out_params].
+    pub fn is_out_params(&self) -> bool {
+        self.out_params
+    }
+
+    /// Changes to the session state reported by the server for this result.
+    ///
+    /// The server only reports the kinds of changes that are enabled by the
+    /// `session_track_*` system variables, for example:
+    ///
+    /// ```sql
+    /// SET SESSION session_track_gtids = OWN_GTID;
+    /// ```
+    ///
+    /// See the [MySQL documentation](https://dev.mysql.com/doc/refman/8.0/en/session-state-tracking.html)
+    /// for details.
+    pub fn session_state_changes(&self) -> &[MySqlSessionStateChange] {
+        &self.session_state_changes
+    }
+
+    /// The GTIDs of the transactions committed by this result, if reported by the server.
+    ///
+    /// Requires `session_track_gtids` to be set to `OWN_GTID` or `ALL_GTIDS`. The returned set can
+    /// be passed to `WAIT_FOR_EXECUTED_GTID_SET()` on a replica to ensure it has caught up.
+    pub fn last_gtids(&self) -> Option<&str> {
+        self.session_state_changes
+            .iter()
+            .rev()
+            .find_map(|change| match change {
+                MySqlSessionStateChange::Gtids(gtids) => Some(&**gtids),
+                _ => None,
+            })
+    }
+}
+
+/// A change to the session state, reported by the server with the result of a statement.
+///
+/// See [`MySqlQueryResult::session_state_changes()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MySqlSessionStateChange {
+    /// A tracked system variable was set (`session_track_system_variables`).
+    SystemVariable { name: String, value: String },
+
+    /// The default schema was changed (`session_track_schema`).
+    Schema(String),
+
+    /// Some part of the session state was changed (`session_track_state_change`).
+    StateChange(bool),
+
+    /// The GTIDs of committed transactions (`session_track_gtids`).
+    Gtids(String),
+
+    /// The characteristics of the current transaction, as a sequence of SQL statements
+    /// that restart it (`session_track_transaction_info = CHARACTERISTICS`).
+    TransactionCharacteristics(String),
+
+    /// The state of the current transaction (`session_track_transaction_info`).
+    TransactionState(String),
 }
 
 impl Extend<MySqlQueryResult> for MySqlQueryResult {
@@ -21,6 +102,8 @@ impl Extend<MySqlQueryResult> for MySqlQueryResult {
         for elem in iter {
             self.rows_affected += elem.rows_affected;
             self.last_insert_id = elem.last_insert_id;
+            self.session_state_changes
+                .extend(elem.session_state_changes);
         }
     }
 }
@@ -2,7 +2,7 @@ use std::fmt::{self, Display, Formatter};
 
 pub(crate) use sqlx_core::type_info::*;
 
-use crate::protocol::text::{ColumnDefinition, ColumnFlags, ColumnType};
+use crate::protocol::text::{ColumnDefinition, ColumnFlags, ColumnType, ExtendedType};
 
 /// Type information for a MySql type.
 #[derive(Debug, Clone)]
@@ -15,6 +15,10 @@ pub struct MySqlTypeInfo {
     // [max_size] for integer types, this is (M) in BIT(M) or TINYINT(M)
     #[cfg_attr(feature = "offline", serde(default))]
     pub(crate) max_size: Option<u32>,
+
+    // [extended_type] MariaDB-specific types which are transmitted as a basic type
+    #[cfg_attr(feature = "offline", serde(default))]
+    pub(crate) extended_type: Option<ExtendedType>,
 }
 
 impl MySqlTypeInfo {
@@ -24,6 +28,7 @@ impl MySqlTypeInfo {
             flags: ColumnFlags::BINARY,
             char_set: 63,
             max_size: None,
+            extended_type: None,
         }
     }
 
@@ -34,11 +39,18 @@ impl MySqlTypeInfo {
             flags: ColumnFlags::BINARY,
             char_set: 63,
             max_size: None,
+            extended_type: None,
         }
     }
 
     #[doc(hidden)]
     pub fn __type_feature_gate(&self) -> Option<&'static str> {
+        match self.extended_type {
+            Some(ExtendedType::Json) => return Some("json"),
+            Some(ExtendedType::Uuid) => return Some("uuid"),
+            _ => {}
+        }
+
         match self.r#type {
             ColumnType::Date | ColumnType::Time | ColumnType::Timestamp | ColumnType::Datetime => {
                 Some("time")
@@ -57,6 +69,7 @@ impl MySqlTypeInfo {
             flags: column.flags,
             char_set: column.char_set,
             max_size: Some(column.max_size),
+            extended_type: column.extended_type,
         }
     }
 }
@@ -73,13 +86,16 @@ impl TypeInfo for MySqlTypeInfo {
     }
 
     fn name(&self) -> &str {
-        self.r#type.name(self.char_set, self.flags, self.max_size)
+        match self.extended_type {
+            Some(extended_type) => extended_type.name(),
+            None => self.r#type.name(self.char_set, self.flags, self.max_size),
+        }
     }
 }
 
 impl PartialEq<MySqlTypeInfo> for MySqlTypeInfo {
     fn eq(&self, other: &MySqlTypeInfo) -> bool {
-        if self.r#type != other.r#type {
+        if self.r#type != other.r#type || self.extended_type != other.extended_type {
             return false;
         }
 
@@ -15,6 +15,7 @@ impl Type<MySql> for bool {
             char_set: 63,
             max_size: Some(1),
             r#type: ColumnType::Tiny,
+            extended_type: None,
         }
     }
 
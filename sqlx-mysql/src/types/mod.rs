@@ -62,7 +62,10 @@
 //! | Rust type                             | MySQL type(s)                                        |
 //! |---------------------------------------|------------------------------------------------------|
 //! | `uuid::Uuid`                          | BYTE(16), VARCHAR, CHAR, TEXT                        |
-//! | `uuid::fmt::Hyphenated`               | CHAR(36)                                             |
+//! | `uuid::fmt::Hyphenated`               | CHAR(36), UUID (MariaDB)                             |
+//!
+//! MariaDB's native `UUID` type is transmitted as text, so `uuid::Uuid` can decode it but
+//! binding one (as 16 raw bytes) will not work; bind `Uuid::hyphenated()` instead.
 //!
 //! ### [`json`](https://crates.io/crates/serde_json)
 //!
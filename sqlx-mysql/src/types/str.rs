@@ -21,6 +21,7 @@ impl Type<MySql> for str {
             char_set: COLLATE_UTF8MB4_UNICODE_CI, // utf8mb4_unicode_ci
             flags: ColumnFlags::empty(),
             max_size: None,
+            extended_type: None,
         }
     }
 
@@ -12,6 +12,7 @@ fn uint_type_info(ty: ColumnType) -> MySqlTypeInfo {
         flags: ColumnFlags::BINARY | ColumnFlags::UNSIGNED,
         char_set: 63,
         max_size: None,
+        extended_type: None,
     }
 }
 
@@ -4,6 +4,7 @@ use crate::decode::Decode;
 use crate::encode::{Encode, IsNull};
 use crate::error::BoxDynError;
 use crate::io::MySqlBufMutExt;
+use crate::protocol::text::ExtendedType;
 use crate::types::Type;
 use crate::{MySql, MySqlTypeInfo, MySqlValueRef};
 
@@ -13,10 +14,13 @@ impl Type<MySql> for Uuid {
     }
 
     fn compatible(ty: &MySqlTypeInfo) -> bool {
-        <&[u8] as Type<MySql>>::compatible(ty)
+        ty.extended_type == Some(ExtendedType::Uuid) || <&[u8] as Type<MySql>>::compatible(ty)
     }
 }
 
+/// Encodes the UUID as 16 raw bytes, e.g. for a `BINARY(16)` column.
+///
+/// MariaDB's native `UUID` type expects text; bind [`Uuid::hyphenated()`] for it instead.
 impl Encode<'_, MySql> for Uuid {
     fn encode_by_ref(&self, buf: &mut Vec<u8>) -> IsNull {
         buf.put_bytes_lenenc(self.as_bytes());
@@ -27,6 +31,13 @@ impl Encode<'_, MySql> for Uuid {
 
 impl Decode<'_, MySql> for Uuid {
     fn decode(value: MySqlValueRef<'_>) -> Result<Self, BoxDynError> {
+        // MariaDB's native `UUID` type is transmitted as text
+        if value.type_info.extended_type == Some(ExtendedType::Uuid) {
+            let text = <&str as Decode<MySql>>::decode(value)?;
+
+            return Uuid::parse_str(text).map_err(Into::into);
+        }
+
         // delegate to the &[u8] type to decode from MySQL
         let bytes = <&[u8] as Decode<MySql>>::decode(value)?;
 
@@ -41,10 +52,11 @@ impl Type<MySql> for Hyphenated {
     }
 
     fn compatible(ty: &MySqlTypeInfo) -> bool {
-        <&str as Type<MySql>>::compatible(ty)
+        ty.extended_type == Some(ExtendedType::Uuid) || <&str as Type<MySql>>::compatible(ty)
     }
 }
 
+/// Encodes the UUID as text, e.g. for a `CHAR(36)` column or MariaDB's native `UUID` type.
 impl Encode<'_, MySql> for Hyphenated {
     fn encode_by_ref(&self, buf: &mut Vec<u8>) -> IsNull {
         buf.put_str_lenenc(&self.to_string());
@@ -1,8 +1,8 @@
 use crate::error::Error;
 use crate::ext::ustr::UStr;
 use crate::message::{ParameterDescription, RowDescription};
-use crate::query_as::query_as;
-use crate::query_scalar::{query_scalar, query_scalar_with};
+use crate::query_as::{query_as, query_as_with};
+use crate::query_scalar::query_scalar;
 use crate::statement::PgStatementMetadata;
 use crate::type_info::{PgCustomType, PgType, PgTypeKind};
 use crate::types::Json;
@@ -10,6 +10,7 @@ use crate::types::Oid;
 use crate::HashMap;
 use crate::{PgArguments, PgColumn, PgConnection, PgTypeInfo};
 use futures_core::future::BoxFuture;
+use std::collections::HashSet;
 use std::fmt::Write;
 use std::sync::Arc;
 
@@ -365,7 +366,9 @@ WHERE rngtypid = $1
             return Ok(vec![]);
         }
 
-        let mut nullable_query = String::from("SELECT NOT pg_attribute.attnotnull FROM (VALUES ");
+        let mut nullable_query = String::from(
+            "SELECT NOT pg_attribute.attnotnull, nspname, relname, attname FROM (VALUES ",
+        );
         let mut args = PgArguments::default();
 
         for (i, (column, bind)) in meta.columns.iter().zip((1..).step_by(3)).enumerate() {
@@ -392,13 +395,21 @@ WHERE rngtypid = $1
                 ON table_id IS NOT NULL \
                AND attrelid = table_id \
                AND attnum = col_idx \
+            LEFT JOIN pg_catalog.pg_class ON pg_class.oid = attrelid \
+            LEFT JOIN pg_catalog.pg_namespace ON pg_namespace.oid = relnamespace \
             ORDER BY col.idx",
         );
 
-        let mut nullables = query_scalar_with::<_, Option<bool>, _>(&nullable_query, args)
+        // the nullability of each column, and the schema, relation and name it is read from
+        type CatalogColumn = (Option<bool>, Option<String>, Option<String>, Option<String>);
+
+        let columns: Vec<CatalogColumn> = query_as_with(&nullable_query, args)
             .fetch_all(&mut *self)
             .await?;
 
+        let mut nullables: Vec<Option<bool>> =
+            columns.iter().map(|(nullable, ..)| *nullable).collect();
+
         // If the server is CockroachDB or Materialize, skip this step (#1248).
         if !self.stream.parameter_statuses.contains_key("crdb_version")
             && !self.stream.parameter_statuses.contains_key("mz_version")
@@ -413,13 +424,28 @@ WHERE rngtypid = $1
             }
         }
 
+        // the overrides set by the user win over anything we inferred
+        for (nullable, column) in nullables.iter_mut().zip(&columns) {
+            if let (_, Some(schema), Some(relation), Some(column)) = column {
+                if let Some(nullability) = self.nullability_overrides.iter().rev().find(|o| {
+                    o.column == *column
+                        && (o.relation == *relation
+                            || o.relation.split_once('.') == Some((schema, relation)))
+                }) {
+                    *nullable = Some(nullability.nullable);
+                }
+            }
+        }
+
         Ok(nullables)
     }
 
     /// Infer nullability for columns of this statement using EXPLAIN VERBOSE.
     ///
-    /// This currently only marks columns that are on the inner half of an outer join
-    /// and returns `None` for all others.
+    /// Columns on the nullable side of an outer join, or grouped by grouping sets, are marked
+    /// nullable. Columns which can be followed through views, subqueries and CTEs back to a
+    /// `NOT NULL` column of a table are marked not null, as are `count()` aggregates.
+    /// All others are `None`.
     async fn nullables_from_explain(
         &mut self,
         stmt_id: Oid,
@@ -447,46 +473,275 @@ WHERE rngtypid = $1
             explain += ")";
         }
 
-        let (Json([explain]),): (Json<[Explain; 1]>,) = query_as(&explain).fetch_one(self).await?;
+        let (Json([explain]),): (Json<[Explain; 1]>,) =
+            query_as(&explain).fetch_one(&mut *self).await?;
 
         let mut nullables = Vec::new();
 
-        if let Explain::Plan(
-            plan @ Plan {
-                output: Some(outputs),
-                ..
-            },
-        ) = &explain
-        {
-            nullables.resize(outputs.len(), None);
-            visit_plan(&plan, outputs, &mut nullables);
+        if let Explain::Plan(plan) = &explain {
+            let Some(outputs) = &plan.output else {
+                return Ok(nullables);
+            };
+
+            let mut nullable_outputs = HashSet::new();
+            visit_plan(plan, false, &mut nullable_outputs);
+
+            // the columns of tables whose `NOT NULL` constraint decides the nullability of an
+            // output, looked up in a single query once every output has been followed
+            let mut table_columns = Vec::new();
+
+            for (idx, output) in outputs.iter().enumerate() {
+                let nullable = if nullable_outputs.contains(output.as_str()) {
+                    // N.B. this may produce false positives but those don't cause runtime errors
+                    Some(true)
+                } else if output.starts_with("count(") {
+                    Some(false)
+                } else {
+                    match column_origin(plan, output, &nullable_outputs) {
+                        Some(Origin::Nullable) => Some(true),
+                        Some(Origin::Column {
+                            schema,
+                            relation,
+                            column,
+                        }) => {
+                            table_columns.push((idx, schema, relation, column));
+                            None
+                        }
+                        None => None,
+                    }
+                };
+
+                nullables.push(nullable);
+            }
+
+            for idx in self.not_null_columns(&table_columns).await? {
+                nullables[idx] = Some(false);
+            }
         }
 
         Ok(nullables)
     }
-}
 
-fn visit_plan(plan: &Plan, outputs: &[String], nullables: &mut Vec<Option<bool>>) {
-    if let Some(plan_outputs) = &plan.output {
-        // all outputs of a Full Join must be marked nullable
-        // otherwise, all outputs of the inner half of an outer join must be marked nullable
-        if plan.join_type.as_deref() == Some("Full")
-            || plan.parent_relation.as_deref() == Some("Inner")
-        {
-            for output in plan_outputs {
-                if let Some(i) = outputs.iter().position(|o| o == output) {
-                    // N.B. this may produce false positives but those don't cause runtime errors
-                    nullables[i] = Some(true);
-                }
+    /// Return the indices of those of `columns`, given as `(index, schema, relation, column)`,
+    /// which are declared `NOT NULL`.
+    async fn not_null_columns(
+        &mut self,
+        columns: &[(usize, &str, &str, String)],
+    ) -> Result<Vec<usize>, Error> {
+        if columns.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut not_null_query = String::from("SELECT col.idx FROM (VALUES ");
+        let mut args = PgArguments::default();
+
+        for ((idx, schema, relation, column), bind) in columns.iter().zip((1..).step_by(4)) {
+            if !args.buffer.is_empty() {
+                not_null_query += ", ";
             }
+
+            let _ = write!(
+                not_null_query,
+                "(${}::int4, ${}::text, ${}::text, ${}::text)",
+                bind,
+                bind + 1,
+                bind + 2,
+                bind + 3
+            );
+
+            args.add(*idx as i32);
+            args.add(*schema);
+            args.add(*relation);
+            args.add(column.as_str());
+        }
+
+        not_null_query.push_str(
+            ") as col(idx, schema_name, relation_name, column_name) \
+            INNER JOIN pg_catalog.pg_namespace ON nspname = schema_name \
+            INNER JOIN pg_catalog.pg_class \
+                ON relnamespace = pg_namespace.oid \
+               AND relname = relation_name \
+            INNER JOIN pg_catalog.pg_attribute \
+                ON attrelid = pg_class.oid \
+               AND attname = column_name \
+               AND NOT attisdropped \
+            WHERE attnotnull",
+        );
+
+        let not_null: Vec<(i32,)> = query_as_with(&not_null_query, args)
+            .fetch_all(&mut *self)
+            .await?;
+
+        Ok(not_null.into_iter().map(|(idx,)| idx as usize).collect())
+    }
+}
+
+/// Collect the outputs of every node of `plan` which may be null although the columns they
+/// read are not: those on the nullable side of an outer join, or grouped by grouping sets.
+fn visit_plan<'p>(plan: &'p Plan, nullable: bool, outputs: &mut HashSet<&'p str>) {
+    if nullable || plan.grouping_sets.is_some() {
+        outputs.extend(plan.output.iter().flatten().map(String::as_str));
+    }
+
+    for child in plan.plans.iter().flatten() {
+        // a Left join keeps every row of its outer half, and a Right join of its inner half
+        let nullable = nullable
+            || matches!(
+                (plan.join_type.as_deref(), child.parent_relation.as_deref()),
+                (Some("Full"), _) | (Some("Left"), Some("Inner")) | (Some("Right"), Some("Outer"))
+            );
+
+        visit_plan(child, nullable, outputs);
+    }
+}
+
+/// Where the value of an output of a plan comes from.
+enum Origin<'p> {
+    /// The output is nullable wherever it comes from.
+    Nullable,
+    /// The output is a column of a table.
+    Column {
+        schema: &'p str,
+        relation: &'p str,
+        column: String,
+    },
+}
+
+/// Follow the output `expr` of a node of `root` through subqueries and CTEs back to the column
+/// of the table it reads, if it is a plain column reference.
+///
+/// Set operations, such as `UNION` and recursive CTEs, and aliases naming different
+/// relations, are not followed.
+fn column_origin<'p>(
+    root: &'p Plan,
+    expr: &str,
+    nullable_outputs: &HashSet<&str>,
+) -> Option<Origin<'p>> {
+    if nullable_outputs.contains(expr) {
+        return Some(Origin::Nullable);
+    }
+
+    let (alias, column) = split_column_ref(expr)?;
+
+    let mut scans = Vec::new();
+    find_scans(root, &alias, &mut scans);
+
+    let (scan, rest) = scans.split_first()?;
+
+    if let (Some(schema), Some(relation)) = (&scan.schema, &scan.relation_name) {
+        // the same alias may be scanned twice, e.g. by `UPDATE .. FROM`
+        let same_relation = rest.iter().all(|other| {
+            other.schema.as_ref() == Some(schema) && other.relation_name.as_ref() == Some(relation)
+        });
+
+        return same_relation.then_some(Origin::Column {
+            schema,
+            relation,
+            column,
+        });
+    }
+
+    if !rest.is_empty() {
+        return None;
+    }
+
+    let source = match scan.node_type.as_deref()? {
+        "CTE Scan" => {
+            let name = format!("CTE {}", scan.cte_name.as_deref()?);
+            find_subplan(root, &name)?
         }
+        "Subquery Scan" => scan.plans.as_deref()?.first()?,
+        _ => return None,
+    };
+
+    if is_set_operation(source) {
+        return None;
+    }
+
+    let mut outputs = source.output.iter().flatten().filter(|output| {
+        split_column_ref(output).is_some_and(|(_, source_column)| source_column == column)
+    });
+
+    match (outputs.next(), outputs.next()) {
+        (Some(output), None) => column_origin(root, output, nullable_outputs),
+        _ => None,
+    }
+}
+
+/// Find every node of `plan` scanning a relation, subquery or CTE under `alias`.
+fn find_scans<'p>(plan: &'p Plan, alias: &str, scans: &mut Vec<&'p Plan>) {
+    if plan.alias.as_deref() == Some(alias) {
+        scans.push(plan);
+    }
+
+    for child in plan.plans.iter().flatten() {
+        find_scans(child, alias, scans);
     }
+}
+
+/// Find the node of `plan` computing the subplan `name`, such as `CTE foo` for a CTE.
+fn find_subplan<'p>(plan: &'p Plan, name: &str) -> Option<&'p Plan> {
+    if plan.subplan_name.as_deref() == Some(name) {
+        return Some(plan);
+    }
+
+    plan.plans
+        .iter()
+        .flatten()
+        .find_map(|child| find_subplan(child, name))
+}
+
+/// Whether the rows of `plan` may come from more than one query, as they do for `UNION`.
+fn is_set_operation(plan: &Plan) -> bool {
+    matches!(
+        plan.node_type.as_deref(),
+        Some("Append" | "Merge Append" | "SetOp" | "Recursive Union")
+    ) || plan
+        .plans
+        .iter()
+        .flatten()
+        // the rows of a subquery or CTE scanned by this node are checked when it is followed
+        .filter(|child| {
+            !matches!(
+                child.parent_relation.as_deref(),
+                Some("InitPlan" | "SubPlan" | "Subquery")
+            )
+        })
+        .any(is_set_operation)
+}
+
+/// Split an output of a plan referencing a column, `alias.column`, into its two identifiers.
+fn split_column_ref(expr: &str) -> Option<(String, String)> {
+    let (alias, rest) = parse_ident(expr)?;
+    let (column, rest) = parse_ident(rest.strip_prefix('.')?)?;
+
+    rest.is_empty().then_some((alias, column))
+}
+
+/// Parse an identifier at the start of `s`, quoted or not, returning it and the rest of `s`.
+fn parse_ident(s: &str) -> Option<(String, &str)> {
+    let Some(mut rest) = s.strip_prefix('"') else {
+        let end = s
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+            .unwrap_or(s.len());
+
+        return (end > 0).then(|| (s[..end].to_owned(), &s[end..]));
+    };
+
+    let mut ident = String::new();
+
+    loop {
+        let end = rest.find('"')?;
+        ident.push_str(&rest[..end]);
+        rest = &rest[end + 1..];
 
-    if let Some(plans) = &plan.plans {
-        if let Some("Left") | Some("Right") = plan.join_type.as_deref() {
-            for plan in plans {
-                visit_plan(plan, outputs, nullables);
+        // a quote inside a quoted identifier is doubled
+        match rest.strip_prefix('"') {
+            Some(after) => {
+                ident.push('"');
+                rest = after;
             }
+            None => return Some((ident, rest)),
         }
     }
 }
@@ -494,7 +749,7 @@ fn visit_plan(plan: &Plan, outputs: &[String], nullables: &mut Vec<Option<bool>>
 #[derive(serde::Deserialize)]
 enum Explain {
     /// {"Plan": ...} -- returned for most statements
-    Plan(Plan),
+    Plan(Box<Plan>),
     /// The string "Utility Statement" -- returned for
     /// a CALL statement
     #[serde(rename = "Utility Statement")]
@@ -503,6 +758,8 @@ enum Explain {
 
 #[derive(serde::Deserialize)]
 struct Plan {
+    #[serde(rename = "Node Type")]
+    node_type: Option<String>,
     #[serde(rename = "Join Type")]
     join_type: Option<String>,
     #[serde(rename = "Parent Relationship")]
@@ -511,4 +768,16 @@ struct Plan {
     output: Option<Vec<String>>,
     #[serde(rename = "Plans")]
     plans: Option<Vec<Plan>>,
+    #[serde(rename = "Schema")]
+    schema: Option<String>,
+    #[serde(rename = "Relation Name")]
+    relation_name: Option<String>,
+    #[serde(rename = "Alias")]
+    alias: Option<String>,
+    #[serde(rename = "CTE Name")]
+    cte_name: Option<String>,
+    #[serde(rename = "Subplan Name")]
+    subplan_name: Option<String>,
+    #[serde(rename = "Grouping Sets")]
+    grouping_sets: Option<serde::de::IgnoredAny>,
 }
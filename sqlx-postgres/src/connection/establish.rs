@@ -147,6 +147,9 @@ impl PgConnection {
             cache_type_oid: HashMap::new(),
             cache_type_info: HashMap::new(),
             log_settings: options.log_settings.clone(),
+            nullability_overrides: options.nullability_overrides.clone(),
+            #[cfg(feature = "migrate")]
+            migrations_table: sqlx_core::migrate::Migrator::DEFAULT_TABLE_NAME.into(),
         })
     }
 }
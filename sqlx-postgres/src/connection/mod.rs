@@ -12,6 +12,7 @@ use crate::io::Decode;
 use crate::message::{
     Close, Message, MessageFormat, Query, ReadyForQuery, Terminate, TransactionStatus,
 };
+use crate::options::NullabilityOverride;
 use crate::statement::PgStatementMetadata;
 use crate::transaction::Transaction;
 use crate::types::Oid;
@@ -64,6 +65,13 @@ pub struct PgConnection {
     pub(crate) transaction_depth: usize,
 
     log_settings: LogSettings,
+
+    // nullability of columns set by the user, taking precedence over what we infer
+    nullability_overrides: Vec<NullabilityOverride>,
+
+    // table applied migrations are recorded in, set by the `Migrator`
+    #[cfg(feature = "migrate")]
+    pub(crate) migrations_table: std::borrow::Cow<'static, str>,
 }
 
 impl PgConnection {
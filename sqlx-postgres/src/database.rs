@@ -8,6 +8,7 @@ use crate::{
 pub(crate) use sqlx_core::database::{
     Database, HasArguments, HasStatement, HasStatementCache, HasValueRef,
 };
+use sqlx_core::insertable::InsertDialect;
 
 /// PostgreSQL database driver.
 #[derive(Debug)]
@@ -54,3 +55,5 @@ impl<'q> HasStatement<'q> for Postgres {
 }
 
 impl HasStatementCache for Postgres {}
+
+impl InsertDialect for Postgres {}
@@ -15,6 +15,7 @@ use crate::query::query;
 use crate::query_as::query_as;
 use crate::query_scalar::query_scalar;
 use crate::{PgConnectOptions, PgConnection, Postgres};
+use sqlx_core::insertable::InsertDialect;
 
 fn parse_for_maintenance(url: &str) -> Result<(PgConnectOptions, String), Error> {
     let mut options = PgConnectOptions::from_str(url)?;
@@ -88,12 +89,19 @@ impl MigrateDatabase for Postgres {
 }
 
 impl Migrate for PgConnection {
+    fn set_migrations_table(&mut self, table_name: &str) -> Result<(), MigrateError> {
+        self.migrations_table = table_name.to_owned().into();
+        Ok(())
+    }
+
     fn ensure_migrations_table(&mut self) -> BoxFuture<'_, Result<(), MigrateError>> {
         Box::pin(async move {
+            let table_name = Postgres::quote_identifier(&self.migrations_table);
+
             // language=SQL
-            self.execute(
+            self.execute(&*format!(
                 r#"
-CREATE TABLE IF NOT EXISTS _sqlx_migrations (
+CREATE TABLE IF NOT EXISTS {} (
     version BIGINT PRIMARY KEY,
     description TEXT NOT NULL,
     installed_on TIMESTAMPTZ NOT NULL DEFAULT now(),
@@ -102,7 +110,8 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
     execution_time BIGINT NOT NULL
 );
                 "#,
-            )
+                table_name
+            ))
             .await?;
 
             Ok(())
@@ -111,10 +120,13 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
 
     fn dirty_version(&mut self) -> BoxFuture<'_, Result<Option<i64>, MigrateError>> {
         Box::pin(async move {
+            let table_name = Postgres::quote_identifier(&self.migrations_table);
+
             // language=SQL
-            let row: Option<(i64,)> = query_as(
-                "SELECT version FROM _sqlx_migrations WHERE success = false ORDER BY version LIMIT 1",
-            )
+            let row: Option<(i64,)> = query_as(&format!(
+                "SELECT version FROM {} WHERE success = false ORDER BY version LIMIT 1",
+                table_name
+            ))
             .fetch_optional(self)
             .await?;
 
@@ -126,11 +138,15 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
         &mut self,
     ) -> BoxFuture<'_, Result<Vec<AppliedMigration>, MigrateError>> {
         Box::pin(async move {
+            let table_name = Postgres::quote_identifier(&self.migrations_table);
+
             // language=SQL
-            let rows: Vec<(i64, Vec<u8>)> =
-                query_as("SELECT version, checksum FROM _sqlx_migrations ORDER BY version")
-                    .fetch_all(self)
-                    .await?;
+            let rows: Vec<(i64, Vec<u8>)> = query_as(&format!(
+                "SELECT version, checksum FROM {} ORDER BY version",
+                table_name
+            ))
+            .fetch_all(self)
+            .await?;
 
             let migrations = rows
                 .into_iter()
@@ -185,6 +201,8 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
         migration: &'m Migration,
     ) -> BoxFuture<'m, Result<Duration, MigrateError>> {
         Box::pin(async move {
+            let table_name = Postgres::quote_identifier(&self.migrations_table);
+
             let mut tx = self.begin().await?;
             let start = Instant::now();
 
@@ -196,12 +214,13 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
             let _ = tx.execute(&*migration.sql).await?;
 
             // language=SQL
-            let _ = query(
+            let _ = query(&format!(
                 r#"
-    INSERT INTO _sqlx_migrations ( version, description, success, checksum, execution_time )
+    INSERT INTO {} ( version, description, success, checksum, execution_time )
     VALUES ( $1, $2, TRUE, $3, -1 )
                 "#,
-            )
+                table_name
+            ))
             .bind(migration.version)
             .bind(&*migration.description)
             .bind(&*migration.checksum)
@@ -217,13 +236,14 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
             let elapsed = start.elapsed();
 
             // language=SQL
-            let _ = query(
+            let _ = query(&format!(
                 r#"
-    UPDATE _sqlx_migrations
+    UPDATE {}
     SET execution_time = $1
     WHERE version = $2
                 "#,
-            )
+                table_name
+            ))
             .bind(elapsed.as_nanos() as i64)
             .bind(migration.version)
             .execute(self)
@@ -238,6 +258,8 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
         migration: &'m Migration,
     ) -> BoxFuture<'m, Result<Duration, MigrateError>> {
         Box::pin(async move {
+            let table_name = Postgres::quote_identifier(&self.migrations_table);
+
             // Use a single transaction for the actual migration script and the essential bookeeping so we never
             // execute migrations twice. See https://github.com/launchbadge/sqlx/issues/1966.
             let mut tx = self.begin().await?;
@@ -246,7 +268,7 @@ CREATE TABLE IF NOT EXISTS _sqlx_migrations (
             let _ = tx.execute(&*migration.sql).await?;
 
             // language=SQL
-            let _ = query(r#"DELETE FROM _sqlx_migrations WHERE version = $1"#)
+            let _ = query(&format!(r#"DELETE FROM {} WHERE version = $1"#, table_name))
                 .bind(migration.version)
                 .execute(&mut *tx)
                 .await?;
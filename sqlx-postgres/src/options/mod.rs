@@ -94,6 +94,16 @@ pub struct PgConnectOptions {
     pub(crate) log_settings: LogSettings,
     pub(crate) extra_float_digits: Option<Cow<'static, str>>,
     pub(crate) options: Option<String>,
+    pub(crate) nullability_overrides: Vec<NullabilityOverride>,
+}
+
+/// Whether a column of a table or view may be `NULL`, as set by
+/// [`PgConnectOptions::column_nullability`].
+#[derive(Debug, Clone)]
+pub(crate) struct NullabilityOverride {
+    pub(crate) relation: String,
+    pub(crate) column: String,
+    pub(crate) nullable: bool,
 }
 
 impl Default for PgConnectOptions {
@@ -160,6 +170,7 @@ impl PgConnectOptions {
             extra_float_digits: Some("3".into()),
             log_settings: Default::default(),
             options: var("PGOPTIONS").ok(),
+            nullability_overrides: Vec::new(),
         }
     }
 
@@ -473,6 +484,35 @@ impl PgConnectOptions {
         self
     }
 
+    /// Sets whether `column` of the table or view `relation` may be `NULL`, overriding the
+    /// nullability inferred for it when describing a statement.
+    ///
+    /// The relation may be qualified with its schema, as `schema.relation`; otherwise it matches
+    /// a relation of that name in any schema. This is mostly useful for the columns of views,
+    /// which Postgres reports as nullable whenever their definition cannot be followed back to
+    /// a `NOT NULL` column.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use sqlx_core::postgres::PgConnectOptions;
+    /// let options = PgConnectOptions::new()
+    ///     .column_nullability("public.active_users", "id", false);
+    /// ```
+    pub fn column_nullability(
+        mut self,
+        relation: impl Into<String>,
+        column: impl Into<String>,
+        nullable: bool,
+    ) -> Self {
+        self.nullability_overrides.push(NullabilityOverride {
+            relation: relation.into(),
+            column: column.into(),
+            nullable,
+        });
+        self
+    }
+
     /// We try using a socket if hostname starts with `/` or if socket parameter
     /// is specified.
     pub(crate) fn fetch_socket(&self) -> Option<String> {
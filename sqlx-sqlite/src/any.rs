@@ -89,7 +89,6 @@ impl AnyConnectionBackend for SqliteConnection {
         Box::pin(
             self.worker
                 .execute(query, args, self.row_channel_size, persistent)
-                .map_ok(flume::Receiver::into_stream)
                 .try_flatten_stream()
                 .map(
                     move |res: sqlx_core::Result<Either<SqliteQueryResult, SqliteRow>>| match res? {
@@ -112,7 +111,6 @@ impl AnyConnectionBackend for SqliteConnection {
             let mut stream = self
                 .worker
                 .execute(query, args, self.row_channel_size, persistent)
-                .map_ok(flume::Receiver::into_stream)
                 .await?;
             futures_util::pin_mut!(stream);
 
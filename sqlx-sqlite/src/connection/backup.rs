@@ -0,0 +1,267 @@
+use std::os::raw::c_int;
+use std::ptr::NonNull;
+use std::time::Duration;
+
+use libsqlite3_sys::{
+    sqlite3_backup, sqlite3_backup_finish, sqlite3_backup_init, sqlite3_backup_pagecount,
+    sqlite3_backup_remaining, sqlite3_backup_step, SQLITE_BUSY, SQLITE_DONE, SQLITE_LOCKED,
+    SQLITE_OK,
+};
+
+use crate::connection::establish::EstablishParams;
+use crate::connection::handle::ConnectionHandle;
+use crate::connection::ConnectionState;
+use crate::error::Error;
+use crate::{SqliteConnectOptions, SqliteError};
+
+/// Options for [`SqliteConnection::backup_to()`][crate::SqliteConnection::backup_to] and
+/// [`SqliteConnection::restore_from()`][crate::SqliteConnection::restore_from].
+#[derive(Debug, Clone)]
+pub struct SqliteBackupOptions {
+    pub(crate) pages_per_step: c_int,
+    pub(crate) step_sleep: Duration,
+    pub(crate) busy_retries: u32,
+}
+
+impl Default for SqliteBackupOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SqliteBackupOptions {
+    /// Construct the default options: copy 100 pages per step, sleep for 10 milliseconds
+    /// between steps and give up after 100 consecutive steps fail because a database is locked.
+    pub fn new() -> Self {
+        Self {
+            pages_per_step: 100,
+            step_sleep: Duration::from_millis(10),
+            busy_retries: 100,
+        }
+    }
+
+    /// Set the number of pages to copy in each step; values less than 1 are treated as 1.
+    ///
+    /// The source database is only locked while a step is running, so smaller steps let other
+    /// connections write to it more often while the backup is in progress.
+    pub fn pages_per_step(mut self, pages: u32) -> Self {
+        self.pages_per_step = pages.clamp(1, c_int::MAX as u32) as c_int;
+        self
+    }
+
+    /// Copy the whole database in a single step.
+    pub fn all_pages(mut self) -> Self {
+        self.pages_per_step = -1;
+        self
+    }
+
+    /// Set how long to sleep between steps, giving other connections a chance to access the
+    /// source database.
+    ///
+    /// The sleep happens on the task polling the backup, not on the worker thread, so
+    /// other commands sent to this connection can run in between.
+    pub fn step_sleep(mut self, sleep: Duration) -> Self {
+        self.step_sleep = sleep;
+        self
+    }
+
+    /// Set how many consecutive steps may fail because either database is locked by another
+    /// connection (`SQLITE_BUSY` or `SQLITE_LOCKED`) before the backup is abandoned with
+    /// that error.
+    ///
+    /// Each retry waits for [`step_sleep`][Self::step_sleep] first.
+    pub fn busy_retries(mut self, retries: u32) -> Self {
+        self.busy_retries = retries;
+        self
+    }
+}
+
+/// The progress of an online backup, reported after each step.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SqliteBackupProgress {
+    remaining: u32,
+    page_count: u32,
+}
+
+impl SqliteBackupProgress {
+    /// The number of pages still to be copied.
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    /// The total number of pages in the source database.
+    ///
+    /// This may change between steps if the source database is modified.
+    pub fn page_count(&self) -> u32 {
+        self.page_count
+    }
+
+    /// Returns `true` if the backup has completed.
+    pub fn is_done(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+/// Which way pages are copied between the worker's connection and the other database.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum BackupDirection {
+    To,
+    From,
+}
+
+/// The other database of a backup, opened on the worker thread.
+pub(crate) struct BackupTarget {
+    params: EstablishParams,
+    pragmas: String,
+}
+
+impl BackupTarget {
+    pub(crate) fn from_options(options: &SqliteConnectOptions) -> Result<Self, Error> {
+        Ok(Self {
+            params: EstablishParams::from_options(options)?,
+            pragmas: options.pragma_string(),
+        })
+    }
+
+    fn open(&self) -> Result<ConnectionState, Error> {
+        let mut conn = self.params.establish()?;
+
+        // `establish()` has already set the key of an encrypted database; the other pragmas,
+        // e.g. `page_size`, must be set before the backup writes to it
+        conn.handle.exec(&*self.pragmas)?;
+
+        Ok(conn)
+    }
+}
+
+/// The outcome of a single step of a backup.
+#[derive(Debug)]
+pub(crate) enum BackupStep {
+    /// Some pages were copied, and there may be more.
+    Copied(SqliteBackupProgress),
+    /// The last page was copied and the backup is finished.
+    Done(SqliteBackupProgress),
+    /// Either database is locked by another connection; the step can be retried.
+    Busy(c_int),
+}
+
+/// A backup in progress, owned by the worker thread.
+///
+/// Only one backup can be in progress per connection, as the stream driving it borrows the
+/// connection mutably.
+pub(crate) struct Backup {
+    raw: NonNull<sqlite3_backup>,
+    finished: bool,
+    direction: BackupDirection,
+    // must be dropped after the backup is finished, as SQLite holds a pointer to it
+    other: Box<ConnectionState>,
+}
+
+// SAFETY: backups are only accessed by the worker thread, like the connection handle
+unsafe impl Send for Backup {}
+
+impl Backup {
+    pub(crate) fn init(
+        handle: &ConnectionHandle,
+        target: BackupTarget,
+        direction: BackupDirection,
+    ) -> Result<Self, Error> {
+        let other = Box::new(target.open()?);
+
+        let (source, dest) = match direction {
+            BackupDirection::To => (handle, &other.handle),
+            BackupDirection::From => (&other.handle, handle),
+        };
+
+        // <https://www.sqlite.org/c3ref/backup_finish.html#sqlite3backupinit>
+        // SAFETY: both handles are exclusively owned by this thread
+        let raw = unsafe {
+            sqlite3_backup_init(
+                dest.as_ptr(),
+                b"main\0".as_ptr().cast(),
+                source.as_ptr(),
+                b"main\0".as_ptr().cast(),
+            )
+        };
+
+        match NonNull::new(raw) {
+            Some(raw) => Ok(Self {
+                raw,
+                finished: false,
+                direction,
+                other,
+            }),
+            None => Err(Error::Database(Box::new(SqliteError::new(dest.as_ptr())))),
+        }
+    }
+
+    /// Copy up to `pages` pages, finishing the backup in `slot` once it is done or has failed.
+    pub(crate) fn step(
+        slot: &mut Option<Backup>,
+        handle: &ConnectionHandle,
+        pages: c_int,
+    ) -> Result<BackupStep, Error> {
+        let backup = slot
+            .as_mut()
+            .ok_or_else(|| err_protocol!("no backup is in progress"))?;
+
+        // SAFETY: the backup is not finished while it's in `slot`
+        let (status, progress) = unsafe {
+            let status = sqlite3_backup_step(backup.raw.as_ptr(), pages);
+            let progress = SqliteBackupProgress {
+                remaining: sqlite3_backup_remaining(backup.raw.as_ptr()) as u32,
+                page_count: sqlite3_backup_pagecount(backup.raw.as_ptr()) as u32,
+            };
+
+            (status, progress)
+        };
+
+        match status {
+            SQLITE_OK => Ok(BackupStep::Copied(progress)),
+            SQLITE_BUSY | SQLITE_LOCKED => Ok(BackupStep::Busy(status)),
+
+            // any other error is reported by `sqlite3_backup_finish()`
+            _ => {
+                let backup = slot.take().expect("BUG: backup disappeared");
+                backup.finish(handle)?;
+
+                if status == SQLITE_DONE {
+                    Ok(BackupStep::Done(progress))
+                } else {
+                    Err(Error::Database(Box::new(SqliteError::from_code(status))))
+                }
+            }
+        }
+    }
+
+    fn finish(mut self, handle: &ConnectionHandle) -> Result<(), Error> {
+        if self.finish_raw() != SQLITE_OK {
+            let dest = match self.direction {
+                BackupDirection::To => &self.other.handle,
+                BackupDirection::From => handle,
+            };
+
+            return Err(Error::Database(Box::new(SqliteError::new(dest.as_ptr()))));
+        }
+
+        Ok(())
+    }
+
+    fn finish_raw(&mut self) -> c_int {
+        if self.finished {
+            return SQLITE_OK;
+        }
+
+        self.finished = true;
+
+        // SAFETY: the backup is not used after this
+        unsafe { sqlite3_backup_finish(self.raw.as_ptr()) }
+    }
+}
+
+impl Drop for Backup {
+    fn drop(&mut self) {
+        // abandon the backup if it's still in progress, e.g. when the connection is closed
+        self.finish_raw();
+    }
+}
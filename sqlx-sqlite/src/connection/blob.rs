@@ -0,0 +1,387 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, SeekFrom};
+use std::os::raw::{c_int, c_void};
+use std::pin::Pin;
+use std::ptr::{self, NonNull};
+use std::task::{Context, Poll};
+
+use futures_core::future::BoxFuture;
+use futures_io::{AsyncRead, AsyncSeek, AsyncWrite};
+use libsqlite3_sys::{
+    sqlite3_blob, sqlite3_blob_bytes, sqlite3_blob_close, sqlite3_blob_open, sqlite3_blob_read,
+    sqlite3_blob_write, SQLITE_OK,
+};
+
+use crate::connection::handle::ConnectionHandle;
+use crate::connection::worker::Release;
+use crate::error::Error;
+use crate::{SqliteConnection, SqliteError};
+
+/// A handle for incremental I/O on a single BLOB (or TEXT) value, opened with
+/// [`SqliteConnection::open_blob()`].
+///
+/// The value is read and written in chunks on the worker thread of the connection, so it is
+/// never fully loaded into memory. Reads and writes are bounded by the size of the value, which
+/// cannot be changed through this handle; to store a larger value, first set it to a
+/// `zeroblob(N)` of the desired size.
+///
+/// If the row is modified or deleted by anything other than this handle, the handle expires
+/// and all further I/O fails.
+///
+/// See [Incremental BLOB I/O](https://www.sqlite.org/c3ref/blob_open.html) for details.
+pub struct SqliteBlob<'c> {
+    conn: &'c mut SqliteConnection,
+    id: u64,
+    len: u64,
+    pos: u64,
+    state: BlobState,
+}
+
+enum BlobState {
+    Idle,
+    Reading(BoxFuture<'static, Result<Vec<u8>, Error>>),
+    Writing(BoxFuture<'static, Result<usize, Error>>),
+}
+
+impl<'c> SqliteBlob<'c> {
+    pub(crate) async fn open(
+        conn: &'c mut SqliteConnection,
+        table: &str,
+        column: &str,
+        rowid: i64,
+        read_write: bool,
+    ) -> Result<SqliteBlob<'c>, Error> {
+        let (id, len) = conn
+            .worker
+            .blob_open(table, column, rowid, read_write)
+            .await?;
+
+        Ok(SqliteBlob {
+            conn,
+            id,
+            len,
+            pos: 0,
+            state: BlobState::Idle,
+        })
+    }
+
+    /// Returns the size of the value in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the value is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn remaining(&self) -> usize {
+        usize::try_from(self.len.saturating_sub(self.pos)).unwrap_or(usize::MAX)
+    }
+
+    fn poll_write_in_progress(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        let res = match &mut self.state {
+            BlobState::Writing(fut) => futures_core::ready!(fut.as_mut().poll(cx)),
+            _ => return Poll::Ready(Ok(0)),
+        };
+
+        self.state = BlobState::Idle;
+
+        let written = res.map_err(to_io_error)?;
+        self.pos += written as u64;
+
+        Poll::Ready(Ok(written))
+    }
+}
+
+impl AsyncRead for SqliteBlob<'_> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if let BlobState::Writing(_) = this.state {
+            futures_core::ready!(this.poll_write_in_progress(cx))?;
+        }
+
+        if let BlobState::Idle = this.state {
+            let len = cmp::min(buf.len(), this.remaining());
+
+            if len == 0 {
+                return Poll::Ready(Ok(0));
+            }
+
+            this.state = BlobState::Reading(this.conn.worker.blob_read(this.id, this.pos, len));
+        }
+
+        let res = match &mut this.state {
+            BlobState::Reading(fut) => futures_core::ready!(fut.as_mut().poll(cx)),
+            _ => unreachable!(),
+        };
+
+        this.state = BlobState::Idle;
+
+        let data = res.map_err(to_io_error)?;
+
+        // `buf` may be shorter than when the read was started
+        let n = cmp::min(data.len(), buf.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        this.pos += n as u64;
+
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncWrite for SqliteBlob<'_> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        match this.state {
+            // a write started by a previous call
+            BlobState::Writing(_) => return this.poll_write_in_progress(cx),
+            // discard an abandoned read
+            BlobState::Reading(_) => this.state = BlobState::Idle,
+            BlobState::Idle => (),
+        }
+
+        let len = cmp::min(buf.len(), this.remaining());
+
+        if len == 0 {
+            // the size of a blob cannot be changed
+            return Poll::Ready(Ok(0));
+        }
+
+        this.state = BlobState::Writing(this.conn.worker.blob_write(
+            this.id,
+            this.pos,
+            buf[..len].to_vec(),
+        ));
+
+        this.poll_write_in_progress(cx)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // writes are applied to the database as soon as they complete
+        self.get_mut().poll_write_in_progress(cx).map_ok(|_| ())
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+impl AsyncSeek for SqliteBlob<'_> {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+
+        match this.state {
+            BlobState::Writing(_) => {
+                futures_core::ready!(this.poll_write_in_progress(cx))?;
+            }
+            BlobState::Reading(_) => this.state = BlobState::Idle,
+            BlobState::Idle => (),
+        }
+
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => add_offset(this.len, offset),
+            SeekFrom::Current(offset) => add_offset(this.pos, offset),
+        };
+
+        match new_pos {
+            Some(new_pos) => {
+                this.pos = new_pos;
+                Poll::Ready(Ok(new_pos))
+            }
+            None => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            ))),
+        }
+    }
+}
+
+impl Drop for SqliteBlob<'_> {
+    fn drop(&mut self) {
+        self.conn.worker.release(Release::Blob(self.id));
+    }
+}
+
+impl Debug for SqliteBlob<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SqliteBlob")
+            .field("len", &self.len)
+            .field("pos", &self.pos)
+            .finish_non_exhaustive()
+    }
+}
+
+fn add_offset(base: u64, offset: i64) -> Option<u64> {
+    if offset >= 0 {
+        base.checked_add(offset as u64)
+    } else {
+        base.checked_sub(offset.unsigned_abs())
+    }
+}
+
+fn to_io_error(e: Error) -> io::Error {
+    match e {
+        Error::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::Other, e),
+    }
+}
+
+/// The blob handles opened on a connection, owned by the worker thread.
+#[derive(Default)]
+pub(crate) struct Blobs {
+    handles: HashMap<u64, BlobHandle>,
+    next_id: u64,
+}
+
+struct BlobHandle(NonNull<sqlite3_blob>);
+
+// SAFETY: blob handles are only accessed by the worker thread, like the connection handle
+unsafe impl Send for BlobHandle {}
+
+impl Blobs {
+    pub(crate) fn open(
+        &mut self,
+        handle: &mut ConnectionHandle,
+        table: &str,
+        column: &str,
+        rowid: i64,
+        read_write: bool,
+    ) -> Result<(u64, u64), Error> {
+        let table =
+            CString::new(table).map_err(|_| err_protocol!("invalid table name: {:?}", table))?;
+        let column =
+            CString::new(column).map_err(|_| err_protocol!("invalid column name: {:?}", column))?;
+
+        let mut blob = ptr::null_mut();
+
+        // <https://www.sqlite.org/c3ref/blob_open.html>
+        // SAFETY: we have exclusive access to the database handle
+        let status = unsafe {
+            sqlite3_blob_open(
+                handle.as_ptr(),
+                b"main\0".as_ptr().cast(),
+                table.as_ptr(),
+                column.as_ptr(),
+                rowid,
+                c_int::from(read_write),
+                &mut blob,
+            )
+        };
+
+        if status != SQLITE_OK {
+            // `blob` is set to NULL on failure
+            return Err(Error::Database(Box::new(SqliteError::new(handle.as_ptr()))));
+        }
+
+        // SAFETY: `sqlite3_blob_open()` succeeded
+        let blob = BlobHandle(unsafe { NonNull::new_unchecked(blob) });
+        let len = unsafe { sqlite3_blob_bytes(blob.0.as_ptr()) };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.handles.insert(id, blob);
+
+        Ok((id, len as u64))
+    }
+
+    pub(crate) fn read(
+        &mut self,
+        handle: &mut ConnectionHandle,
+        id: u64,
+        offset: u64,
+        len: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let blob = self.get(id)?;
+        let mut data = vec![0u8; len];
+
+        // SAFETY: `data` is valid for writes of `len` bytes
+        let status = unsafe {
+            sqlite3_blob_read(
+                blob.as_ptr(),
+                data.as_mut_ptr() as *mut c_void,
+                to_c_int(len)?,
+                to_c_int(offset)?,
+            )
+        };
+
+        if status != SQLITE_OK {
+            return Err(Error::Database(Box::new(SqliteError::new(handle.as_ptr()))));
+        }
+
+        Ok(data)
+    }
+
+    pub(crate) fn write(
+        &mut self,
+        handle: &mut ConnectionHandle,
+        id: u64,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<usize, Error> {
+        let blob = self.get(id)?;
+
+        // SAFETY: `data` is valid for reads of its length
+        let status = unsafe {
+            sqlite3_blob_write(
+                blob.as_ptr(),
+                data.as_ptr() as *const c_void,
+                to_c_int(data.len())?,
+                to_c_int(offset)?,
+            )
+        };
+
+        if status != SQLITE_OK {
+            return Err(Error::Database(Box::new(SqliteError::new(handle.as_ptr()))));
+        }
+
+        Ok(data.len())
+    }
+
+    pub(crate) fn close(&mut self, id: u64) {
+        self.handles.remove(&id);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.handles.clear();
+    }
+
+    fn get(&self, id: u64) -> Result<NonNull<sqlite3_blob>, Error> {
+        self.handles
+            .get(&id)
+            .map(|blob| blob.0)
+            .ok_or_else(|| err_protocol!("blob handle {} is not open", id))
+    }
+}
+
+impl Drop for BlobHandle {
+    fn drop(&mut self) {
+        // SAFETY: the handle is not used after this
+        unsafe {
+            sqlite3_blob_close(self.0.as_ptr());
+        }
+    }
+}
+
+fn to_c_int<T: TryInto<c_int> + Copy + Debug>(value: T) -> Result<c_int, Error> {
+    value
+        .try_into()
+        .map_err(|_| err_protocol!("blob offset or length out of range: {:?}", value))
+}
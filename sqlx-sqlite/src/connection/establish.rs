@@ -1,4 +1,10 @@
+use crate::connection::blob::Blobs;
 use crate::connection::handle::ConnectionHandle;
+use crate::connection::hooks::Hooks;
+use crate::connection::progress::Progress;
+use crate::connection::serialize::SerializedDatabase;
+#[cfg(feature = "sqlite-session")]
+use crate::connection::session::Sessions;
 use crate::connection::LogSettings;
 use crate::connection::{ConnectionState, Statements};
 use crate::error::Error;
@@ -44,6 +50,10 @@ pub struct EstablishParams {
     extensions: IndexMap<CString, Option<CString>>,
     pub(crate) thread_name: String,
     pub(crate) command_channel_size: usize,
+    deserialize: Option<SerializedDatabase>,
+    pub(crate) begin_immediate: bool,
+    pub(crate) statement_timeout: Option<Duration>,
+    cipher_pragmas: String,
     #[cfg(feature = "regexp")]
     register_regexp_function: bool,
 }
@@ -147,6 +157,10 @@ impl EstablishParams {
             extensions,
             thread_name: (options.thread_name)(THREAD_ID.fetch_add(1, Ordering::AcqRel)),
             command_channel_size: options.command_channel_size,
+            deserialize: options.deserialize.clone(),
+            begin_immediate: options.begin_immediate,
+            statement_timeout: options.statement_timeout,
+            cipher_pragmas: options.cipher_pragma_string(),
             #[cfg(feature = "regexp")]
             register_regexp_function: options.register_regexp_function,
         })
@@ -192,7 +206,7 @@ impl EstablishParams {
 
         // SAFE: tested for NULL just above
         // This allows any returns below to close this handle with RAII
-        let handle = unsafe { ConnectionHandle::new(handle) };
+        let mut handle = unsafe { ConnectionHandle::new(handle) };
 
         if status != SQLITE_OK {
             return Err(Error::Database(Box::new(SqliteError::new(handle.as_ptr()))));
@@ -254,6 +268,10 @@ impl EstablishParams {
             }
         }
 
+        if let Some(data) = &self.deserialize {
+            data.deserialize_into(&handle)?;
+        }
+
         #[cfg(feature = "regexp")]
         if self.register_regexp_function {
             // configure a `regexp` function for sqlite, it does not come with one by default
@@ -277,8 +295,34 @@ impl EstablishParams {
             return Err(Error::Database(Box::new(SqliteError::new(handle.as_ptr()))));
         }
 
+        if !self.cipher_pragmas.is_empty() {
+            // SQLCipher needs the key before anything reads the database
+            handle.exec(&*self.cipher_pragmas)?;
+
+            // the key is only checked when the database is first read; a wrong key makes
+            // every query fail with "file is not a database"
+            handle
+                .exec("SELECT COUNT(*) FROM sqlite_master")
+                .map_err(|e| match e.as_database_error() {
+                    Some(db) if db.downcast_ref::<SqliteError>().is_not_a_database() => {
+                        Error::Configuration(
+                            "the database could not be decrypted: the key is incorrect, \
+                             or the file is not an encrypted database"
+                                .into(),
+                        )
+                    }
+                    _ => e,
+                })?;
+        }
+
         Ok(ConnectionState {
             handle,
+            hooks: Hooks::default(),
+            progress: Progress::default(),
+            blobs: Blobs::default(),
+            backup: None,
+            #[cfg(feature = "sqlite-session")]
+            sessions: Sessions::default(),
             statements: Statements::new(self.statement_cache_capacity),
             transaction_depth: 0,
             log_settings: self.log_settings.clone(),
@@ -118,6 +118,7 @@ impl Iterator for ExecuteIter<'_> {
 
                 Some(Ok(Either::Left(done)))
             }
+            Err(e) if e.is_interrupt() => Some(Err(Error::Interrupted)),
             Err(e) => Some(Err(e.into())),
         }
     }
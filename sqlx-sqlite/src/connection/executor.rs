@@ -27,7 +27,6 @@ impl<'c> Executor<'c> for &'c mut SqliteConnection {
         Box::pin(
             self.worker
                 .execute(sql, arguments, self.row_channel_size, persistent)
-                .map_ok(flume::Receiver::into_stream)
                 .try_flatten_stream(),
         )
     }
@@ -48,7 +47,6 @@ impl<'c> Executor<'c> for &'c mut SqliteConnection {
             let stream = self
                 .worker
                 .execute(sql, arguments, self.row_channel_size, persistent)
-                .map_ok(flume::Receiver::into_stream)
                 .try_flatten_stream();
 
             futures_util::pin_mut!(stream);
@@ -0,0 +1,430 @@
+use std::any::Any;
+use std::ffi::CString;
+use std::fmt::{self, Debug, Formatter};
+use std::mem;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::sync::Arc;
+
+use libsqlite3_sys::{
+    sqlite3_aggregate_context, sqlite3_context, sqlite3_create_function_v2,
+    sqlite3_create_window_function, sqlite3_result_blob64, sqlite3_result_double,
+    sqlite3_result_error, sqlite3_result_error_nomem, sqlite3_result_int, sqlite3_result_int64,
+    sqlite3_result_null, sqlite3_result_text64, sqlite3_user_data, sqlite3_value, SQLITE_OK,
+    SQLITE_TRANSIENT, SQLITE_UTF8,
+};
+
+use crate::connection::handle::ConnectionHandle;
+use crate::decode::Decode;
+use crate::encode::{Encode, IsNull};
+use crate::error::{BoxDynError, Error};
+use crate::type_info::DataType;
+use crate::value::Value;
+use crate::{Sqlite, SqliteArgumentValue, SqliteError, SqliteTypeInfo, SqliteValue};
+
+/// The arguments of a user-defined SQL function.
+///
+/// This is implemented for tuples of up to 16 elements where every element implements
+/// [`Decode<Sqlite>`], each of which is decoded from the corresponding argument. A unary
+/// function takes a 1-tuple, e.g. `|(x,): (i64,)| x * 2`.
+///
+/// It is also implemented for `Vec<SqliteValue>`, which registers a variadic function that
+/// receives all arguments undecoded.
+///
+/// Arguments are decoded without checking type compatibility, as with
+/// [`Row::try_get_unchecked()`][sqlx_core::row::Row::try_get_unchecked], since SQLite is
+/// dynamically typed. Use `Option<T>` for arguments that may be `NULL`.
+pub trait SqliteFunctionArgs: Sized {
+    /// The number of arguments the function accepts, or `-1` if it accepts any number.
+    const ARITY: i32;
+
+    /// Decode the arguments from the values passed by SQLite.
+    fn from_values(values: Vec<SqliteValue>) -> Result<Self, BoxDynError>;
+}
+
+impl SqliteFunctionArgs for Vec<SqliteValue> {
+    const ARITY: i32 = -1;
+
+    fn from_values(values: Vec<SqliteValue>) -> Result<Self, BoxDynError> {
+        Ok(values)
+    }
+}
+
+macro_rules! impl_function_args_for_tuple {
+    ($arity:literal; $( $idx:tt : $T:ident ),*) => {
+        impl<$($T,)*> SqliteFunctionArgs for ($($T,)*)
+        where
+            $($T: for<'r> Decode<'r, Sqlite>,)*
+        {
+            const ARITY: i32 = $arity;
+
+            #[allow(unused_variables)]
+            fn from_values(values: Vec<SqliteValue>) -> Result<Self, BoxDynError> {
+                Ok(($($T::decode(values[$idx].as_ref())?,)*))
+            }
+        }
+    };
+}
+
+impl_function_args_for_tuple!(0;);
+impl_function_args_for_tuple!(1; 0: T1);
+impl_function_args_for_tuple!(2; 0: T1, 1: T2);
+impl_function_args_for_tuple!(3; 0: T1, 1: T2, 2: T3);
+impl_function_args_for_tuple!(4; 0: T1, 1: T2, 2: T3, 3: T4);
+impl_function_args_for_tuple!(5; 0: T1, 1: T2, 2: T3, 3: T4, 4: T5);
+impl_function_args_for_tuple!(6; 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6);
+impl_function_args_for_tuple!(7; 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7);
+impl_function_args_for_tuple!(8; 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8);
+impl_function_args_for_tuple!(9; 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9);
+impl_function_args_for_tuple!(10; 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10);
+impl_function_args_for_tuple!(11; 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10, 10: T11);
+impl_function_args_for_tuple!(12; 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10, 10: T11, 11: T12);
+impl_function_args_for_tuple!(13; 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10, 10: T11, 11: T12, 12: T13);
+impl_function_args_for_tuple!(14; 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10, 10: T11, 11: T12, 12: T13, 13: T14);
+impl_function_args_for_tuple!(15; 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10, 10: T11, 11: T12, 12: T13, 13: T14, 14: T15);
+impl_function_args_for_tuple!(16; 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8, 8: T9, 9: T10, 10: T11, 11: T12, 12: T13, 13: T14, 14: T15, 15: T16);
+
+/// A user-defined SQL function that can be registered on any number of connections.
+#[derive(Clone)]
+pub struct Function {
+    name: Arc<str>,
+    create: Arc<CreateFn>,
+}
+
+type CreateFn = dyn Fn(&mut ConnectionHandle, &CString) -> c_int + Send + Sync + 'static;
+
+impl Function {
+    pub fn scalar<N, A, R, F>(name: N, func: F) -> Self
+    where
+        N: Into<Arc<str>>,
+        A: SqliteFunctionArgs + 'static,
+        R: Encode<'static, Sqlite> + 'static,
+        F: Fn(A) -> R + Send + Sync + 'static,
+    {
+        let func = Arc::new(func);
+
+        Function {
+            name: name.into(),
+            create: Arc::new(move |handle, name| unsafe {
+                // SAFETY: `call_scalar` and `drop_arc_value` match the type of `func`
+                sqlite3_create_function_v2(
+                    handle.as_ptr(),
+                    name.as_ptr(),
+                    A::ARITY,
+                    SQLITE_UTF8,
+                    Arc::into_raw(Arc::clone(&func)) as *mut c_void,
+                    Some(call_scalar::<A, R, F>),
+                    None,
+                    None,
+                    Some(drop_arc_value::<F>),
+                )
+            }),
+        }
+    }
+
+    pub fn aggregate<N, A, R, S>(name: N, aggregate: Aggregate<S, A, R>) -> Self
+    where
+        N: Into<Arc<str>>,
+        A: SqliteFunctionArgs + 'static,
+        R: Encode<'static, Sqlite> + 'static,
+        S: 'static,
+    {
+        let aggregate = Arc::new(aggregate);
+
+        Function {
+            name: name.into(),
+            create: Arc::new(move |handle, name| unsafe {
+                let data = Arc::into_raw(Arc::clone(&aggregate)) as *mut c_void;
+
+                // SAFETY: the callbacks match the type of `aggregate`
+                if aggregate.window.is_some() {
+                    sqlite3_create_window_function(
+                        handle.as_ptr(),
+                        name.as_ptr(),
+                        A::ARITY,
+                        SQLITE_UTF8,
+                        data,
+                        Some(aggregate_step::<S, A, R>),
+                        Some(aggregate_final::<S, A, R>),
+                        Some(aggregate_value::<S, A, R>),
+                        Some(aggregate_inverse::<S, A, R>),
+                        Some(drop_arc_value::<Aggregate<S, A, R>>),
+                    )
+                } else {
+                    sqlite3_create_function_v2(
+                        handle.as_ptr(),
+                        name.as_ptr(),
+                        A::ARITY,
+                        SQLITE_UTF8,
+                        data,
+                        None,
+                        Some(aggregate_step::<S, A, R>),
+                        Some(aggregate_final::<S, A, R>),
+                        Some(drop_arc_value::<Aggregate<S, A, R>>),
+                    )
+                }
+            }),
+        }
+    }
+
+    pub(crate) fn create(&self, handle: &mut ConnectionHandle) -> Result<(), Error> {
+        let c_name = CString::new(&*self.name)
+            .map_err(|_| err_protocol!("invalid function name: {:?}", self.name))?;
+
+        // Unlike `sqlite3_create_collation_v2()`, the destructor is invoked by SQLite
+        // if registering the function fails, so there is nothing to clean up here.
+        let r = (self.create)(handle, &c_name);
+
+        if r == SQLITE_OK {
+            Ok(())
+        } else {
+            Err(Error::Database(Box::new(SqliteError::new(handle.as_ptr()))))
+        }
+    }
+}
+
+impl Debug for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The callbacks of a user-defined aggregate or aggregate window function.
+pub struct Aggregate<S, A, R> {
+    init: Box<dyn Fn() -> S + Send + Sync + 'static>,
+    step: Box<StepFn<S, A>>,
+    finalize: Box<dyn Fn(S) -> R + Send + Sync + 'static>,
+    window: Option<WindowCallbacks<S, A, R>>,
+}
+
+struct WindowCallbacks<S, A, R> {
+    inverse: Box<StepFn<S, A>>,
+    value: Box<dyn Fn(&S) -> R + Send + Sync + 'static>,
+}
+
+type StepFn<S, A> = dyn Fn(&mut S, A) + Send + Sync + 'static;
+
+impl<S, A, R> Aggregate<S, A, R> {
+    pub fn new(
+        init: impl Fn() -> S + Send + Sync + 'static,
+        step: impl Fn(&mut S, A) + Send + Sync + 'static,
+        finalize: impl Fn(S) -> R + Send + Sync + 'static,
+    ) -> Self {
+        Aggregate {
+            init: Box::new(init),
+            step: Box::new(step),
+            finalize: Box::new(finalize),
+            window: None,
+        }
+    }
+
+    pub fn window(
+        mut self,
+        inverse: impl Fn(&mut S, A) + Send + Sync + 'static,
+        value: impl Fn(&S) -> R + Send + Sync + 'static,
+    ) -> Self {
+        self.window = Some(WindowCallbacks {
+            inverse: Box::new(inverse),
+            value: Box::new(value),
+        });
+        self
+    }
+}
+
+unsafe extern "C" fn drop_arc_value<T>(p: *mut c_void) {
+    drop(Arc::from_raw(p as *const T));
+}
+
+unsafe extern "C" fn call_scalar<A, R, F>(
+    ctx: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) where
+    A: SqliteFunctionArgs,
+    R: Encode<'static, Sqlite>,
+    F: Fn(A) -> R,
+{
+    let func = &*(sqlite3_user_data(ctx) as *const F);
+
+    guard(ctx, || {
+        let args = decode_args::<A>(argc, argv)?;
+        set_result(ctx, func(args));
+        Ok(())
+    });
+}
+
+unsafe extern "C" fn aggregate_step<S, A, R>(
+    ctx: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) where
+    A: SqliteFunctionArgs,
+{
+    let aggregate = &*(sqlite3_user_data(ctx) as *const Aggregate<S, A, R>);
+
+    guard(ctx, || {
+        let args = decode_args::<A>(argc, argv)?;
+
+        if let Some(state) = aggregate_state(ctx, aggregate) {
+            (aggregate.step)(state, args);
+        }
+
+        Ok(())
+    });
+}
+
+unsafe extern "C" fn aggregate_inverse<S, A, R>(
+    ctx: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) where
+    A: SqliteFunctionArgs,
+{
+    let aggregate = &*(sqlite3_user_data(ctx) as *const Aggregate<S, A, R>);
+
+    guard(ctx, || {
+        let args = decode_args::<A>(argc, argv)?;
+
+        if let (Some(window), Some(state)) = (&aggregate.window, aggregate_state(ctx, aggregate)) {
+            (window.inverse)(state, args);
+        }
+
+        Ok(())
+    });
+}
+
+unsafe extern "C" fn aggregate_value<S, A, R>(ctx: *mut sqlite3_context)
+where
+    R: Encode<'static, Sqlite>,
+{
+    let aggregate = &*(sqlite3_user_data(ctx) as *const Aggregate<S, A, R>);
+
+    guard(ctx, || {
+        if let (Some(window), Some(state)) = (&aggregate.window, aggregate_state(ctx, aggregate)) {
+            set_result(ctx, (window.value)(state));
+        }
+
+        Ok(())
+    });
+}
+
+unsafe extern "C" fn aggregate_final<S, A, R>(ctx: *mut sqlite3_context)
+where
+    R: Encode<'static, Sqlite>,
+{
+    let aggregate = &*(sqlite3_user_data(ctx) as *const Aggregate<S, A, R>);
+
+    // passing a size of zero does not allocate, so this is null if `xStep` was never called
+    let p = sqlite3_aggregate_context(ctx, 0) as *mut *mut S;
+
+    let state = if p.is_null() || (*p).is_null() {
+        None
+    } else {
+        Some(Box::from_raw(mem::replace(&mut *p, ptr::null_mut())))
+    };
+
+    guard(ctx, || {
+        let state = match state {
+            Some(state) => *state,
+            None => (aggregate.init)(),
+        };
+
+        set_result(ctx, (aggregate.finalize)(state));
+        Ok(())
+    });
+}
+
+/// Get the state of the aggregate for the current invocation, initializing it if necessary.
+///
+/// SQLite only gives us a zeroed allocation that is freed without running any destructor,
+/// so we store a pointer to a boxed state in it, which is reclaimed in `aggregate_final`.
+unsafe fn aggregate_state<'a, S, A, R>(
+    ctx: *mut sqlite3_context,
+    aggregate: &Aggregate<S, A, R>,
+) -> Option<&'a mut S> {
+    let p = sqlite3_aggregate_context(ctx, mem::size_of::<*mut S>() as c_int) as *mut *mut S;
+
+    if p.is_null() {
+        sqlite3_result_error_nomem(ctx);
+        return None;
+    }
+
+    if (*p).is_null() {
+        *p = Box::into_raw(Box::new((aggregate.init)()));
+    }
+
+    Some(&mut **p)
+}
+
+unsafe fn decode_args<A: SqliteFunctionArgs>(
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) -> Result<A, BoxDynError> {
+    let values = (0..argc as usize)
+        .map(|i| SqliteValue::new(*argv.add(i), SqliteTypeInfo(DataType::Null)))
+        .collect();
+
+    A::from_values(values)
+}
+
+pub(crate) unsafe fn set_result<'q, R: Encode<'q, Sqlite>>(ctx: *mut sqlite3_context, value: R) {
+    let mut buf = Vec::with_capacity(1);
+
+    if let IsNull::Yes = value.encode(&mut buf) {
+        sqlite3_result_null(ctx);
+        return;
+    }
+
+    match buf.into_iter().next() {
+        None | Some(SqliteArgumentValue::Null) => sqlite3_result_null(ctx),
+        Some(SqliteArgumentValue::Text(v)) => sqlite3_result_text64(
+            ctx,
+            v.as_ptr() as *const c_char,
+            v.len() as u64,
+            SQLITE_TRANSIENT(),
+            SQLITE_UTF8 as u8,
+        ),
+        Some(SqliteArgumentValue::Blob(v)) => sqlite3_result_blob64(
+            ctx,
+            v.as_ptr() as *const c_void,
+            v.len() as u64,
+            SQLITE_TRANSIENT(),
+        ),
+        Some(SqliteArgumentValue::Double(v)) => sqlite3_result_double(ctx, v),
+        Some(SqliteArgumentValue::Int(v)) => sqlite3_result_int(ctx, v),
+        Some(SqliteArgumentValue::Int64(v)) => sqlite3_result_int64(ctx, v),
+    }
+}
+
+/// Run a user callback, reporting errors and panics to SQLite as the result of the function.
+///
+/// Unwinding across the FFI boundary is undefined behavior so panics must be caught here.
+unsafe fn guard(ctx: *mut sqlite3_context, f: impl FnOnce() -> Result<(), BoxDynError>) {
+    let message = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => return,
+        Ok(Err(e)) => e.to_string(),
+        Err(payload) => format!(
+            "user-defined function panicked: {}",
+            panic_message(&*payload)
+        ),
+    };
+
+    sqlite3_result_error(
+        ctx,
+        message.as_ptr() as *const c_char,
+        message.len() as c_int,
+    );
+}
+
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s
+    } else {
+        "Box<dyn Any>"
+    }
+}
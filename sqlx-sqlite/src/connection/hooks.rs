@@ -0,0 +1,169 @@
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use libsqlite3_sys::{
+    sqlite3_commit_hook, sqlite3_int64, sqlite3_rollback_hook, sqlite3_update_hook, SQLITE_DELETE,
+    SQLITE_INSERT, SQLITE_UPDATE,
+};
+
+use crate::connection::handle::ConnectionHandle;
+
+/// The kind of change reported by [`SqliteConnection::update_hook()`][crate::SqliteConnection::update_hook].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SqliteOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A row change reported by [`SqliteConnection::update_hook()`][crate::SqliteConnection::update_hook].
+///
+/// See [`sqlite3_update_hook()`](https://www.sqlite.org/c3ref/update_hook.html) for which
+/// changes are reported; notably, changes to `WITHOUT ROWID` tables and changes made by
+/// truncating a table with an unqualified `DELETE` are not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqliteUpdateEvent {
+    pub operation: SqliteOperation,
+    /// The name of the database containing the table, e.g. `main` or `temp`.
+    pub database: String,
+    pub table: String,
+    pub rowid: i64,
+}
+
+type CommitFilter = dyn FnMut() -> bool + Send + 'static;
+
+struct CommitHook {
+    tx: flume::Sender<()>,
+    should_commit: Box<CommitFilter>,
+}
+
+/// Hooks registered on a connection.
+///
+/// SQLite only keeps a pointer to the hook state, so we own it here. This must be dropped
+/// after the connection handle is closed or the hooks are replaced.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    update: Option<Box<flume::Sender<SqliteUpdateEvent>>>,
+    commit: Option<Box<CommitHook>>,
+    rollback: Option<Box<flume::Sender<()>>>,
+}
+
+impl Hooks {
+    pub(crate) fn set_update_hook(
+        &mut self,
+        handle: &mut ConnectionHandle,
+        tx: flume::Sender<SqliteUpdateEvent>,
+    ) {
+        let state = Box::new(tx);
+
+        // SAFETY: the pointer stays valid until the hook is replaced or the connection closed,
+        // as the box is not dropped before that
+        unsafe {
+            sqlite3_update_hook(
+                handle.as_ptr(),
+                Some(update_hook),
+                &*state as *const flume::Sender<SqliteUpdateEvent> as *mut c_void,
+            );
+        }
+
+        self.update = Some(state);
+    }
+
+    pub(crate) fn set_commit_hook(
+        &mut self,
+        handle: &mut ConnectionHandle,
+        tx: flume::Sender<()>,
+        should_commit: Box<CommitFilter>,
+    ) {
+        let mut state = Box::new(CommitHook { tx, should_commit });
+
+        // SAFETY: see `set_update_hook()`
+        unsafe {
+            sqlite3_commit_hook(
+                handle.as_ptr(),
+                Some(commit_hook),
+                &mut *state as *mut CommitHook as *mut c_void,
+            );
+        }
+
+        self.commit = Some(state);
+    }
+
+    pub(crate) fn set_rollback_hook(
+        &mut self,
+        handle: &mut ConnectionHandle,
+        tx: flume::Sender<()>,
+    ) {
+        let state = Box::new(tx);
+
+        // SAFETY: see `set_update_hook()`
+        unsafe {
+            sqlite3_rollback_hook(
+                handle.as_ptr(),
+                Some(rollback_hook),
+                &*state as *const flume::Sender<()> as *mut c_void,
+            );
+        }
+
+        self.rollback = Some(state);
+    }
+
+    /// Unregister all hooks, closing their channels.
+    pub(crate) fn clear(&mut self, handle: &mut ConnectionHandle) {
+        // SAFETY: passing a null callback unregisters the hook
+        unsafe {
+            sqlite3_update_hook(handle.as_ptr(), None, ptr::null_mut());
+            sqlite3_commit_hook(handle.as_ptr(), None, ptr::null_mut());
+            sqlite3_rollback_hook(handle.as_ptr(), None, ptr::null_mut());
+        }
+
+        *self = Hooks::default();
+    }
+}
+
+unsafe extern "C" fn update_hook(
+    data: *mut c_void,
+    op: c_int,
+    database: *const c_char,
+    table: *const c_char,
+    rowid: sqlite3_int64,
+) {
+    let tx = &*(data as *const flume::Sender<SqliteUpdateEvent>);
+
+    let operation = match op {
+        SQLITE_INSERT => SqliteOperation::Insert,
+        SQLITE_UPDATE => SqliteOperation::Update,
+        SQLITE_DELETE => SqliteOperation::Delete,
+        _ => return,
+    };
+
+    // an error only means the receiver was dropped
+    let _ = tx.send(SqliteUpdateEvent {
+        operation,
+        database: CStr::from_ptr(database).to_string_lossy().into_owned(),
+        table: CStr::from_ptr(table).to_string_lossy().into_owned(),
+        rowid,
+    });
+}
+
+unsafe extern "C" fn commit_hook(data: *mut c_void) -> c_int {
+    let hook = &mut *(data as *mut CommitHook);
+
+    // a panic cannot unwind into SQLite, so we treat it as a veto
+    let commit = catch_unwind(AssertUnwindSafe(|| (hook.should_commit)())).unwrap_or(false);
+
+    if commit {
+        let _ = hook.tx.send(());
+    }
+
+    // returning non-zero turns the commit into a rollback
+    c_int::from(!commit)
+}
+
+unsafe extern "C" fn rollback_hook(data: *mut c_void) {
+    let tx = &*(data as *const flume::Sender<()>);
+
+    let _ = tx.send(());
+}
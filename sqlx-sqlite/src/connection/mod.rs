@@ -1,4 +1,5 @@
 use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
 use futures_intrusive::sync::MutexGuard;
 use futures_util::future;
 use libsqlite3_sys::sqlite3;
@@ -9,25 +10,54 @@ use std::cmp::Ordering;
 use std::fmt::{self, Debug, Formatter};
 use std::ptr::NonNull;
 
+use crate::connection::backup::{Backup, BackupDirection, BackupStep, BackupTarget};
+use crate::connection::blob::Blobs;
 use crate::connection::establish::EstablishParams;
-use crate::connection::worker::ConnectionWorker;
+use crate::connection::function::{Aggregate, Function};
+use crate::connection::hooks::Hooks;
+use crate::connection::progress::Progress;
+#[cfg(feature = "sqlite-session")]
+use crate::connection::session::Sessions;
+use crate::connection::worker::{ConnectionWorker, Release};
+use crate::encode::Encode;
 use crate::options::OptimizeOnClose;
 use crate::statement::VirtualStatement;
-use crate::{Sqlite, SqliteConnectOptions};
+use crate::{Sqlite, SqliteConnectOptions, SqliteError};
 use sqlx_core::executor::Executor;
 use std::fmt::Write;
 
 pub(crate) use sqlx_core::connection::*;
 
+pub use backup::{SqliteBackupOptions, SqliteBackupProgress};
+pub use blob::SqliteBlob;
+pub use function::SqliteFunctionArgs;
 pub(crate) use handle::{ConnectionHandle, ConnectionHandleRaw};
-
+pub use hooks::{SqliteOperation, SqliteUpdateEvent};
+#[cfg(feature = "sqlite-session")]
+pub use session::{
+    SqliteChangesetConflict, SqliteConflictAction, SqliteConflictKind, SqliteSession,
+};
+pub use vtab::{
+    SqliteColumnResult, SqliteConstraintOp, SqliteIndexConstraint, SqliteIndexInfo,
+    SqliteIndexOrderBy, SqliteVirtualTable, SqliteVirtualTableCursor,
+};
+
+pub(crate) mod backup;
+pub(crate) mod blob;
 pub(crate) mod collation;
 pub(crate) mod describe;
 pub(crate) mod establish;
 pub(crate) mod execute;
 mod executor;
 mod explain;
+pub(crate) mod function;
 mod handle;
+pub(crate) mod hooks;
+pub(crate) mod progress;
+pub(crate) mod serialize;
+#[cfg(feature = "sqlite-session")]
+pub(crate) mod session;
+pub(crate) mod vtab;
 
 mod worker;
 
@@ -45,6 +75,10 @@ pub struct SqliteConnection {
     optimize_on_close: OptimizeOnClose,
     pub(crate) worker: ConnectionWorker,
     pub(crate) row_channel_size: usize,
+
+    // table applied migrations are recorded in, set by the `Migrator`
+    #[cfg(feature = "migrate")]
+    pub(crate) migrations_table: std::borrow::Cow<'static, str>,
 }
 
 pub struct LockedSqliteHandle<'a> {
@@ -54,6 +88,16 @@ pub struct LockedSqliteHandle<'a> {
 pub(crate) struct ConnectionState {
     pub(crate) handle: ConnectionHandle,
 
+    // must be dropped after `handle` is closed, as SQLite holds pointers into it
+    pub(crate) hooks: Hooks,
+    pub(crate) progress: Progress,
+
+    pub(crate) blobs: Blobs,
+    pub(crate) backup: Option<Backup>,
+
+    #[cfg(feature = "sqlite-session")]
+    pub(crate) sessions: Sessions,
+
     // transaction status
     pub(crate) transaction_depth: usize,
 
@@ -77,6 +121,8 @@ impl SqliteConnection {
             optimize_on_close: options.optimize_on_close.clone(),
             worker,
             row_channel_size: options.row_channel_size,
+            #[cfg(feature = "migrate")]
+            migrations_table: sqlx_core::migrate::Migrator::DEFAULT_TABLE_NAME.into(),
         })
     }
 
@@ -89,6 +135,405 @@ impl SqliteConnection {
 
         Ok(LockedSqliteHandle { guard })
     }
+
+    /// Register a user-defined scalar SQL function on this connection.
+    ///
+    /// See [`SqliteConnectOptions::create_function()`] for details.
+    pub async fn create_function<A, R, F>(&mut self, name: &str, func: F) -> Result<(), Error>
+    where
+        A: SqliteFunctionArgs + 'static,
+        R: Encode<'static, Sqlite> + 'static,
+        F: Fn(A) -> R + Send + Sync + 'static,
+    {
+        self.worker
+            .create_function(Function::scalar(name, func))
+            .await
+    }
+
+    /// Register a user-defined aggregate SQL function on this connection.
+    ///
+    /// See [`SqliteConnectOptions::create_aggregate()`] for details.
+    pub async fn create_aggregate<A, R, S>(
+        &mut self,
+        name: &str,
+        init: impl Fn() -> S + Send + Sync + 'static,
+        step: impl Fn(&mut S, A) + Send + Sync + 'static,
+        finalize: impl Fn(S) -> R + Send + Sync + 'static,
+    ) -> Result<(), Error>
+    where
+        A: SqliteFunctionArgs + 'static,
+        R: Encode<'static, Sqlite> + 'static,
+        S: 'static,
+    {
+        self.worker
+            .create_function(Function::aggregate(
+                name,
+                Aggregate::new(init, step, finalize),
+            ))
+            .await
+    }
+
+    /// Register a user-defined aggregate window function on this connection.
+    ///
+    /// See [`SqliteConnectOptions::create_window_function()`] for details.
+    pub async fn create_window_function<A, R, S>(
+        &mut self,
+        name: &str,
+        init: impl Fn() -> S + Send + Sync + 'static,
+        step: impl Fn(&mut S, A) + Send + Sync + 'static,
+        inverse: impl Fn(&mut S, A) + Send + Sync + 'static,
+        value: impl Fn(&S) -> R + Send + Sync + 'static,
+        finalize: impl Fn(S) -> R + Send + Sync + 'static,
+    ) -> Result<(), Error>
+    where
+        A: SqliteFunctionArgs + 'static,
+        R: Encode<'static, Sqlite> + 'static,
+        S: 'static,
+    {
+        self.worker
+            .create_function(Function::aggregate(
+                name,
+                Aggregate::new(init, step, finalize).window(inverse, value),
+            ))
+            .await
+    }
+
+    /// Register a hook that reports every row inserted, updated or deleted on this connection.
+    ///
+    /// The returned stream yields an event for each change, as it is made. Note that this
+    /// includes changes which are later rolled back; use
+    /// [`.commit_hook()`][Self::commit_hook] and [`.rollback_hook()`][Self::rollback_hook]
+    /// to find out when they are made durable.
+    ///
+    /// Only one update hook can be registered per connection. Calling this again replaces the
+    /// previous hook and ends its stream. The hook is in place once this returns, and persists
+    /// until [`.clear_hooks()`][Self::clear_hooks] is called, even across returns to a pool.
+    ///
+    /// The hook runs on the worker thread and never blocks, so events are buffered without
+    /// bound until they are received; keep polling the stream or drop it.
+    ///
+    /// See [`sqlite3_update_hook()`](https://www.sqlite.org/c3ref/update_hook.html) for details.
+    pub async fn update_hook(&mut self) -> Result<BoxStream<'static, SqliteUpdateEvent>, Error> {
+        let (tx, rx) = flume::unbounded();
+        self.worker.set_update_hook(tx).await?;
+        Ok(Box::pin(rx.into_stream()))
+    }
+
+    /// Register a hook that is invoked whenever a transaction is about to be committed.
+    ///
+    /// `should_commit` is called on the worker thread before each commit; if it returns `false`
+    /// (or panics), the commit is turned into a rollback and the statement that attempted it
+    /// fails with `SQLITE_CONSTRAINT_COMMITHOOK`. It must not use this connection.
+    ///
+    /// The returned stream yields an item for each commit that was allowed to proceed.
+    ///
+    /// Only one commit hook can be registered per connection. Calling this again replaces the
+    /// previous hook and ends its stream. Like the update hook, it persists until
+    /// [`.clear_hooks()`][Self::clear_hooks] is called.
+    ///
+    /// See [`sqlite3_commit_hook()`](https://www.sqlite.org/c3ref/commit_hook.html) for details.
+    pub async fn commit_hook(
+        &mut self,
+        should_commit: impl FnMut() -> bool + Send + 'static,
+    ) -> Result<BoxStream<'static, ()>, Error> {
+        let (tx, rx) = flume::unbounded();
+        self.worker
+            .set_commit_hook(tx, Box::new(should_commit))
+            .await?;
+        Ok(Box::pin(rx.into_stream()))
+    }
+
+    /// Register a hook that is invoked whenever a transaction is rolled back.
+    ///
+    /// This includes commits vetoed by the [commit hook][Self::commit_hook], but not
+    /// rollbacks performed automatically when the connection is closed.
+    ///
+    /// Only one rollback hook can be registered per connection. Calling this again replaces the
+    /// previous hook and ends its stream. Like the update hook, it persists until
+    /// [`.clear_hooks()`][Self::clear_hooks] is called.
+    ///
+    /// See [`sqlite3_rollback_hook()`](https://www.sqlite.org/c3ref/commit_hook.html) for details.
+    pub async fn rollback_hook(&mut self) -> Result<BoxStream<'static, ()>, Error> {
+        let (tx, rx) = flume::unbounded();
+        self.worker.set_rollback_hook(tx).await?;
+        Ok(Box::pin(rx.into_stream()))
+    }
+
+    /// Unregister the update, commit and rollback hooks, ending their streams.
+    ///
+    /// Hooks belong to the connection, not to the code that registered them: they stay in place
+    /// when a [`PoolConnection`][sqlx_core::pool::PoolConnection] is returned to the pool and
+    /// fire for whoever acquires it next. Call this before releasing the connection, or from
+    /// [`PoolOptions::after_release()`][sqlx_core::pool::PoolOptions::after_release], if that
+    /// is not wanted.
+    pub async fn clear_hooks(&mut self) -> Result<(), Error> {
+        self.worker.clear_hooks().await
+    }
+
+    /// Register a callback that is invoked periodically while statements are running, e.g. to
+    /// report progress of a long `VACUUM`.
+    ///
+    /// `callback` is called on the worker thread about every `num_ops` instructions of the
+    /// SQLite virtual machine. If it returns `false` (or panics), the running statement is
+    /// interrupted and fails with [`Error::Interrupted`]. It must not use this connection.
+    ///
+    /// Only one progress handler can be registered per connection. Calling this again replaces
+    /// the previous handler.
+    ///
+    /// See [`sqlite3_progress_handler()`](https://www.sqlite.org/c3ref/progress_handler.html)
+    /// for details.
+    pub async fn progress_handler(
+        &mut self,
+        num_ops: u32,
+        callback: impl FnMut() -> bool + Send + 'static,
+    ) -> Result<(), Error> {
+        self.worker
+            .set_progress_handler(num_ops, Box::new(callback))
+            .await
+    }
+
+    /// Unregister the progress handler.
+    ///
+    /// Queries are still interrupted when they exceed the
+    /// [statement timeout][SqliteConnectOptions::statement_timeout] or their results are no
+    /// longer wanted.
+    pub async fn clear_progress_handler(&mut self) -> Result<(), Error> {
+        self.worker.clear_progress_handler().await
+    }
+
+    /// Change the key of an encrypted database to the given passphrase, re-encrypting it.
+    ///
+    /// The connection must have been opened with the current
+    /// [key][SqliteConnectOptions::key]. This changes the key of the database, not just of this
+    /// connection, so it only needs to be done once; other connections to the database,
+    /// including those already open in a pool, must be opened again with the new key.
+    ///
+    /// See [`PRAGMA rekey`](https://www.zetetic.net/sqlcipher/sqlcipher-api/#rekey) for details.
+    #[cfg(feature = "sqlcipher")]
+    pub async fn rekey(&mut self, passphrase: impl AsRef<str>) -> Result<(), Error> {
+        let sql = format!(
+            "PRAGMA rekey = {};",
+            crate::options::quote_string(passphrase.as_ref())
+        );
+
+        // run directly on the handle, so the passphrase isn't logged like a query
+        self.lock_handle().await?.guard.handle.exec(sql)
+    }
+
+    /// Serialize the contents of the database `schema` (`main` by default) into a buffer.
+    ///
+    /// This produces the same bytes as the database file on disk would contain, so it can be
+    /// written to a file, or loaded into new connections with
+    /// [`SqliteConnectOptions::deserialize_from()`] to snapshot an in-memory database.
+    ///
+    /// See [`sqlite3_serialize()`](https://www.sqlite.org/c3ref/serialize.html) for details.
+    pub async fn serialize(&mut self, schema: Option<&str>) -> Result<Vec<u8>, Error> {
+        self.worker.serialize(schema).await
+    }
+
+    /// Open a handle for incremental I/O on the BLOB or TEXT value in `column` of the row
+    /// with the given `rowid` in `table`, which must be in the `main` database.
+    ///
+    /// The returned [`SqliteBlob`] implements [`AsyncRead`][futures_io::AsyncRead],
+    /// [`AsyncWrite`][futures_io::AsyncWrite] (if `read_write` is `true`) and
+    /// [`AsyncSeek`][futures_io::AsyncSeek], without loading the whole value into memory.
+    ///
+    /// ```rust,no_run
+    /// # async fn example() -> sqlx_core::error::Result<()> {
+    /// use futures_util::io::AsyncReadExt;
+    /// use sqlx_core::connection::Connection;
+    /// use sqlx_sqlite::SqliteConnection;
+    ///
+    /// let mut conn = SqliteConnection::connect("sqlite:data.db").await?;
+    ///
+    /// let mut blob = conn.open_blob("attachments", "data", 1, false).await?;
+    ///
+    /// let mut header = [0u8; 16];
+    /// blob.read_exact(&mut header).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn open_blob(
+        &mut self,
+        table: &str,
+        column: &str,
+        rowid: i64,
+        read_write: bool,
+    ) -> Result<SqliteBlob<'_>, Error> {
+        SqliteBlob::open(self, table, column, rowid, read_write).await
+    }
+
+    /// Start a session recording changes to `schema` (`main` by default) made through this
+    /// connection, which can be encoded as a changeset and applied to another database with
+    /// [`apply_changeset()`][Self::apply_changeset].
+    ///
+    /// No tables are recorded until they're [attached][SqliteSession::attach].
+    ///
+    /// ```rust,no_run
+    /// # async fn example() -> sqlx_core::error::Result<()> {
+    /// use sqlx_core::connection::Connection;
+    /// use sqlx_core::executor::Executor;
+    /// use sqlx_sqlite::{SqliteConflictAction, SqliteConnection};
+    ///
+    /// let mut conn = SqliteConnection::connect("sqlite:data.db").await?;
+    /// let mut replica = SqliteConnection::connect("sqlite:replica.db").await?;
+    ///
+    /// let mut session = conn.session(None).await?;
+    /// session.attach(None).await?;
+    ///
+    /// (&mut *session).execute("UPDATE accounts SET balance = 0").await?;
+    ///
+    /// let changeset = session.changeset().await?;
+    /// drop(session);
+    ///
+    /// replica
+    ///     .apply_changeset(&changeset, |_| SqliteConflictAction::Omit)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "sqlite-session")]
+    pub async fn session(&mut self, schema: Option<&str>) -> Result<SqliteSession<'_>, Error> {
+        SqliteSession::open(self, schema).await
+    }
+
+    /// Apply a changeset or patchset produced by a [`SqliteSession`] to this database, in a
+    /// single transaction.
+    ///
+    /// `conflict` is called for each change that cannot be applied cleanly and decides how to
+    /// resolve it; it must not use this connection. A panic aborts the changeset.
+    ///
+    /// See [`sqlite3changeset_apply()`](https://www.sqlite.org/session/sqlite3changeset_apply.html)
+    /// for details.
+    #[cfg(feature = "sqlite-session")]
+    pub async fn apply_changeset(
+        &mut self,
+        changeset: &[u8],
+        conflict: impl FnMut(&SqliteChangesetConflict) -> SqliteConflictAction + Send + 'static,
+    ) -> Result<(), Error> {
+        self.worker
+            .apply_changeset(changeset.to_vec(), Box::new(conflict))
+            .await
+    }
+
+    /// Copy the contents of this database into the database opened by `dest`, replacing
+    /// its contents, using the [online backup API](https://www.sqlite.org/backup.html).
+    ///
+    /// The backup runs incrementally, copying
+    /// [a number of pages][SqliteBackupOptions::pages_per_step] at a time on the worker
+    /// thread of this connection and sleeping in between, so other connections can keep
+    /// reading and writing the database. If the database is written to by another connection,
+    /// the backup restarts; writes made through this connection are instead applied to the
+    /// backup as well. Steps that fail because either database is locked are
+    /// [retried a limited number of times][SqliteBackupOptions::busy_retries].
+    ///
+    /// The returned stream yields the progress after each step and ends once the backup is
+    /// complete. It must be polled to completion; dropping it abandons the backup.
+    ///
+    /// The destination is opened with the [pragmas][SqliteConnectOptions::pragma] of `dest`,
+    /// so the key of an encrypted database can be set there.
+    ///
+    /// ```rust,no_run
+    /// # async fn example() -> sqlx_core::error::Result<()> {
+    /// use futures_util::TryStreamExt;
+    /// use sqlx_core::connection::Connection;
+    /// use sqlx_sqlite::{SqliteBackupOptions, SqliteConnectOptions, SqliteConnection};
+    ///
+    /// let mut conn = SqliteConnection::connect("sqlite:data.db").await?;
+    ///
+    /// let dest = SqliteConnectOptions::new()
+    ///     .filename("backup.db")
+    ///     .create_if_missing(true);
+    ///
+    /// let mut backup = conn.backup_to(&dest, SqliteBackupOptions::new());
+    ///
+    /// while let Some(progress) = backup.try_next().await? {
+    ///     println!("{} of {} pages left", progress.remaining(), progress.page_count());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn backup_to(
+        &mut self,
+        dest: &SqliteConnectOptions,
+        options: SqliteBackupOptions,
+    ) -> BoxStream<'_, Result<SqliteBackupProgress, Error>> {
+        self.backup(dest, BackupDirection::To, options)
+    }
+
+    /// Replace the contents of this database with the contents of the database opened by
+    /// `source`, using the [online backup API](https://www.sqlite.org/backup.html).
+    ///
+    /// This connection must not be in a transaction. See [`.backup_to()`][Self::backup_to]
+    /// for details.
+    pub fn restore_from(
+        &mut self,
+        source: &SqliteConnectOptions,
+        options: SqliteBackupOptions,
+    ) -> BoxStream<'_, Result<SqliteBackupProgress, Error>> {
+        self.backup(source, BackupDirection::From, options)
+    }
+
+    fn backup(
+        &mut self,
+        other: &SqliteConnectOptions,
+        direction: BackupDirection,
+        options: SqliteBackupOptions,
+    ) -> BoxStream<'_, Result<SqliteBackupProgress, Error>> {
+        let target = BackupTarget::from_options(other);
+
+        Box::pin(try_stream! {
+            self.worker.backup_init(target?, direction).await?;
+
+            // abandons the backup if the stream is dropped before it is finished
+            let mut backup = BackupGuard {
+                worker: &mut self.worker,
+                finished: false,
+            };
+            let mut busy = 0;
+
+            loop {
+                match backup.worker.backup_step(options.pages_per_step).await {
+                    Ok(BackupStep::Copied(progress)) => {
+                        busy = 0;
+                        r#yield!(progress);
+                    }
+                    Ok(BackupStep::Done(progress)) => {
+                        backup.finished = true;
+                        r#yield!(progress);
+                        return Ok(());
+                    }
+                    Ok(BackupStep::Busy(code)) => {
+                        busy += 1;
+
+                        if busy > options.busy_retries {
+                            return Err(Error::Database(Box::new(SqliteError::from_code(code))));
+                        }
+                    }
+                    Err(e) => {
+                        // the worker has already finished the backup
+                        backup.finished = true;
+                        return Err(e);
+                    }
+                }
+
+                sqlx_core::rt::sleep(options.step_sleep).await;
+            }
+        })
+    }
+}
+
+struct BackupGuard<'a> {
+    worker: &'a mut ConnectionWorker,
+    finished: bool,
+}
+
+impl Drop for BackupGuard<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.worker.release(Release::Backup);
+        }
+    }
 }
 
 impl Debug for SqliteConnection {
@@ -210,8 +655,13 @@ impl LockedSqliteHandle<'_> {
 
 impl Drop for ConnectionState {
     fn drop(&mut self) {
-        // explicitly drop statements before the connection handle is dropped
+        // explicitly drop statements, blob handles, backups and sessions before the connection
+        // handle is dropped
         self.statements.clear();
+        self.blobs.clear();
+        self.backup = None;
+        #[cfg(feature = "sqlite-session")]
+        self.sessions.clear();
     }
 }
 
@@ -0,0 +1,69 @@
+use std::cmp;
+use std::os::raw::{c_int, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use libsqlite3_sys::sqlite3_progress_handler;
+
+use crate::connection::handle::ConnectionHandle;
+
+pub(crate) type ProgressCallback = dyn FnMut() -> bool + Send + 'static;
+
+/// The progress handler of a connection.
+///
+/// SQLite only keeps a pointer to the state, so we own it here. This must be dropped after
+/// the connection handle is closed.
+#[derive(Default)]
+pub(crate) struct Progress {
+    state: Box<ProgressState>,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    callback: Option<Box<ProgressCallback>>,
+}
+
+impl Progress {
+    /// Call `callback` about every `num_ops` virtual machine instructions, interrupting the
+    /// running statement if it returns `false`.
+    pub(crate) fn set_callback(
+        &mut self,
+        handle: &mut ConnectionHandle,
+        num_ops: u32,
+        callback: Box<ProgressCallback>,
+    ) {
+        self.state.callback = Some(callback);
+
+        // SAFETY: the pointer stays valid until the connection is closed, as the box is not
+        // dropped before that
+        unsafe {
+            sqlite3_progress_handler(
+                handle.as_ptr(),
+                cmp::max(c_int::try_from(num_ops).unwrap_or(c_int::MAX), 1),
+                Some(progress_handler),
+                &mut *self.state as *mut ProgressState as *mut c_void,
+            );
+        }
+    }
+
+    pub(crate) fn clear_callback(&mut self, handle: &mut ConnectionHandle) {
+        // SAFETY: removing the handler is always sound
+        unsafe {
+            sqlite3_progress_handler(handle.as_ptr(), 0, None, ptr::null_mut());
+        }
+
+        self.state.callback = None;
+    }
+}
+
+unsafe extern "C" fn progress_handler(data: *mut c_void) -> c_int {
+    let state = &mut *(data as *mut ProgressState);
+
+    let proceed = match &mut state.callback {
+        // interrupt if the callback panics, as we can't unwind into SQLite
+        Some(callback) => catch_unwind(AssertUnwindSafe(callback)).unwrap_or(false),
+        None => true,
+    };
+
+    c_int::from(!proceed)
+}
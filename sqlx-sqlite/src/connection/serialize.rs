@@ -0,0 +1,106 @@
+use std::ffi::CString;
+use std::fmt::{self, Debug, Formatter};
+use std::os::raw::c_uint;
+use std::ptr;
+use std::slice;
+use std::sync::Arc;
+
+use libsqlite3_sys::{
+    sqlite3_deserialize, sqlite3_free, sqlite3_int64, sqlite3_malloc64, sqlite3_serialize,
+    SQLITE_DESERIALIZE_FREEONCLOSE, SQLITE_DESERIALIZE_RESIZEABLE, SQLITE_NOMEM, SQLITE_OK,
+};
+
+use crate::connection::handle::ConnectionHandle;
+use crate::error::Error;
+use crate::SqliteError;
+
+/// The contents of a database to load into new connections, as produced by
+/// [`SqliteConnection::serialize()`][crate::SqliteConnection::serialize].
+#[derive(Clone)]
+pub(crate) struct SerializedDatabase(Arc<[u8]>);
+
+impl SerializedDatabase {
+    pub(crate) fn new(data: Arc<[u8]>) -> Self {
+        Self(data)
+    }
+
+    /// Replace the `main` database of the connection with a private, in-memory copy of this data.
+    pub(crate) fn deserialize_into(&self, handle: &ConnectionHandle) -> Result<(), Error> {
+        let len = self.0.len();
+
+        // SQLite needs to own the buffer so the database can be resized when it's written to
+        // SAFETY: the buffer is valid for `len` bytes, and we have exclusive access to the
+        // database handle
+        let status = unsafe {
+            let buf = sqlite3_malloc64(len as u64) as *mut u8;
+
+            if buf.is_null() && len > 0 {
+                SQLITE_NOMEM
+            } else {
+                if len > 0 {
+                    ptr::copy_nonoverlapping(self.0.as_ptr(), buf, len);
+                }
+
+                // <https://www.sqlite.org/c3ref/deserialize.html>
+                // note: `buf` is freed by SQLite if this fails
+                sqlite3_deserialize(
+                    handle.as_ptr(),
+                    b"main\0".as_ptr().cast(),
+                    buf,
+                    len as sqlite3_int64,
+                    len as sqlite3_int64,
+                    (SQLITE_DESERIALIZE_FREEONCLOSE | SQLITE_DESERIALIZE_RESIZEABLE) as c_uint,
+                )
+            }
+        };
+
+        if status != SQLITE_OK {
+            return Err(Error::Database(Box::new(SqliteError::new(handle.as_ptr()))));
+        }
+
+        Ok(())
+    }
+}
+
+impl Debug for SerializedDatabase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SerializedDatabase")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+/// Copy the contents of `schema` (`main` by default) into a new buffer.
+pub(crate) fn serialize(
+    handle: &mut ConnectionHandle,
+    schema: Option<&str>,
+) -> Result<Vec<u8>, Error> {
+    let schema = CString::new(schema.unwrap_or("main"))
+        .map_err(|_| err_protocol!("invalid schema name: {:?}", schema))?;
+
+    let mut size: sqlite3_int64 = 0;
+
+    // <https://www.sqlite.org/c3ref/serialize.html>
+    // SAFETY: we have exclusive access to the database handle
+    unsafe {
+        let buf = sqlite3_serialize(handle.as_ptr(), schema.as_ptr(), &mut size, 0);
+
+        if buf.is_null() {
+            // an empty database is serialized as a null pointer with a size of zero,
+            // otherwise the size is left at -1
+            if size == 0 {
+                return Ok(Vec::new());
+            }
+
+            return Err(err_protocol!(
+                "failed to serialize schema {:?}; it may not exist or SQLite is out of memory",
+                schema
+            ));
+        }
+
+        let data = slice::from_raw_parts(buf, size as usize).to_vec();
+        sqlite3_free(buf.cast());
+
+        Ok(data)
+    }
+}
@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fmt::{self, Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr::{self, NonNull};
+use std::slice;
+
+use libsqlite3_sys::{
+    sqlite3_changeset_iter, sqlite3_free, sqlite3_session, sqlite3_value, sqlite3changeset_apply,
+    sqlite3changeset_conflict, sqlite3changeset_new, sqlite3changeset_old, sqlite3changeset_op,
+    sqlite3session_attach, sqlite3session_changeset, sqlite3session_create, sqlite3session_delete,
+    sqlite3session_isempty, sqlite3session_patchset, SQLITE_CHANGESET_ABORT,
+    SQLITE_CHANGESET_CONFLICT, SQLITE_CHANGESET_CONSTRAINT, SQLITE_CHANGESET_DATA,
+    SQLITE_CHANGESET_FOREIGN_KEY, SQLITE_CHANGESET_NOTFOUND, SQLITE_CHANGESET_OMIT,
+    SQLITE_CHANGESET_REPLACE, SQLITE_DELETE, SQLITE_INSERT, SQLITE_OK, SQLITE_UPDATE,
+};
+
+use crate::connection::handle::ConnectionHandle;
+use crate::connection::worker::Release;
+use crate::error::Error;
+use crate::type_info::DataType;
+use crate::{SqliteConnection, SqliteError, SqliteOperation, SqliteTypeInfo, SqliteValue};
+
+/// Records the changes made through a connection, opened with
+/// [`SqliteConnection::session()`].
+///
+/// Only changes to [attached][Self::attach] tables with a `PRIMARY KEY` are recorded. The
+/// connection can still be used through this handle while it's open, e.g. with
+/// `query.execute(&mut *session)`.
+///
+/// See the [Session Extension](https://www.sqlite.org/sessionintro.html) for details.
+pub struct SqliteSession<'c> {
+    conn: &'c mut SqliteConnection,
+    id: u64,
+}
+
+impl<'c> SqliteSession<'c> {
+    pub(crate) async fn open(
+        conn: &'c mut SqliteConnection,
+        schema: Option<&str>,
+    ) -> Result<SqliteSession<'c>, Error> {
+        let id = conn.worker.session_create(schema).await?;
+
+        Ok(SqliteSession { conn, id })
+    }
+
+    /// Start recording changes to `table`, or to all tables if `None`.
+    pub async fn attach(&mut self, table: Option<&str>) -> Result<(), Error> {
+        self.conn.worker.session_attach(self.id, table).await
+    }
+
+    /// Returns `true` if no changes have been recorded.
+    pub async fn is_empty(&mut self) -> Result<bool, Error> {
+        self.conn.worker.session_is_empty(self.id).await
+    }
+
+    /// Encode the recorded changes as a changeset, which can be applied to another database
+    /// with [`SqliteConnection::apply_changeset()`].
+    pub async fn changeset(&mut self) -> Result<Vec<u8>, Error> {
+        self.conn.worker.session_changeset(self.id, false).await
+    }
+
+    /// Encode the recorded changes as a patchset.
+    ///
+    /// Patchsets are smaller than changesets because they omit the original values of updated
+    /// and deleted rows, so fewer conflicts can be detected when they're applied.
+    pub async fn patchset(&mut self) -> Result<Vec<u8>, Error> {
+        self.conn.worker.session_changeset(self.id, true).await
+    }
+}
+
+impl Deref for SqliteSession<'_> {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &Self::Target {
+        self.conn
+    }
+}
+
+impl DerefMut for SqliteSession<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.conn
+    }
+}
+
+impl Drop for SqliteSession<'_> {
+    fn drop(&mut self) {
+        self.conn.worker.release(Release::Session(self.id));
+    }
+}
+
+impl Debug for SqliteSession<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SqliteSession").finish_non_exhaustive()
+    }
+}
+
+/// Why a change could not be applied by
+/// [`SqliteConnection::apply_changeset()`][crate::SqliteConnection::apply_changeset].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SqliteConflictKind {
+    /// The row to update or delete exists, but its current values don't match the original
+    /// values recorded in the changeset.
+    Data,
+    /// The row to update or delete does not exist.
+    NotFound,
+    /// The row to insert already exists.
+    Conflict,
+    /// Applying the change would violate a `UNIQUE`, `CHECK` or `NOT NULL` constraint.
+    Constraint,
+    /// Applying the changeset left foreign key constraints violated. This is reported once,
+    /// after all changes were applied.
+    ForeignKey,
+}
+
+/// How to resolve a conflict in
+/// [`SqliteConnection::apply_changeset()`][crate::SqliteConnection::apply_changeset].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SqliteConflictAction {
+    /// Skip the change, or for [`SqliteConflictKind::ForeignKey`], commit anyway.
+    Omit,
+    /// Overwrite the conflicting row with the change.
+    ///
+    /// Only valid for [`SqliteConflictKind::Data`] and [`SqliteConflictKind::Conflict`];
+    /// otherwise the changeset is rolled back with an error.
+    Replace,
+    /// Roll back all changes made by the changeset.
+    Abort,
+}
+
+/// A change that conflicts with the contents of the database.
+#[derive(Clone)]
+pub struct SqliteChangesetConflict {
+    kind: SqliteConflictKind,
+    table: String,
+    operation: Option<SqliteOperation>,
+    old: Vec<Option<SqliteValue>>,
+    new: Vec<Option<SqliteValue>>,
+    conflicting: Vec<Option<SqliteValue>>,
+}
+
+impl SqliteChangesetConflict {
+    pub fn kind(&self) -> SqliteConflictKind {
+        self.kind
+    }
+
+    /// The table the change applies to.
+    ///
+    /// Empty for [`SqliteConflictKind::ForeignKey`].
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// The kind of change, or `None` for [`SqliteConflictKind::ForeignKey`].
+    pub fn operation(&self) -> Option<SqliteOperation> {
+        self.operation
+    }
+
+    /// The original values of the row, for updates and deletes.
+    ///
+    /// For updates, columns that weren't changed are `None`.
+    pub fn old_values(&self) -> &[Option<SqliteValue>] {
+        &self.old
+    }
+
+    /// The new values of the row, for inserts and updates.
+    ///
+    /// For updates, columns that weren't changed are `None`.
+    pub fn new_values(&self) -> &[Option<SqliteValue>] {
+        &self.new
+    }
+
+    /// The current values of the conflicting row in the database, for
+    /// [`SqliteConflictKind::Data`] and [`SqliteConflictKind::Conflict`].
+    pub fn conflicting_values(&self) -> &[Option<SqliteValue>] {
+        &self.conflicting
+    }
+}
+
+impl Debug for SqliteChangesetConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SqliteChangesetConflict")
+            .field("kind", &self.kind)
+            .field("table", &self.table)
+            .field("operation", &self.operation)
+            .finish_non_exhaustive()
+    }
+}
+
+pub(crate) type ConflictHandler =
+    dyn FnMut(&SqliteChangesetConflict) -> SqliteConflictAction + Send + 'static;
+
+/// Apply a changeset or patchset in a single transaction.
+pub(crate) fn apply_changeset(
+    handle: &mut ConnectionHandle,
+    changeset: &mut [u8],
+    mut conflict: Box<ConflictHandler>,
+) -> Result<(), Error> {
+    let len = c_int::try_from(changeset.len())
+        .map_err(|_| err_protocol!("changeset is too large: {} bytes", changeset.len()))?;
+
+    // <https://www.sqlite.org/session/sqlite3changeset_apply.html>
+    // SAFETY: `changeset` and `conflict` outlive the call
+    let status = unsafe {
+        sqlite3changeset_apply(
+            handle.as_ptr(),
+            len,
+            changeset.as_mut_ptr() as *mut c_void,
+            None,
+            Some(conflict_handler),
+            &mut conflict as *mut Box<ConflictHandler> as *mut c_void,
+        )
+    };
+
+    if status != SQLITE_OK {
+        return Err(Error::Database(Box::new(SqliteError::new(handle.as_ptr()))));
+    }
+
+    Ok(())
+}
+
+unsafe extern "C" fn conflict_handler(
+    data: *mut c_void,
+    kind: c_int,
+    iter: *mut sqlite3_changeset_iter,
+) -> c_int {
+    let handler = &mut *(data as *mut Box<ConflictHandler>);
+
+    let kind = match kind {
+        SQLITE_CHANGESET_DATA => SqliteConflictKind::Data,
+        SQLITE_CHANGESET_NOTFOUND => SqliteConflictKind::NotFound,
+        SQLITE_CHANGESET_CONFLICT => SqliteConflictKind::Conflict,
+        SQLITE_CHANGESET_CONSTRAINT => SqliteConflictKind::Constraint,
+        SQLITE_CHANGESET_FOREIGN_KEY => SqliteConflictKind::ForeignKey,
+        _ => return SQLITE_CHANGESET_ABORT,
+    };
+
+    // a panic cannot unwind into SQLite, so we treat it as an abort
+    let action = catch_unwind(AssertUnwindSafe(|| {
+        let conflict = read_conflict(kind, iter);
+        handler(&conflict)
+    }))
+    .unwrap_or(SqliteConflictAction::Abort);
+
+    match action {
+        SqliteConflictAction::Omit => SQLITE_CHANGESET_OMIT,
+        SqliteConflictAction::Replace => SQLITE_CHANGESET_REPLACE,
+        SqliteConflictAction::Abort => SQLITE_CHANGESET_ABORT,
+    }
+}
+
+unsafe fn read_conflict(
+    kind: SqliteConflictKind,
+    iter: *mut sqlite3_changeset_iter,
+) -> SqliteChangesetConflict {
+    let mut conflict = SqliteChangesetConflict {
+        kind,
+        table: String::new(),
+        operation: None,
+        old: Vec::new(),
+        new: Vec::new(),
+        conflicting: Vec::new(),
+    };
+
+    // only the number of violations can be read from the iterator after a foreign key conflict
+    if kind == SqliteConflictKind::ForeignKey {
+        return conflict;
+    }
+
+    let mut table: *const c_char = ptr::null();
+    let mut columns: c_int = 0;
+    let mut op: c_int = 0;
+    let mut indirect: c_int = 0;
+
+    if sqlite3changeset_op(iter, &mut table, &mut columns, &mut op, &mut indirect) != SQLITE_OK {
+        return conflict;
+    }
+
+    if !table.is_null() {
+        conflict.table = CStr::from_ptr(table).to_string_lossy().into_owned();
+    }
+
+    conflict.operation = match op {
+        SQLITE_INSERT => Some(SqliteOperation::Insert),
+        SQLITE_UPDATE => Some(SqliteOperation::Update),
+        SQLITE_DELETE => Some(SqliteOperation::Delete),
+        _ => None,
+    };
+
+    if op != SQLITE_INSERT {
+        conflict.old = read_values(iter, columns, sqlite3changeset_old);
+    }
+
+    if op != SQLITE_DELETE {
+        conflict.new = read_values(iter, columns, sqlite3changeset_new);
+    }
+
+    if matches!(
+        kind,
+        SqliteConflictKind::Data | SqliteConflictKind::Conflict
+    ) {
+        conflict.conflicting = read_values(iter, columns, sqlite3changeset_conflict);
+    }
+
+    conflict
+}
+
+type ReadValueFn =
+    unsafe extern "C" fn(*mut sqlite3_changeset_iter, c_int, *mut *mut sqlite3_value) -> c_int;
+
+unsafe fn read_values(
+    iter: *mut sqlite3_changeset_iter,
+    columns: c_int,
+    read: ReadValueFn,
+) -> Vec<Option<SqliteValue>> {
+    (0..columns)
+        .map(|i| {
+            let mut value = ptr::null_mut();
+
+            if read(iter, i, &mut value) != SQLITE_OK || value.is_null() {
+                return None;
+            }
+
+            Some(SqliteValue::new(value, SqliteTypeInfo(DataType::Null)))
+        })
+        .collect()
+}
+
+/// The sessions opened on a connection, owned by the worker thread.
+#[derive(Default)]
+pub(crate) struct Sessions {
+    handles: HashMap<u64, SessionHandle>,
+    next_id: u64,
+}
+
+struct SessionHandle(NonNull<sqlite3_session>);
+
+// SAFETY: sessions are only accessed by the worker thread, like the connection handle
+unsafe impl Send for SessionHandle {}
+
+impl Sessions {
+    pub(crate) fn create(
+        &mut self,
+        handle: &mut ConnectionHandle,
+        schema: Option<&str>,
+    ) -> Result<u64, Error> {
+        let schema = CString::new(schema.unwrap_or("main"))
+            .map_err(|_| err_protocol!("invalid schema name: {:?}", schema))?;
+
+        let mut session = ptr::null_mut();
+
+        // <https://www.sqlite.org/session/sqlite3session_create.html>
+        // SAFETY: we have exclusive access to the database handle
+        let status =
+            unsafe { sqlite3session_create(handle.as_ptr(), schema.as_ptr(), &mut session) };
+
+        if status != SQLITE_OK {
+            return Err(Error::Database(Box::new(SqliteError::new(handle.as_ptr()))));
+        }
+
+        // SAFETY: `sqlite3session_create()` succeeded
+        let session = SessionHandle(unsafe { NonNull::new_unchecked(session) });
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.handles.insert(id, session);
+
+        Ok(id)
+    }
+
+    pub(crate) fn attach(
+        &mut self,
+        handle: &mut ConnectionHandle,
+        id: u64,
+        table: Option<&str>,
+    ) -> Result<(), Error> {
+        let session = self.get(id)?;
+
+        let table = table
+            .map(CString::new)
+            .transpose()
+            .map_err(|_| err_protocol!("invalid table name: {:?}", table))?;
+
+        // SAFETY: a null table name attaches all tables
+        let status = unsafe {
+            sqlite3session_attach(
+                session.as_ptr(),
+                table.as_ref().map_or(ptr::null(), |table| table.as_ptr()),
+            )
+        };
+
+        if status != SQLITE_OK {
+            return Err(Error::Database(Box::new(SqliteError::new(handle.as_ptr()))));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn is_empty(&mut self, id: u64) -> Result<bool, Error> {
+        let session = self.get(id)?;
+
+        // SAFETY: the session is open
+        Ok(unsafe { sqlite3session_isempty(session.as_ptr()) } != 0)
+    }
+
+    pub(crate) fn changeset(
+        &mut self,
+        handle: &mut ConnectionHandle,
+        id: u64,
+        patchset: bool,
+    ) -> Result<Vec<u8>, Error> {
+        let session = self.get(id)?;
+
+        let mut len: c_int = 0;
+        let mut buf: *mut c_void = ptr::null_mut();
+
+        // SAFETY: the session is open, and the buffer is freed below
+        unsafe {
+            let status = if patchset {
+                sqlite3session_patchset(session.as_ptr(), &mut len, &mut buf)
+            } else {
+                sqlite3session_changeset(session.as_ptr(), &mut len, &mut buf)
+            };
+
+            if status != SQLITE_OK {
+                return Err(Error::Database(Box::new(SqliteError::new(handle.as_ptr()))));
+            }
+
+            // an empty changeset may be returned as a null pointer
+            if buf.is_null() {
+                return Ok(Vec::new());
+            }
+
+            let data = slice::from_raw_parts(buf as *const u8, len as usize).to_vec();
+            sqlite3_free(buf);
+
+            Ok(data)
+        }
+    }
+
+    pub(crate) fn delete(&mut self, id: u64) {
+        self.handles.remove(&id);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.handles.clear();
+    }
+
+    fn get(&self, id: u64) -> Result<NonNull<sqlite3_session>, Error> {
+        self.handles
+            .get(&id)
+            .map(|session| session.0)
+            .ok_or_else(|| err_protocol!("session {} is not open", id))
+    }
+}
+
+impl Drop for SessionHandle {
+    fn drop(&mut self) {
+        // SAFETY: the handle is not used after this
+        unsafe {
+            sqlite3session_delete(self.0.as_ptr());
+        }
+    }
+}
@@ -0,0 +1,632 @@
+use std::cmp;
+use std::ffi::{CStr, CString};
+use std::fmt::{self, Debug, Formatter};
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use std::sync::Arc;
+
+use libsqlite3_sys::{
+    sqlite3, sqlite3_context, sqlite3_create_module_v2, sqlite3_declare_vtab, sqlite3_free,
+    sqlite3_index_info, sqlite3_int64, sqlite3_malloc64, sqlite3_module, sqlite3_value,
+    sqlite3_value_int64, sqlite3_value_type, sqlite3_vtab, sqlite3_vtab_cursor, SQLITE_ERROR,
+    SQLITE_INDEX_CONSTRAINT_EQ, SQLITE_INDEX_CONSTRAINT_GE, SQLITE_INDEX_CONSTRAINT_GLOB,
+    SQLITE_INDEX_CONSTRAINT_GT, SQLITE_INDEX_CONSTRAINT_IS, SQLITE_INDEX_CONSTRAINT_ISNOT,
+    SQLITE_INDEX_CONSTRAINT_ISNOTNULL, SQLITE_INDEX_CONSTRAINT_ISNULL, SQLITE_INDEX_CONSTRAINT_LE,
+    SQLITE_INDEX_CONSTRAINT_LIKE, SQLITE_INDEX_CONSTRAINT_LIMIT, SQLITE_INDEX_CONSTRAINT_LT,
+    SQLITE_INDEX_CONSTRAINT_MATCH, SQLITE_INDEX_CONSTRAINT_NE, SQLITE_INDEX_CONSTRAINT_OFFSET,
+    SQLITE_INDEX_CONSTRAINT_REGEXP, SQLITE_NULL, SQLITE_OK,
+};
+
+use crate::connection::function::{panic_message, set_result};
+use crate::connection::handle::ConnectionHandle;
+use crate::encode::Encode;
+use crate::error::{BoxDynError, Error};
+use crate::type_info::DataType;
+use crate::{Sqlite, SqliteError, SqliteTypeInfo, SqliteValue};
+
+/// A virtual table implemented in Rust, registered with
+/// [`SqliteConnectOptions::create_module()`][crate::SqliteConnectOptions::create_module].
+///
+/// An instance is created for each connection that uses the table, and is only accessed from
+/// the worker thread of that connection. Data shared between connections, like an in-memory
+/// buffer, should be kept behind an `Arc`.
+///
+/// See [The Virtual Table Mechanism Of SQLite](https://www.sqlite.org/vtab.html) for details.
+pub trait SqliteVirtualTable: Sized + 'static {
+    type Cursor: SqliteVirtualTableCursor;
+
+    /// The `CREATE TABLE` statement declaring the columns of the table, e.g.
+    /// `CREATE TABLE x(name TEXT, value REAL)`. The name of the table is ignored.
+    fn schema(&self) -> String;
+
+    /// Choose how to scan the table for a query, through `info`.
+    ///
+    /// By default, every query does a full scan of the table and SQLite filters the rows.
+    fn best_index(&self, info: &mut SqliteIndexInfo<'_>) -> Result<(), BoxDynError> {
+        let _ = info;
+        Ok(())
+    }
+
+    /// Open a new cursor for scanning the table.
+    fn open(&self) -> Result<Self::Cursor, BoxDynError>;
+
+    /// Insert a row, returning its rowid. `rowid` is `None` unless it was specified explicitly.
+    ///
+    /// By default, the table is read-only.
+    fn insert(&mut self, rowid: Option<i64>, values: Vec<SqliteValue>) -> Result<i64, BoxDynError> {
+        let _ = (rowid, values);
+        Err("virtual table is read-only".into())
+    }
+
+    /// Replace the row with the rowid `old_rowid`, which may be changed to `new_rowid`.
+    ///
+    /// By default, the table is read-only.
+    fn update(
+        &mut self,
+        old_rowid: i64,
+        new_rowid: i64,
+        values: Vec<SqliteValue>,
+    ) -> Result<(), BoxDynError> {
+        let _ = (old_rowid, new_rowid, values);
+        Err("virtual table is read-only".into())
+    }
+
+    /// Delete the row with the given rowid.
+    ///
+    /// By default, the table is read-only.
+    fn delete(&mut self, rowid: i64) -> Result<(), BoxDynError> {
+        let _ = rowid;
+        Err("virtual table is read-only".into())
+    }
+}
+
+/// A cursor scanning the rows of a [`SqliteVirtualTable`].
+pub trait SqliteVirtualTableCursor: Sized + 'static {
+    /// Start a new scan, positioned at the first row.
+    ///
+    /// `index_num` is the value set with [`SqliteIndexInfo::set_index_num()`], and `args` are
+    /// the values of the constraints passed with [`SqliteIndexInfo::use_constraint()`].
+    fn filter(&mut self, index_num: i32, args: Vec<SqliteValue>) -> Result<(), BoxDynError>;
+
+    /// Advance to the next row.
+    fn next(&mut self) -> Result<(), BoxDynError>;
+
+    /// Returns `true` if the cursor has moved past the last row.
+    fn eof(&self) -> bool;
+
+    /// Produce the value of the column with the given index in the current row, through
+    /// `result`.
+    fn column(&self, index: usize, result: &mut SqliteColumnResult<'_>) -> Result<(), BoxDynError>;
+
+    /// Returns the rowid of the current row.
+    fn rowid(&self) -> Result<i64, BoxDynError>;
+}
+
+/// The value of a column produced by [`SqliteVirtualTableCursor::column()`].
+///
+/// The column is `NULL` unless a value is set.
+pub struct SqliteColumnResult<'a> {
+    ctx: *mut sqlite3_context,
+    _marker: PhantomData<&'a mut sqlite3_context>,
+}
+
+impl SqliteColumnResult<'_> {
+    /// Set the value of the column, encoded the same way as a bind parameter.
+    pub fn set<'q>(&mut self, value: impl Encode<'q, Sqlite>) {
+        // SAFETY: the context is valid for the duration of `xColumn`
+        unsafe { set_result(self.ctx, value) }
+    }
+}
+
+/// The comparison operator of a [`SqliteIndexConstraint`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SqliteConstraintOp {
+    Eq,
+    Gt,
+    Le,
+    Lt,
+    Ge,
+    Match,
+    Like,
+    Glob,
+    Regexp,
+    Ne,
+    IsNot,
+    IsNotNull,
+    IsNull,
+    Is,
+    Limit,
+    Offset,
+    /// An operator overloaded by a function, with the given code.
+    Function(u8),
+}
+
+impl SqliteConstraintOp {
+    fn from_code(code: u8) -> Self {
+        match c_int::from(code) {
+            SQLITE_INDEX_CONSTRAINT_EQ => SqliteConstraintOp::Eq,
+            SQLITE_INDEX_CONSTRAINT_GT => SqliteConstraintOp::Gt,
+            SQLITE_INDEX_CONSTRAINT_LE => SqliteConstraintOp::Le,
+            SQLITE_INDEX_CONSTRAINT_LT => SqliteConstraintOp::Lt,
+            SQLITE_INDEX_CONSTRAINT_GE => SqliteConstraintOp::Ge,
+            SQLITE_INDEX_CONSTRAINT_MATCH => SqliteConstraintOp::Match,
+            SQLITE_INDEX_CONSTRAINT_LIKE => SqliteConstraintOp::Like,
+            SQLITE_INDEX_CONSTRAINT_GLOB => SqliteConstraintOp::Glob,
+            SQLITE_INDEX_CONSTRAINT_REGEXP => SqliteConstraintOp::Regexp,
+            SQLITE_INDEX_CONSTRAINT_NE => SqliteConstraintOp::Ne,
+            SQLITE_INDEX_CONSTRAINT_ISNOT => SqliteConstraintOp::IsNot,
+            SQLITE_INDEX_CONSTRAINT_ISNOTNULL => SqliteConstraintOp::IsNotNull,
+            SQLITE_INDEX_CONSTRAINT_ISNULL => SqliteConstraintOp::IsNull,
+            SQLITE_INDEX_CONSTRAINT_IS => SqliteConstraintOp::Is,
+            SQLITE_INDEX_CONSTRAINT_LIMIT => SqliteConstraintOp::Limit,
+            SQLITE_INDEX_CONSTRAINT_OFFSET => SqliteConstraintOp::Offset,
+            // codes of overloaded functions start at `SQLITE_INDEX_CONSTRAINT_FUNCTION`
+            _ => SqliteConstraintOp::Function(code),
+        }
+    }
+}
+
+/// A `WHERE` clause term that may be used to narrow a scan of a virtual table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SqliteIndexConstraint {
+    /// The index of the column, or `-1` for the rowid.
+    pub column: i32,
+    pub op: SqliteConstraintOp,
+    /// If `false`, the constraint cannot be used for this plan and must be ignored.
+    pub usable: bool,
+}
+
+/// A term of the `ORDER BY` clause of a query on a virtual table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SqliteIndexOrderBy {
+    /// The index of the column, or `-1` for the rowid.
+    pub column: i32,
+    pub desc: bool,
+}
+
+/// The query plan chosen by [`SqliteVirtualTable::best_index()`].
+///
+/// See [`sqlite3_index_info`](https://www.sqlite.org/vtab.html#the_xbestindex_method) for details.
+pub struct SqliteIndexInfo<'a> {
+    info: &'a mut sqlite3_index_info,
+}
+
+impl SqliteIndexInfo<'_> {
+    /// The constraints of the query, in the order expected by
+    /// [`.use_constraint()`][Self::use_constraint].
+    pub fn constraints(&self) -> impl Iterator<Item = SqliteIndexConstraint> + '_ {
+        // SAFETY: SQLite guarantees the array has `nConstraint` elements
+        unsafe { raw_slice(self.info.aConstraint, self.info.nConstraint) }
+            .iter()
+            .map(|constraint| SqliteIndexConstraint {
+                column: constraint.iColumn,
+                op: SqliteConstraintOp::from_code(constraint.op),
+                usable: constraint.usable != 0,
+            })
+    }
+
+    /// The terms of the `ORDER BY` clause of the query.
+    pub fn order_by(&self) -> impl Iterator<Item = SqliteIndexOrderBy> + '_ {
+        // SAFETY: SQLite guarantees the array has `nOrderBy` elements
+        unsafe { raw_slice(self.info.aOrderBy, self.info.nOrderBy) }
+            .iter()
+            .map(|order_by| SqliteIndexOrderBy {
+                column: order_by.iColumn,
+                desc: order_by.desc != 0,
+            })
+    }
+
+    /// Pass the right-hand value of the constraint with the given index to
+    /// [`SqliteVirtualTableCursor::filter()`], as the argument at `arg_index`.
+    ///
+    /// If `omit` is `true`, SQLite trusts the cursor to only return matching rows and doesn't
+    /// check the constraint again.
+    ///
+    /// ### Panics
+    /// If `constraint` is out of bounds.
+    pub fn use_constraint(&mut self, constraint: usize, arg_index: usize, omit: bool) {
+        assert!(
+            constraint < self.info.nConstraint as usize,
+            "constraint index out of bounds"
+        );
+
+        // SAFETY: the array has `nConstraint` elements, like `aConstraint`
+        let usage = unsafe { &mut *self.info.aConstraintUsage.add(constraint) };
+        usage.argvIndex = c_int::try_from(arg_index + 1).unwrap_or(c_int::MAX);
+        usage.omit = u8::from(omit);
+    }
+
+    /// Set a number identifying the chosen plan, which is passed to
+    /// [`SqliteVirtualTableCursor::filter()`].
+    pub fn set_index_num(&mut self, num: i32) {
+        self.info.idxNum = num;
+    }
+
+    /// Set to `true` if the cursor returns rows in the order given by
+    /// [`.order_by()`][Self::order_by], so SQLite doesn't sort them again.
+    pub fn set_order_by_consumed(&mut self, consumed: bool) {
+        self.info.orderByConsumed = c_int::from(consumed);
+    }
+
+    /// Set the approximate cost of the plan; SQLite chooses the plan with the lowest cost.
+    pub fn set_estimated_cost(&mut self, cost: f64) {
+        self.info.estimatedCost = cost;
+    }
+
+    /// Set the approximate number of rows returned by the plan.
+    pub fn set_estimated_rows(&mut self, rows: i64) {
+        self.info.estimatedRows = rows;
+    }
+}
+
+impl Debug for SqliteIndexInfo<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SqliteIndexInfo")
+            .field("constraints", &self.constraints().collect::<Vec<_>>())
+            .field("order_by", &self.order_by().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+unsafe fn raw_slice<'a, T>(data: *const T, len: c_int) -> &'a [T] {
+    if data.is_null() || len <= 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, len as usize)
+    }
+}
+
+/// A virtual table module that can be registered on any number of connections.
+#[derive(Clone)]
+pub struct Module {
+    name: Arc<str>,
+    create: Arc<CreateFn>,
+}
+
+type CreateFn = dyn Fn(&mut ConnectionHandle, &CString) -> c_int + Send + Sync + 'static;
+
+struct ModuleData<F> {
+    ffi: sqlite3_module,
+    connect: Arc<F>,
+}
+
+#[repr(C)]
+struct VTab<T> {
+    // must be the first field, as SQLite only knows about this part
+    base: sqlite3_vtab,
+    table: T,
+}
+
+#[repr(C)]
+struct VTabCursor<C> {
+    base: sqlite3_vtab_cursor,
+    cursor: C,
+}
+
+impl Module {
+    pub fn new<N, T, F>(name: N, connect: F) -> Self
+    where
+        N: Into<Arc<str>>,
+        T: SqliteVirtualTable,
+        F: Fn(&[String]) -> Result<T, BoxDynError> + Send + Sync + 'static,
+    {
+        let connect = Arc::new(connect);
+
+        Module {
+            name: name.into(),
+            create: Arc::new(move |handle, name| unsafe {
+                let data = Box::into_raw(Box::new(ModuleData {
+                    ffi: ffi_module::<T, F>(),
+                    connect: Arc::clone(&connect),
+                }));
+
+                // SAFETY: the module is boxed together with the state passed to the callbacks,
+                // and dropped by SQLite with `drop_module` when it's no longer used
+                sqlite3_create_module_v2(
+                    handle.as_ptr(),
+                    name.as_ptr(),
+                    &(*data).ffi,
+                    data as *mut c_void,
+                    Some(drop_module::<F>),
+                )
+            }),
+        }
+    }
+
+    pub(crate) fn create(&self, handle: &mut ConnectionHandle) -> Result<(), Error> {
+        let c_name = CString::new(&*self.name)
+            .map_err(|_| err_protocol!("invalid module name: {:?}", self.name))?;
+
+        // the destructor is invoked by SQLite if registering the module fails
+        let r = (self.create)(handle, &c_name);
+
+        if r == SQLITE_OK {
+            Ok(())
+        } else {
+            Err(Error::Database(Box::new(SqliteError::new(handle.as_ptr()))))
+        }
+    }
+}
+
+impl Debug for Module {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Module")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+fn ffi_module<T, F>() -> sqlite3_module
+where
+    T: SqliteVirtualTable,
+    F: Fn(&[String]) -> Result<T, BoxDynError>,
+{
+    // SAFETY: all fields are integers or nullable function pointers
+    let mut module: sqlite3_module = unsafe { mem::zeroed() };
+
+    module.iVersion = 1;
+    // the same callbacks for `xCreate` and `xConnect` allow the module to be used both as
+    // an eponymous table and with `CREATE VIRTUAL TABLE`
+    module.xCreate = Some(x_connect::<T, F>);
+    module.xConnect = Some(x_connect::<T, F>);
+    module.xBestIndex = Some(x_best_index::<T>);
+    module.xDisconnect = Some(x_disconnect::<T>);
+    module.xDestroy = Some(x_disconnect::<T>);
+    module.xOpen = Some(x_open::<T>);
+    module.xClose = Some(x_close::<T::Cursor>);
+    module.xFilter = Some(x_filter::<T::Cursor>);
+    module.xNext = Some(x_next::<T::Cursor>);
+    module.xEof = Some(x_eof::<T::Cursor>);
+    module.xColumn = Some(x_column::<T::Cursor>);
+    module.xRowid = Some(x_rowid::<T::Cursor>);
+    module.xUpdate = Some(x_update::<T>);
+
+    module
+}
+
+unsafe extern "C" fn drop_module<F>(p: *mut c_void) {
+    drop(Box::from_raw(p as *mut ModuleData<F>));
+}
+
+/// Run a user callback, turning errors and panics into a message for SQLite.
+///
+/// Unwinding across the FFI boundary is undefined behavior so panics must be caught here.
+fn call<R>(f: impl FnOnce() -> Result<R, BoxDynError>) -> Result<R, String> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(payload) => Err(format!(
+            "virtual table panicked: {}",
+            panic_message(&*payload)
+        )),
+    }
+}
+
+/// Copy `message` into a string allocated by SQLite, which SQLite frees.
+unsafe fn sqlite_string(message: &str) -> *mut c_char {
+    let buf = sqlite3_malloc64(message.len() as u64 + 1) as *mut c_char;
+
+    if !buf.is_null() {
+        ptr::copy_nonoverlapping(message.as_ptr() as *const c_char, buf, message.len());
+        *buf.add(message.len()) = 0;
+    }
+
+    buf
+}
+
+/// Report an error from a callback of a virtual table.
+unsafe fn vtab_error(vtab: *mut sqlite3_vtab, message: &str) -> c_int {
+    sqlite3_free((*vtab).zErrMsg as *mut c_void);
+    (*vtab).zErrMsg = sqlite_string(message);
+
+    SQLITE_ERROR
+}
+
+unsafe fn values(argc: c_int, argv: *mut *mut sqlite3_value) -> Vec<SqliteValue> {
+    (0..cmp::max(argc, 0) as usize)
+        .map(|i| SqliteValue::new(*argv.add(i), SqliteTypeInfo(DataType::Null)))
+        .collect()
+}
+
+unsafe extern "C" fn x_connect<T, F>(
+    db: *mut sqlite3,
+    aux: *mut c_void,
+    argc: c_int,
+    argv: *const *const c_char,
+    pp_vtab: *mut *mut sqlite3_vtab,
+    pz_err: *mut *mut c_char,
+) -> c_int
+where
+    T: SqliteVirtualTable,
+    F: Fn(&[String]) -> Result<T, BoxDynError>,
+{
+    let module = &*(aux as *const ModuleData<F>);
+
+    let res = call(|| {
+        // the first three arguments are the names of the module, database and table
+        let args: Vec<String> = (3..cmp::max(argc, 3) as usize)
+            .map(|i| CStr::from_ptr(*argv.add(i)).to_string_lossy().into_owned())
+            .collect();
+
+        let table = (module.connect)(&args)?;
+        let schema = CString::new(table.schema())?;
+
+        if sqlite3_declare_vtab(db, schema.as_ptr()) != SQLITE_OK {
+            return Err(SqliteError::new(db).into());
+        }
+
+        Ok(table)
+    });
+
+    match res {
+        Ok(table) => {
+            *pp_vtab = Box::into_raw(Box::new(VTab {
+                // SAFETY: SQLite initializes the fields after this returns
+                base: mem::zeroed(),
+                table,
+            })) as *mut sqlite3_vtab;
+
+            SQLITE_OK
+        }
+        Err(message) => {
+            *pz_err = sqlite_string(&message);
+            SQLITE_ERROR
+        }
+    }
+}
+
+unsafe extern "C" fn x_best_index<T: SqliteVirtualTable>(
+    vtab: *mut sqlite3_vtab,
+    info: *mut sqlite3_index_info,
+) -> c_int {
+    let table = &(*(vtab as *mut VTab<T>)).table;
+    let mut info = SqliteIndexInfo { info: &mut *info };
+
+    match call(|| table.best_index(&mut info)) {
+        Ok(()) => SQLITE_OK,
+        Err(message) => vtab_error(vtab, &message),
+    }
+}
+
+unsafe extern "C" fn x_disconnect<T>(vtab: *mut sqlite3_vtab) -> c_int {
+    let vtab = Box::from_raw(vtab as *mut VTab<T>);
+    sqlite3_free(vtab.base.zErrMsg as *mut c_void);
+
+    SQLITE_OK
+}
+
+unsafe extern "C" fn x_open<T: SqliteVirtualTable>(
+    vtab: *mut sqlite3_vtab,
+    pp_cursor: *mut *mut sqlite3_vtab_cursor,
+) -> c_int {
+    let table = &(*(vtab as *mut VTab<T>)).table;
+
+    match call(|| table.open()) {
+        Ok(cursor) => {
+            *pp_cursor = Box::into_raw(Box::new(VTabCursor {
+                // SAFETY: SQLite initializes the fields after this returns
+                base: mem::zeroed(),
+                cursor,
+            })) as *mut sqlite3_vtab_cursor;
+
+            SQLITE_OK
+        }
+        Err(message) => vtab_error(vtab, &message),
+    }
+}
+
+unsafe extern "C" fn x_close<C>(cursor: *mut sqlite3_vtab_cursor) -> c_int {
+    drop(Box::from_raw(cursor as *mut VTabCursor<C>));
+
+    SQLITE_OK
+}
+
+unsafe extern "C" fn x_filter<C: SqliteVirtualTableCursor>(
+    cursor: *mut sqlite3_vtab_cursor,
+    index_num: c_int,
+    _index_str: *const c_char,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) -> c_int {
+    let c = &mut (*(cursor as *mut VTabCursor<C>)).cursor;
+
+    match call(|| c.filter(index_num, values(argc, argv))) {
+        Ok(()) => SQLITE_OK,
+        Err(message) => vtab_error((*cursor).pVtab, &message),
+    }
+}
+
+unsafe extern "C" fn x_next<C: SqliteVirtualTableCursor>(
+    cursor: *mut sqlite3_vtab_cursor,
+) -> c_int {
+    let c = &mut (*(cursor as *mut VTabCursor<C>)).cursor;
+
+    match call(|| c.next()) {
+        Ok(()) => SQLITE_OK,
+        Err(message) => vtab_error((*cursor).pVtab, &message),
+    }
+}
+
+unsafe extern "C" fn x_eof<C: SqliteVirtualTableCursor>(cursor: *mut sqlite3_vtab_cursor) -> c_int {
+    let c = &(*(cursor as *mut VTabCursor<C>)).cursor;
+
+    // end the scan if this panics
+    c_int::from(call(|| Ok(c.eof())).unwrap_or(true))
+}
+
+unsafe extern "C" fn x_column<C: SqliteVirtualTableCursor>(
+    cursor: *mut sqlite3_vtab_cursor,
+    ctx: *mut sqlite3_context,
+    index: c_int,
+) -> c_int {
+    let c = &(*(cursor as *mut VTabCursor<C>)).cursor;
+    let mut result = SqliteColumnResult {
+        ctx,
+        _marker: PhantomData,
+    };
+
+    match call(|| c.column(index as usize, &mut result)) {
+        Ok(()) => SQLITE_OK,
+        Err(message) => vtab_error((*cursor).pVtab, &message),
+    }
+}
+
+unsafe extern "C" fn x_rowid<C: SqliteVirtualTableCursor>(
+    cursor: *mut sqlite3_vtab_cursor,
+    p_rowid: *mut sqlite3_int64,
+) -> c_int {
+    let c = &(*(cursor as *mut VTabCursor<C>)).cursor;
+
+    match call(|| c.rowid()) {
+        Ok(rowid) => {
+            *p_rowid = rowid;
+            SQLITE_OK
+        }
+        Err(message) => vtab_error((*cursor).pVtab, &message),
+    }
+}
+
+unsafe extern "C" fn x_update<T: SqliteVirtualTable>(
+    vtab: *mut sqlite3_vtab,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+    p_rowid: *mut sqlite3_int64,
+) -> c_int {
+    let table = &mut (*(vtab as *mut VTab<T>)).table;
+
+    // <https://www.sqlite.org/vtab.html#xupdate>
+    let res = call(|| {
+        if argc == 1 {
+            return table.delete(sqlite3_value_int64(*argv));
+        }
+
+        let values = values(argc - 2, argv.add(2));
+
+        if sqlite3_value_type(*argv) == SQLITE_NULL {
+            let rowid = match sqlite3_value_type(*argv.add(1)) {
+                SQLITE_NULL => None,
+                _ => Some(sqlite3_value_int64(*argv.add(1))),
+            };
+
+            *p_rowid = table.insert(rowid, values)?;
+
+            Ok(())
+        } else {
+            table.update(
+                sqlite3_value_int64(*argv),
+                sqlite3_value_int64(*argv.add(1)),
+                values,
+            )
+        }
+    });
+
+    match res {
+        Ok(()) => SQLITE_OK,
+        Err(message) => vtab_error(vtab, &message),
+    }
+}
@@ -1,25 +1,40 @@
 use std::borrow::Cow;
+use std::cmp;
 use std::future::Future;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
 use futures_intrusive::sync::{Mutex, MutexGuard};
 
 use futures_channel::oneshot;
+use futures_core::future::BoxFuture;
+use futures_core::Stream;
+use libsqlite3_sys::sqlite3_interrupt;
 use sqlx_core::describe::Describe;
 use sqlx_core::error::Error;
+use sqlx_core::rt;
 use sqlx_core::transaction::{
     begin_ansi_transaction_sql, commit_ansi_transaction_sql, rollback_ansi_transaction_sql,
 };
 use sqlx_core::Either;
 
+use crate::connection::backup::{Backup, BackupDirection, BackupStep, BackupTarget};
 use crate::connection::collation::create_collation;
 use crate::connection::describe::describe;
 use crate::connection::establish::EstablishParams;
+use crate::connection::function::Function;
+use crate::connection::hooks::SqliteUpdateEvent;
+use crate::connection::progress::ProgressCallback;
+use crate::connection::serialize::serialize;
+#[cfg(feature = "sqlite-session")]
+use crate::connection::session::{apply_changeset, ConflictHandler};
 use crate::connection::ConnectionState;
 use crate::connection::{execute, ConnectionHandleRaw};
-use crate::{Sqlite, SqliteArguments, SqliteQueryResult, SqliteRow, SqliteStatement};
+use crate::statement::VirtualStatement;
+use crate::{Sqlite, SqliteArguments, SqliteError, SqliteQueryResult, SqliteRow, SqliteStatement};
 
 // Each SQLite connection has a dedicated thread.
 
@@ -33,23 +48,58 @@ pub(crate) struct ConnectionWorker {
     pub(crate) handle_raw: ConnectionHandleRaw,
     /// Mutex for locking access to the database.
     pub(crate) shared: Arc<WorkerSharedState>,
+    /// Queries are interrupted once their results take longer than this to arrive.
+    statement_timeout: Option<Duration>,
 }
 
 pub(crate) struct WorkerSharedState {
     pub(crate) cached_statements_size: AtomicUsize,
     pub(crate) conn: Mutex<ConnectionState>,
+    /// Resources whose handles were dropped, to be released before the next command runs.
+    pending_releases: std::sync::Mutex<Vec<Release>>,
+    /// The queries which may be interrupted from other threads.
+    queries: std::sync::Mutex<QueryIds>,
+    next_query_id: AtomicU64,
+}
+
+#[derive(Default)]
+struct QueryIds {
+    /// The query the worker is running, if any.
+    running: Option<u64>,
+    /// Queries up to this one are interrupted, and skipped if the worker hasn't started them.
+    interrupted: Option<u64>,
+}
+
+/// A resource owned by the worker thread that is released when the handle to it is dropped.
+///
+/// `Drop` impls must not block on the command channel, so these are released through
+/// [`ConnectionWorker::release()`] instead of a regular command.
+#[derive(Debug)]
+pub(crate) enum Release {
+    Backup,
+    Blob(u64),
+    #[cfg(feature = "sqlite-session")]
+    Session(u64),
 }
 
 enum Command {
     Prepare {
+        id: u64,
         query: Box<str>,
         tx: oneshot::Sender<Result<SqliteStatement<'static>, Error>>,
     },
     Describe {
+        id: u64,
         query: Box<str>,
         tx: oneshot::Sender<Result<Describe<Sqlite>, Error>>,
     },
+    IsReadOnly {
+        id: u64,
+        query: Box<str>,
+        tx: oneshot::Sender<Result<bool, Error>>,
+    },
     Execute {
+        id: u64,
         query: Box<str>,
         arguments: Option<SqliteArguments<'static>>,
         persistent: bool,
@@ -68,6 +118,92 @@ enum Command {
         create_collation:
             Box<dyn FnOnce(&mut ConnectionState) -> Result<(), Error> + Send + Sync + 'static>,
     },
+    CreateFunction {
+        function: Function,
+        tx: oneshot::Sender<Result<(), Error>>,
+    },
+    SetUpdateHook {
+        events: flume::Sender<SqliteUpdateEvent>,
+        tx: oneshot::Sender<()>,
+    },
+    SetCommitHook {
+        events: flume::Sender<()>,
+        should_commit: Box<dyn FnMut() -> bool + Send + 'static>,
+        tx: oneshot::Sender<()>,
+    },
+    SetRollbackHook {
+        events: flume::Sender<()>,
+        tx: oneshot::Sender<()>,
+    },
+    ClearHooks {
+        tx: oneshot::Sender<()>,
+    },
+    SetProgressHandler {
+        num_ops: u32,
+        callback: Box<ProgressCallback>,
+    },
+    ClearProgressHandler,
+    Serialize {
+        schema: Option<Box<str>>,
+        tx: oneshot::Sender<Result<Vec<u8>, Error>>,
+    },
+    BlobOpen {
+        table: Box<str>,
+        column: Box<str>,
+        rowid: i64,
+        read_write: bool,
+        tx: oneshot::Sender<Result<(u64, u64), Error>>,
+    },
+    BlobRead {
+        id: u64,
+        offset: u64,
+        len: usize,
+        tx: oneshot::Sender<Result<Vec<u8>, Error>>,
+    },
+    BlobWrite {
+        id: u64,
+        offset: u64,
+        data: Vec<u8>,
+        tx: oneshot::Sender<Result<usize, Error>>,
+    },
+    #[cfg(feature = "sqlite-session")]
+    SessionCreate {
+        schema: Option<Box<str>>,
+        tx: oneshot::Sender<Result<u64, Error>>,
+    },
+    #[cfg(feature = "sqlite-session")]
+    SessionAttach {
+        id: u64,
+        table: Option<Box<str>>,
+        tx: oneshot::Sender<Result<(), Error>>,
+    },
+    #[cfg(feature = "sqlite-session")]
+    SessionIsEmpty {
+        id: u64,
+        tx: oneshot::Sender<Result<bool, Error>>,
+    },
+    #[cfg(feature = "sqlite-session")]
+    SessionChangeset {
+        id: u64,
+        patchset: bool,
+        tx: oneshot::Sender<Result<Vec<u8>, Error>>,
+    },
+    #[cfg(feature = "sqlite-session")]
+    ApplyChangeset {
+        changeset: Vec<u8>,
+        conflict: Box<ConflictHandler>,
+        tx: oneshot::Sender<Result<(), Error>>,
+    },
+    BackupInit {
+        target: Box<BackupTarget>,
+        direction: BackupDirection,
+        tx: oneshot::Sender<Result<(), Error>>,
+    },
+    BackupStep {
+        pages: c_int,
+        tx: oneshot::Sender<Result<BackupStep, Error>>,
+    },
+    ReleasePending,
     UnlockDb,
     ClearCache {
         tx: oneshot::Sender<()>,
@@ -103,6 +239,9 @@ impl ConnectionWorker {
                     // and then immediately try to relock it; an unfair mutex would immediately
                     // grant us the lock even if another task is waiting.
                     conn: Mutex::new(conn, true),
+                    pending_releases: std::sync::Mutex::new(Vec::new()),
+                    queries: std::sync::Mutex::default(),
+                    next_query_id: AtomicU64::new(0),
                 });
                 let mut conn = shared.conn.try_lock().unwrap();
 
@@ -111,6 +250,7 @@ impl ConnectionWorker {
                         command_tx,
                         handle_raw: conn.handle.to_raw(),
                         shared: Arc::clone(&shared),
+                        statement_timeout: params.statement_timeout,
                     }))
                     .is_err()
                 {
@@ -122,10 +262,21 @@ impl ConnectionWorker {
                 // would rollback an already completed transaction.
                 let mut ignore_next_start_rollback = false;
 
+                // the writer of a `SqliteRwPool` takes the write lock as soon as a transaction
+                // starts, so it doesn't fail with `SQLITE_BUSY` when upgrading the lock later
+                let begin_immediate = params.begin_immediate;
+
                 for cmd in command_rx {
+                    for release in shared.take_pending_releases() {
+                        release.apply(&mut conn);
+                    }
+
                     match cmd {
-                        Command::Prepare { query, tx } => {
-                            tx.send(prepare(&mut conn, &query).map(|prepared| {
+                        Command::Prepare { id, query, tx } => {
+                            let res = shared
+                                .run_query(id, || prepare(&mut conn, &query))
+                                .unwrap_or(Err(Error::Interrupted));
+                            tx.send(res.map(|prepared| {
                                 update_cached_statements_size(
                                     &conn,
                                     &shared.cached_statements_size,
@@ -134,40 +285,62 @@ impl ConnectionWorker {
                             }))
                             .ok();
                         }
-                        Command::Describe { query, tx } => {
-                            tx.send(describe(&mut conn, &query)).ok();
+                        Command::Describe { id, query, tx } => {
+                            let res = shared
+                                .run_query(id, || describe(&mut conn, &query))
+                                .unwrap_or(Err(Error::Interrupted));
+                            tx.send(res).ok();
+                        }
+                        Command::IsReadOnly { id, query, tx } => {
+                            let res = shared
+                                .run_query(id, || is_read_only(&mut conn, &query))
+                                .unwrap_or(Err(Error::Interrupted));
+                            tx.send(res).ok();
                         }
                         Command::Execute {
+                            id,
                             query,
                             arguments,
                             persistent,
                             tx,
                         } => {
-                            let iter = match execute::iter(&mut conn, &query, arguments, persistent)
-                            {
-                                Ok(iter) => iter,
-                                Err(e) => {
-                                    tx.send(Err(e)).ok();
-                                    continue;
+                            let res = shared.run_query(id, || {
+                                match execute::iter(&mut conn, &query, arguments, persistent) {
+                                    Ok(iter) => {
+                                        for res in iter {
+                                            let res = res.map_err(map_interrupt);
+                                            // stepping an interrupted statement again would
+                                            // restart it
+                                            let interrupted =
+                                                matches!(res, Err(Error::Interrupted));
+
+                                            if tx.send(res).is_err() || interrupted {
+                                                break;
+                                            }
+                                        }
+                                    }
+                                    Err(e) => {
+                                        tx.send(Err(map_interrupt(e))).ok();
+                                    }
                                 }
-                            };
+                            });
 
-                            for res in iter {
-                                if tx.send(res).is_err() {
-                                    break;
-                                }
+                            if res.is_none() {
+                                tx.send(Err(Error::Interrupted)).ok();
                             }
 
                             update_cached_statements_size(&conn, &shared.cached_statements_size);
                         }
                         Command::Begin { tx } => {
                             let depth = conn.transaction_depth;
-                            let res =
-                                conn.handle
-                                    .exec(begin_ansi_transaction_sql(depth))
-                                    .map(|_| {
-                                        conn.transaction_depth += 1;
-                                    });
+                            let sql = if depth == 0 && begin_immediate {
+                                Cow::Borrowed("BEGIN IMMEDIATE")
+                            } else {
+                                begin_ansi_transaction_sql(depth)
+                            };
+                            let res = conn.handle.exec(sql).map(|_| {
+                                conn.transaction_depth += 1;
+                            });
                             let res_ok = res.is_ok();
 
                             if tx.blocking_send(res).is_err() && res_ok {
@@ -246,6 +419,129 @@ impl ConnectionWorker {
                                 tracing::warn!(%error, "error applying collation in background worker");
                             }
                         }
+                        Command::CreateFunction { function, tx } => {
+                            tx.send(function.create(&mut conn.handle)).ok();
+                        }
+                        Command::SetUpdateHook { events, tx } => {
+                            let conn = &mut *conn;
+                            conn.hooks.set_update_hook(&mut conn.handle, events);
+                            tx.send(()).ok();
+                        }
+                        Command::SetCommitHook {
+                            events,
+                            should_commit,
+                            tx,
+                        } => {
+                            let conn = &mut *conn;
+                            conn.hooks
+                                .set_commit_hook(&mut conn.handle, events, should_commit);
+                            tx.send(()).ok();
+                        }
+                        Command::SetRollbackHook { events, tx } => {
+                            let conn = &mut *conn;
+                            conn.hooks.set_rollback_hook(&mut conn.handle, events);
+                            tx.send(()).ok();
+                        }
+                        Command::ClearHooks { tx } => {
+                            let conn = &mut *conn;
+                            conn.hooks.clear(&mut conn.handle);
+                            tx.send(()).ok();
+                        }
+                        Command::SetProgressHandler { num_ops, callback } => {
+                            let conn = &mut *conn;
+                            conn.progress
+                                .set_callback(&mut conn.handle, num_ops, callback);
+                        }
+                        Command::ClearProgressHandler => {
+                            let conn = &mut *conn;
+                            conn.progress.clear_callback(&mut conn.handle);
+                        }
+                        Command::Serialize { schema, tx } => {
+                            tx.send(serialize(&mut conn.handle, schema.as_deref())).ok();
+                        }
+                        Command::BlobOpen {
+                            table,
+                            column,
+                            rowid,
+                            read_write,
+                            tx,
+                        } => {
+                            let conn = &mut *conn;
+                            tx.send(conn.blobs.open(
+                                &mut conn.handle,
+                                &table,
+                                &column,
+                                rowid,
+                                read_write,
+                            ))
+                            .ok();
+                        }
+                        Command::BlobRead {
+                            id,
+                            offset,
+                            len,
+                            tx,
+                        } => {
+                            let conn = &mut *conn;
+                            tx.send(conn.blobs.read(&mut conn.handle, id, offset, len))
+                                .ok();
+                        }
+                        Command::BlobWrite {
+                            id,
+                            offset,
+                            data,
+                            tx,
+                        } => {
+                            let conn = &mut *conn;
+                            tx.send(conn.blobs.write(&mut conn.handle, id, offset, &data))
+                                .ok();
+                        }
+                        #[cfg(feature = "sqlite-session")]
+                        Command::SessionCreate { schema, tx } => {
+                            let conn = &mut *conn;
+                            tx.send(conn.sessions.create(&mut conn.handle, schema.as_deref()))
+                                .ok();
+                        }
+                        #[cfg(feature = "sqlite-session")]
+                        Command::SessionAttach { id, table, tx } => {
+                            let conn = &mut *conn;
+                            tx.send(conn.sessions.attach(&mut conn.handle, id, table.as_deref()))
+                                .ok();
+                        }
+                        #[cfg(feature = "sqlite-session")]
+                        Command::SessionIsEmpty { id, tx } => {
+                            tx.send(conn.sessions.is_empty(id)).ok();
+                        }
+                        #[cfg(feature = "sqlite-session")]
+                        Command::SessionChangeset { id, patchset, tx } => {
+                            let conn = &mut *conn;
+                            tx.send(conn.sessions.changeset(&mut conn.handle, id, patchset))
+                                .ok();
+                        }
+                        #[cfg(feature = "sqlite-session")]
+                        Command::ApplyChangeset {
+                            mut changeset,
+                            conflict,
+                            tx,
+                        } => {
+                            tx.send(apply_changeset(&mut conn.handle, &mut changeset, conflict))
+                                .ok();
+                        }
+                        Command::BackupInit {
+                            target,
+                            direction,
+                            tx,
+                        } => {
+                            let res = Backup::init(&conn.handle, *target, direction);
+                            tx.send(res.map(|backup| conn.backup = Some(backup))).ok();
+                        }
+                        Command::BackupStep { pages, tx } => {
+                            let conn = &mut *conn;
+                            tx.send(Backup::step(&mut conn.backup, &conn.handle, pages))
+                                .ok();
+                        }
+                        // the pending releases were applied above
+                        Command::ReleasePending => {}
                         Command::ClearCache { tx } => {
                             conn.statements.clear();
                             update_cached_statements_size(&conn, &shared.cached_statements_size);
@@ -274,19 +570,30 @@ impl ConnectionWorker {
     }
 
     pub(crate) async fn prepare(&mut self, query: &str) -> Result<SqliteStatement<'static>, Error> {
-        self.oneshot_cmd(|tx| Command::Prepare {
+        self.interruptible_cmd(|id, tx| Command::Prepare {
+            id,
             query: query.into(),
             tx,
         })
-        .await?
+        .await
     }
 
     pub(crate) async fn describe(&mut self, query: &str) -> Result<Describe<Sqlite>, Error> {
-        self.oneshot_cmd(|tx| Command::Describe {
+        self.interruptible_cmd(|id, tx| Command::Describe {
+            id,
             query: query.into(),
             tx,
         })
-        .await?
+        .await
+    }
+
+    pub(crate) async fn is_read_only(&mut self, query: &str) -> Result<bool, Error> {
+        self.interruptible_cmd(|id, tx| Command::IsReadOnly {
+            id,
+            query: query.into(),
+            tx,
+        })
+        .await
     }
 
     pub(crate) async fn execute(
@@ -295,11 +602,16 @@ impl ConnectionWorker {
         args: Option<SqliteArguments<'_>>,
         chan_size: usize,
         persistent: bool,
-    ) -> Result<flume::Receiver<Result<Either<SqliteQueryResult, SqliteRow>, Error>>, Error> {
+    ) -> Result<
+        impl Stream<Item = Result<Either<SqliteQueryResult, SqliteRow>, Error>> + Send + 'static,
+        Error,
+    > {
         let (tx, rx) = flume::bounded(chan_size);
+        let guard = self.begin_query();
 
         self.command_tx
             .send_async(Command::Execute {
+                id: guard.id,
                 query: query.into(),
                 arguments: args.map(SqliteArguments::into_static),
                 persistent,
@@ -308,7 +620,34 @@ impl ConnectionWorker {
             .await
             .map_err(|_| Error::WorkerCrashed)?;
 
-        Ok(rx)
+        // only the time spent waiting for results counts toward the timeout, not the time the
+        // caller takes to process them
+        let remaining = self.statement_timeout;
+
+        Ok(futures_util::stream::unfold(
+            (rx, guard, remaining),
+            |(rx, guard, remaining)| async move {
+                let started_at = Instant::now();
+
+                let res = match remaining {
+                    Some(remaining) => match rt::timeout(remaining, rx.recv_async()).await {
+                        Ok(res) => res,
+                        Err(_) => {
+                            guard.interrupt();
+                            rx.recv_async().await
+                        }
+                    },
+                    None => rx.recv_async().await,
+                };
+
+                let remaining = remaining.map(|r| r.saturating_sub(started_at.elapsed()));
+
+                // the channel is closed once the query completes
+                let res = res.ok()?;
+
+                Some((res, (rx, guard, remaining)))
+            },
+        ))
     }
 
     pub(crate) async fn begin(&mut self) -> Result<(), Error> {
@@ -350,6 +689,35 @@ impl ConnectionWorker {
         rx.await.map_err(|_| Error::WorkerCrashed)
     }
 
+    /// Like `oneshot_cmd()`, but the command is interrupted if it exceeds the statement
+    /// timeout, or if the returned future is dropped before it completes.
+    async fn interruptible_cmd<F, T>(&mut self, command: F) -> Result<T, Error>
+    where
+        F: FnOnce(u64, oneshot::Sender<Result<T, Error>>) -> Command,
+    {
+        let (tx, mut rx) = oneshot::channel();
+        let guard = self.begin_query();
+
+        self.command_tx
+            .send_async(command(guard.id, tx))
+            .await
+            .map_err(|_| Error::WorkerCrashed)?;
+
+        let res = match self.statement_timeout {
+            Some(timeout) => match rt::timeout(timeout, &mut rx).await {
+                Ok(res) => res,
+                Err(_) => {
+                    guard.interrupt();
+                    rx.await
+                }
+            },
+            None => rx.await,
+        };
+
+        res.map_err(|_| Error::WorkerCrashed)?
+            .map_err(map_interrupt)
+    }
+
     async fn oneshot_cmd_with_ack<F, T>(&mut self, command: F) -> Result<T, Error>
     where
         F: FnOnce(rendezvous_oneshot::Sender<T>) -> Command,
@@ -381,6 +749,227 @@ impl ConnectionWorker {
         Ok(())
     }
 
+    pub(crate) async fn create_function(&mut self, function: Function) -> Result<(), Error> {
+        self.oneshot_cmd(|tx| Command::CreateFunction { function, tx })
+            .await?
+    }
+
+    pub(crate) async fn set_update_hook(
+        &mut self,
+        events: flume::Sender<SqliteUpdateEvent>,
+    ) -> Result<(), Error> {
+        self.oneshot_cmd(|tx| Command::SetUpdateHook { events, tx })
+            .await
+    }
+
+    pub(crate) async fn set_commit_hook(
+        &mut self,
+        events: flume::Sender<()>,
+        should_commit: Box<dyn FnMut() -> bool + Send + 'static>,
+    ) -> Result<(), Error> {
+        self.oneshot_cmd(|tx| Command::SetCommitHook {
+            events,
+            should_commit,
+            tx,
+        })
+        .await
+    }
+
+    pub(crate) async fn set_rollback_hook(
+        &mut self,
+        events: flume::Sender<()>,
+    ) -> Result<(), Error> {
+        self.oneshot_cmd(|tx| Command::SetRollbackHook { events, tx })
+            .await
+    }
+
+    pub(crate) async fn clear_hooks(&mut self) -> Result<(), Error> {
+        self.oneshot_cmd(|tx| Command::ClearHooks { tx }).await
+    }
+
+    pub(crate) async fn set_progress_handler(
+        &mut self,
+        num_ops: u32,
+        callback: Box<ProgressCallback>,
+    ) -> Result<(), Error> {
+        self.send(Command::SetProgressHandler { num_ops, callback })
+            .await
+    }
+
+    pub(crate) async fn clear_progress_handler(&mut self) -> Result<(), Error> {
+        self.send(Command::ClearProgressHandler).await
+    }
+
+    pub(crate) async fn serialize(&mut self, schema: Option<&str>) -> Result<Vec<u8>, Error> {
+        self.oneshot_cmd(|tx| Command::Serialize {
+            schema: schema.map(Into::into),
+            tx,
+        })
+        .await?
+    }
+
+    pub(crate) async fn blob_open(
+        &mut self,
+        table: &str,
+        column: &str,
+        rowid: i64,
+        read_write: bool,
+    ) -> Result<(u64, u64), Error> {
+        self.oneshot_cmd(|tx| Command::BlobOpen {
+            table: table.into(),
+            column: column.into(),
+            rowid,
+            read_write,
+            tx,
+        })
+        .await?
+    }
+
+    pub(crate) fn blob_read(
+        &self,
+        id: u64,
+        offset: u64,
+        len: usize,
+    ) -> BoxFuture<'static, Result<Vec<u8>, Error>> {
+        self.detached_oneshot_cmd(|tx| Command::BlobRead {
+            id,
+            offset,
+            len,
+            tx,
+        })
+    }
+
+    pub(crate) fn blob_write(
+        &self,
+        id: u64,
+        offset: u64,
+        data: Vec<u8>,
+    ) -> BoxFuture<'static, Result<usize, Error>> {
+        self.detached_oneshot_cmd(|tx| Command::BlobWrite {
+            id,
+            offset,
+            data,
+            tx,
+        })
+    }
+
+    /// Like `oneshot_cmd()`, but the returned future does not borrow `self`.
+    fn detached_oneshot_cmd<F, T>(&self, command: F) -> BoxFuture<'static, Result<T, Error>>
+    where
+        F: FnOnce(oneshot::Sender<Result<T, Error>>) -> Command,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let command = command(tx);
+        let command_tx = self.command_tx.clone();
+
+        Box::pin(async move {
+            command_tx
+                .send_async(command)
+                .await
+                .map_err(|_| Error::WorkerCrashed)?;
+
+            rx.await.map_err(|_| Error::WorkerCrashed)?
+        })
+    }
+
+    #[cfg(feature = "sqlite-session")]
+    pub(crate) async fn session_create(&mut self, schema: Option<&str>) -> Result<u64, Error> {
+        self.oneshot_cmd(|tx| Command::SessionCreate {
+            schema: schema.map(Into::into),
+            tx,
+        })
+        .await?
+    }
+
+    #[cfg(feature = "sqlite-session")]
+    pub(crate) async fn session_attach(
+        &mut self,
+        id: u64,
+        table: Option<&str>,
+    ) -> Result<(), Error> {
+        self.oneshot_cmd(|tx| Command::SessionAttach {
+            id,
+            table: table.map(Into::into),
+            tx,
+        })
+        .await?
+    }
+
+    #[cfg(feature = "sqlite-session")]
+    pub(crate) async fn session_is_empty(&mut self, id: u64) -> Result<bool, Error> {
+        self.oneshot_cmd(|tx| Command::SessionIsEmpty { id, tx })
+            .await?
+    }
+
+    #[cfg(feature = "sqlite-session")]
+    pub(crate) async fn session_changeset(
+        &mut self,
+        id: u64,
+        patchset: bool,
+    ) -> Result<Vec<u8>, Error> {
+        self.oneshot_cmd(|tx| Command::SessionChangeset { id, patchset, tx })
+            .await?
+    }
+
+    #[cfg(feature = "sqlite-session")]
+    pub(crate) async fn apply_changeset(
+        &mut self,
+        changeset: Vec<u8>,
+        conflict: Box<ConflictHandler>,
+    ) -> Result<(), Error> {
+        self.oneshot_cmd(|tx| Command::ApplyChangeset {
+            changeset,
+            conflict,
+            tx,
+        })
+        .await?
+    }
+
+    pub(crate) async fn backup_init(
+        &mut self,
+        target: BackupTarget,
+        direction: BackupDirection,
+    ) -> Result<(), Error> {
+        self.oneshot_cmd(|tx| Command::BackupInit {
+            target: Box::new(target),
+            direction,
+            tx,
+        })
+        .await?
+    }
+
+    pub(crate) async fn backup_step(&mut self, pages: c_int) -> Result<BackupStep, Error> {
+        self.oneshot_cmd(|tx| Command::BackupStep { pages, tx })
+            .await?
+    }
+
+    fn begin_query(&self) -> QueryGuard {
+        QueryGuard {
+            shared: Arc::clone(&self.shared),
+            handle_raw: self.handle_raw.clone(),
+            id: self.shared.next_query_id.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Release a resource owned by the worker without blocking, e.g. from a `Drop` impl.
+    pub(crate) fn release(&self, release: Release) {
+        self.shared.pending_releases.lock().unwrap().push(release);
+
+        // wake the worker up; if the queue is full, there is at least one command after this
+        // release, and the worker applies it before running that command. If the worker has
+        // already stopped, the resource was released with the connection.
+        let _ = self.command_tx.try_send(Command::ReleasePending);
+    }
+
+    // commands are processed in order, so there is no need to wait for these to be applied
+    async fn send(&mut self, command: Command) -> Result<(), Error> {
+        self.command_tx
+            .send_async(command)
+            .await
+            .map_err(|_| Error::WorkerCrashed)
+    }
+
     pub(crate) async fn clear_cache(&mut self) -> Result<(), Error> {
         self.oneshot_cmd(|tx| Command::ClearCache { tx }).await
     }
@@ -418,6 +1007,76 @@ impl ConnectionWorker {
     }
 }
 
+impl WorkerSharedState {
+    fn take_pending_releases(&self) -> Vec<Release> {
+        std::mem::take(&mut *self.pending_releases.lock().unwrap())
+    }
+
+    /// Run the query `id`, which can be interrupted through its [`QueryGuard`] until `f`
+    /// returns, or return `None` without running it if it was interrupted already.
+    fn run_query<T>(&self, id: u64, f: impl FnOnce() -> T) -> Option<T> {
+        {
+            let mut queries = self.queries.lock().unwrap();
+
+            if queries.interrupted >= Some(id) {
+                return None;
+            }
+
+            queries.running = Some(id);
+        }
+
+        let res = f();
+        // `sqlite3_interrupt()` can't be called for this query once the lock is released here
+        self.queries.lock().unwrap().running = None;
+        Some(res)
+    }
+}
+
+/// Interrupts a query run by the worker when it is dropped, unless the query has completed.
+///
+/// The worker thread is busy running the query, so it is interrupted from the calling side
+/// with `sqlite3_interrupt()`.
+struct QueryGuard {
+    shared: Arc<WorkerSharedState>,
+    handle_raw: ConnectionHandleRaw,
+    id: u64,
+}
+
+impl QueryGuard {
+    fn interrupt(&self) {
+        let mut queries = self.shared.queries.lock().unwrap();
+
+        // the lock keeps the worker from moving on to another query, which must not be
+        // interrupted instead of this one
+        if queries.running == Some(self.id) {
+            // SAFETY: `sqlite3_interrupt()` may be called from any thread, and the connection
+            // is only closed once `shared`, which owns it, is dropped
+            unsafe { sqlite3_interrupt(self.handle_raw.as_ptr()) };
+        }
+
+        // if the worker hasn't got to the query yet, it is skipped. Queries are sent one at a
+        // time, so those before this one have completed or been interrupted already.
+        queries.interrupted = cmp::max(queries.interrupted, Some(self.id));
+    }
+}
+
+impl Drop for QueryGuard {
+    fn drop(&mut self) {
+        self.interrupt();
+    }
+}
+
+impl Release {
+    fn apply(self, conn: &mut ConnectionState) {
+        match self {
+            Release::Backup => conn.backup = None,
+            Release::Blob(id) => conn.blobs.close(id),
+            #[cfg(feature = "sqlite-session")]
+            Release::Session(id) => conn.sessions.delete(id),
+        }
+    }
+}
+
 fn prepare(conn: &mut ConnectionState, query: &str) -> Result<SqliteStatement<'static>, Error> {
     // prepare statement object (or checkout from cache)
     let statement = conn.statements.get(query, true)?;
@@ -444,10 +1103,44 @@ fn prepare(conn: &mut ConnectionState, query: &str) -> Result<SqliteStatement<'s
     })
 }
 
+/// Returns `true` if every statement in `query` leaves the database unchanged.
+fn is_read_only(conn: &mut ConnectionState, query: &str) -> Result<bool, Error> {
+    // the query is only classified here, not executed, so don't cache the prepared statement
+    let mut statement = VirtualStatement::new(query, false)?;
+
+    loop {
+        match statement.prepare_next(&mut conn.handle) {
+            Ok(Some(statement)) => {
+                if !statement.handle.read_only() {
+                    return Ok(false);
+                }
+            }
+            Ok(None) => return Ok(true),
+            // e.g. the statement refers to a table created by a previous statement; the error
+            // is reported when the query is executed
+            Err(_) => return Ok(false),
+        }
+    }
+}
+
 fn update_cached_statements_size(conn: &ConnectionState, size: &AtomicUsize) {
     size.store(conn.statements.len(), Ordering::Release);
 }
 
+/// Report an interrupted statement as [`Error::Interrupted`], whichever command ran it.
+fn map_interrupt(e: Error) -> Error {
+    match &e {
+        Error::Database(db)
+            if db
+                .try_downcast_ref::<SqliteError>()
+                .is_some_and(SqliteError::is_interrupt) =>
+        {
+            Error::Interrupted
+        }
+        _ => e,
+    }
+}
+
 // A oneshot channel where send completes only after the receiver receives the value.
 mod rendezvous_oneshot {
     use super::oneshot::{self, Canceled};
@@ -485,3 +1178,54 @@ mod rendezvous_oneshot {
         }
     }
 }
+
+#[test]
+fn test_release_blobs_while_command_queue_is_full() -> Result<(), Error> {
+    use crate::SqliteConnectOptions;
+    use futures_util::FutureExt;
+    use std::str::FromStr;
+
+    futures_executor::block_on(async {
+        let options = SqliteConnectOptions::from_str(":memory:")?.command_buffer_size(1);
+        let mut worker =
+            ConnectionWorker::establish(EstablishParams::from_options(&options)?).await?;
+
+        worker.unlock_db().await?.handle.exec(
+            "CREATE TABLE blobs (data BLOB NOT NULL); INSERT INTO blobs (data) VALUES (x'00');",
+        )?;
+
+        let mut ids = Vec::new();
+        for _ in 0..10 {
+            ids.push(worker.blob_open("blobs", "data", 1, false).await?.0);
+        }
+
+        // block the worker until the guard is dropped, then fill up the queue
+        let shared = Arc::clone(&worker.shared);
+        let (guard, res) = futures_util::future::join(
+            shared.conn.lock(),
+            worker.command_tx.send_async(Command::UnlockDb),
+        )
+        .await;
+        res.map_err(|_| Error::WorkerCrashed)?;
+
+        let mut read = worker.blob_read(ids[0], 0, 1);
+        assert!((&mut read).now_or_never().is_none());
+        assert!(worker.command_tx.is_full());
+
+        // none of these may block
+        for &id in &ids {
+            worker.release(Release::Blob(id));
+        }
+
+        drop(guard);
+
+        // the blobs are released before the queued read runs
+        assert!(read.await.is_err());
+
+        for &id in &ids {
+            assert!(worker.blob_read(id, 0, 1).await.is_err());
+        }
+
+        Ok(())
+    })
+}
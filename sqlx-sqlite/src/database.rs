@@ -1,6 +1,7 @@
 pub(crate) use sqlx_core::database::{
     Database, HasArguments, HasStatement, HasStatementCache, HasValueRef,
 };
+use sqlx_core::insertable::InsertDialect;
 
 use crate::{
     SqliteArgumentValue, SqliteArguments, SqliteColumn, SqliteConnection, SqliteQueryResult,
@@ -53,3 +54,5 @@ impl<'q> HasStatement<'q> for Sqlite {
 }
 
 impl HasStatementCache for Sqlite {}
+
+impl InsertDialect for Sqlite {}
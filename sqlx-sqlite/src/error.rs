@@ -5,9 +5,9 @@ use std::os::raw::c_int;
 use std::{borrow::Cow, str::from_utf8_unchecked};
 
 use libsqlite3_sys::{
-    sqlite3, sqlite3_errmsg, sqlite3_extended_errcode, SQLITE_CONSTRAINT_CHECK,
+    sqlite3, sqlite3_errmsg, sqlite3_errstr, sqlite3_extended_errcode, SQLITE_CONSTRAINT_CHECK,
     SQLITE_CONSTRAINT_FOREIGNKEY, SQLITE_CONSTRAINT_NOTNULL, SQLITE_CONSTRAINT_PRIMARYKEY,
-    SQLITE_CONSTRAINT_UNIQUE,
+    SQLITE_CONSTRAINT_UNIQUE, SQLITE_INTERRUPT, SQLITE_NOTADB,
 };
 
 pub(crate) use sqlx_core::error::*;
@@ -40,6 +40,29 @@ impl SqliteError {
         }
     }
 
+    /// For errors that are returned by a function but not recorded on the database handle,
+    /// e.g. by `sqlite3_backup_step()`.
+    pub(crate) fn from_code(code: c_int) -> Self {
+        // SAFETY: `sqlite3_errstr()` returns a static string for any code
+        let message = unsafe { CStr::from_ptr(sqlite3_errstr(code)) }.to_string_lossy();
+
+        Self {
+            code,
+            message: message.into_owned(),
+        }
+    }
+
+    /// Returns `true` if the statement was interrupted, by `sqlite3_interrupt()` or a progress
+    /// handler.
+    pub(crate) fn is_interrupt(&self) -> bool {
+        self.code & 0xff == SQLITE_INTERRUPT
+    }
+
+    /// Returns `true` if the file is not a database, or could not be decrypted.
+    pub(crate) fn is_not_a_database(&self) -> bool {
+        self.code & 0xff == SQLITE_NOTADB
+    }
+
     /// For errors during extension load, the error message is supplied via a separate pointer
     pub(crate) fn extension(handle: *mut sqlite3, error_msg: &CStr) -> Self {
         let mut err = Self::new(handle);
@@ -33,7 +33,16 @@ use std::sync::atomic::AtomicBool;
 
 pub use arguments::{SqliteArgumentValue, SqliteArguments};
 pub use column::SqliteColumn;
-pub use connection::{LockedSqliteHandle, SqliteConnection};
+pub use connection::{
+    LockedSqliteHandle, SqliteBackupOptions, SqliteBackupProgress, SqliteBlob, SqliteColumnResult,
+    SqliteConnection, SqliteConstraintOp, SqliteFunctionArgs, SqliteIndexConstraint,
+    SqliteIndexInfo, SqliteIndexOrderBy, SqliteOperation, SqliteUpdateEvent, SqliteVirtualTable,
+    SqliteVirtualTableCursor,
+};
+#[cfg(feature = "sqlite-session")]
+pub use connection::{
+    SqliteChangesetConflict, SqliteConflictAction, SqliteConflictKind, SqliteSession,
+};
 pub use database::Sqlite;
 pub use error::SqliteError;
 pub use options::{
@@ -41,6 +50,7 @@ pub use options::{
 };
 pub use query_result::SqliteQueryResult;
 pub use row::SqliteRow;
+pub use rw_pool::{SqliteRwPool, SqliteRwPoolOptions};
 pub use statement::SqliteStatement;
 pub use transaction::SqliteTransactionManager;
 pub use type_info::SqliteTypeInfo;
@@ -63,6 +73,7 @@ mod logger;
 mod options;
 mod query_result;
 mod row;
+mod rw_pool;
 mod statement;
 mod transaction;
 mod type_info;
@@ -8,6 +8,20 @@ use std::fmt::Write;
 use std::time::Duration;
 use url::Url;
 
+/// SQLCipher pragmas which must be executed right after the key, before any other operation
+/// on the database: https://www.zetetic.net/sqlcipher/sqlcipher-api/
+const CIPHER_PRAGMAS: &[&str] = &[
+    "key",
+    "cipher_plaintext_header_size",
+    "cipher_salt",
+    "kdf_iter",
+    "cipher_kdf_algorithm",
+    "cipher_use_hmac",
+    "cipher_compatibility",
+    "cipher_page_size",
+    "cipher_hmac_algorithm",
+];
+
 impl ConnectOptions for SqliteConnectOptions {
     type Connection = SqliteConnection;
 
@@ -25,12 +39,21 @@ impl ConnectOptions for SqliteConnectOptions {
             // Execute PRAGMAs
             conn.execute(&*self.pragma_string()).await?;
 
-            if !self.collations.is_empty() {
+            if !self.collations.is_empty() || !self.functions.is_empty() || !self.modules.is_empty()
+            {
                 let mut locked = conn.lock_handle().await?;
 
                 for collation in &self.collations {
                     collation.create(&mut locked.guard.handle)?;
                 }
+
+                for function in &self.functions {
+                    function.create(&mut locked.guard.handle)?;
+                }
+
+                for module in &self.modules {
+                    module.create(&mut locked.guard.handle)?;
+                }
             }
 
             Ok(conn)
@@ -54,6 +77,10 @@ impl SqliteConnectOptions {
         let mut string = String::new();
 
         for (key, opt_value) in &self.pragmas {
+            if CIPHER_PRAGMAS.contains(&&**key) {
+                continue;
+            }
+
             if let Some(value) = opt_value {
                 write!(string, "PRAGMA {} = {}; ", key, value).ok();
             }
@@ -61,4 +88,19 @@ impl SqliteConnectOptions {
 
         string
     }
+
+    /// Collect the SQLCipher `PRAGMA` commands, which are executed when the connection is
+    /// opened, before anything else reads the database
+    pub(crate) fn cipher_pragma_string(&self) -> String {
+        let mut string = String::new();
+
+        // in the order of `CIPHER_PRAGMAS`, so the key comes first
+        for key in CIPHER_PRAGMAS {
+            if let Some(Some(value)) = self.pragmas.get(*key) {
+                write!(string, "PRAGMA {} = {}; ", key, value).ok();
+            }
+        }
+
+        string
+    }
 }
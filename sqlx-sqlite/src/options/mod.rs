@@ -18,6 +18,12 @@ pub use synchronous::SqliteSynchronous;
 
 use crate::common::DebugFn;
 use crate::connection::collation::Collation;
+use crate::connection::function::{Aggregate, Function, SqliteFunctionArgs};
+use crate::connection::serialize::SerializedDatabase;
+use crate::connection::vtab::{Module, SqliteVirtualTable};
+use crate::encode::Encode;
+use crate::error::BoxDynError;
+use crate::Sqlite;
 use sqlx_core::IndexMap;
 
 /// Options and flags which can be used to configure a SQLite connection.
@@ -61,6 +67,7 @@ pub struct SqliteConnectOptions {
     pub(crate) shared_cache: bool,
     pub(crate) statement_cache_capacity: usize,
     pub(crate) busy_timeout: Duration,
+    pub(crate) statement_timeout: Option<Duration>,
     pub(crate) log_settings: LogSettings,
     pub(crate) immutable: bool,
     pub(crate) vfs: Option<Cow<'static, str>>,
@@ -76,6 +83,13 @@ pub struct SqliteConnectOptions {
     pub(crate) row_channel_size: usize,
 
     pub(crate) collations: Vec<Collation>,
+    pub(crate) functions: Vec<Function>,
+    pub(crate) modules: Vec<Module>,
+
+    pub(crate) deserialize: Option<SerializedDatabase>,
+
+    // set for the writer of a `SqliteRwPool`
+    pub(crate) begin_immediate: bool,
 
     pub(crate) serialized: bool,
     pub(crate) thread_name: Arc<DebugFn<dyn Fn(u64) -> String + Send + Sync + 'static>>,
@@ -189,12 +203,17 @@ impl SqliteConnectOptions {
             shared_cache: false,
             statement_cache_capacity: 100,
             busy_timeout: Duration::from_secs(5),
+            statement_timeout: None,
             log_settings: Default::default(),
             immutable: false,
             vfs: None,
             pragmas,
             extensions: Default::default(),
             collations: Default::default(),
+            functions: Default::default(),
+            modules: Default::default(),
+            deserialize: None,
+            begin_immediate: false,
             serialized: false,
             thread_name: Arc::new(DebugFn(|id| format!("sqlx-sqlite-worker-{}", id))),
             command_channel_size: 50,
@@ -295,6 +314,24 @@ impl SqliteConnectOptions {
         self
     }
 
+    /// Sets the maximum time a query may run before it is interrupted and fails with
+    /// [`Error::Interrupted`][crate::error::Error::Interrupted].
+    ///
+    /// The timeout applies to preparing and describing queries as well as executing them. Only
+    /// the time spent waiting for results counts toward it, not the time taken to process the
+    /// rows already received. Queries are stopped with
+    /// [`sqlite3_interrupt()`](https://www.sqlite.org/c3ref/interrupt.html); if the query runs
+    /// in an explicit transaction, SQLite may roll back the whole transaction.
+    ///
+    /// Regardless of this setting, a query is interrupted as soon as its results are no longer
+    /// wanted, e.g. when the future or stream returned by the executor is dropped.
+    ///
+    /// There is no timeout by default.
+    pub fn statement_timeout(mut self, timeout: Duration) -> Self {
+        self.statement_timeout = Some(timeout);
+        self
+    }
+
     /// Sets the [synchronous](https://www.sqlite.org/pragma.html#pragma_synchronous) setting for the database connection.
     ///
     /// The default synchronous settings is FULL. However, if durability is not a concern,
@@ -324,6 +361,40 @@ impl SqliteConnectOptions {
         self.pragma("page_size", page_size.to_string())
     }
 
+    /// Sets the key used to encrypt and decrypt the database with
+    /// [SQLCipher](https://www.zetetic.net/sqlcipher/sqlcipher-api/#key).
+    ///
+    /// The key is derived from the passphrase, and applied before any other pragma is executed.
+    /// Opening an existing database with the wrong key fails with [`Error::Configuration`]
+    /// instead of the `file is not a database` error SQLite gives for any later query.
+    ///
+    /// To use a raw key instead of a passphrase, pass it with
+    /// <code>.[pragma](Self::pragma)("key", "\"x'...'\"")</code>.
+    ///
+    /// [`Error::Configuration`]: crate::error::Error::Configuration
+    #[cfg(feature = "sqlcipher")]
+    pub fn key(self, passphrase: impl AsRef<str>) -> Self {
+        self.pragma("key", quote_string(passphrase.as_ref()))
+    }
+
+    /// Sets the [cipher_page_size](https://www.zetetic.net/sqlcipher/sqlcipher-api/#cipher_page_size)
+    /// of an encrypted database.
+    ///
+    /// The default for SQLCipher 4 is 4096. It must be the same for every connection.
+    #[cfg(feature = "sqlcipher")]
+    pub fn cipher_page_size(self, page_size: u32) -> Self {
+        self.pragma("cipher_page_size", page_size.to_string())
+    }
+
+    /// Sets the number of iterations of the [key derivation function](https://www.zetetic.net/sqlcipher/sqlcipher-api/#kdf_iter)
+    /// used to derive the key from the passphrase.
+    ///
+    /// The default for SQLCipher 4 is 256000. It must be the same for every connection.
+    #[cfg(feature = "sqlcipher")]
+    pub fn kdf_iter(self, iterations: u32) -> Self {
+        self.pragma("kdf_iter", iterations.to_string())
+    }
+
     /// Sets custom initial pragma for the database connection.
     pub fn pragma<K, V>(mut self, key: K, value: V) -> Self
     where
@@ -359,6 +430,152 @@ impl SqliteConnectOptions {
         self
     }
 
+    /// Add a user-defined scalar SQL function.
+    ///
+    /// The arguments are decoded from the values passed by SQLite as a tuple (see
+    /// [`SqliteFunctionArgs`]) and the return value is encoded the same way as a bind parameter.
+    /// The function is registered on every connection opened with these options, and replaces
+    /// any function with the same name and number of arguments.
+    ///
+    /// See [`sqlite3_create_function()`](https://www.sqlite.org/c3ref/create_function.html) for details.
+    ///
+    /// ```rust
+    /// # use sqlx_sqlite::SqliteConnectOptions;
+    /// let options = SqliteConnectOptions::new()
+    ///     .create_function("add_one", |(x,): (i64,)| x + 1)
+    ///     .create_function("greet", |(name,): (Option<String>,)| {
+    ///         format!("Hello, {}!", name.as_deref().unwrap_or("world"))
+    ///     });
+    /// ```
+    pub fn create_function<N, A, R, F>(mut self, name: N, func: F) -> Self
+    where
+        N: Into<Arc<str>>,
+        A: SqliteFunctionArgs + 'static,
+        R: Encode<'static, Sqlite> + 'static,
+        F: Fn(A) -> R + Send + Sync + 'static,
+    {
+        self.functions.push(Function::scalar(name, func));
+        self
+    }
+
+    /// Add a user-defined aggregate SQL function.
+    ///
+    /// For each group, the state is created with `init`, updated with `step` for each row,
+    /// and turned into the result with `finalize`. If the group is empty, `finalize` is called
+    /// with a freshly initialized state.
+    ///
+    /// See [`sqlite3_create_function()`](https://www.sqlite.org/c3ref/create_function.html) for details.
+    ///
+    /// ```rust
+    /// # use sqlx_sqlite::SqliteConnectOptions;
+    /// let options = SqliteConnectOptions::new().create_aggregate(
+    ///     "product",
+    ///     || 1i64,
+    ///     |product, (x,): (i64,)| *product *= x,
+    ///     |product| product,
+    /// );
+    /// ```
+    pub fn create_aggregate<N, A, R, S>(
+        mut self,
+        name: N,
+        init: impl Fn() -> S + Send + Sync + 'static,
+        step: impl Fn(&mut S, A) + Send + Sync + 'static,
+        finalize: impl Fn(S) -> R + Send + Sync + 'static,
+    ) -> Self
+    where
+        N: Into<Arc<str>>,
+        A: SqliteFunctionArgs + 'static,
+        R: Encode<'static, Sqlite> + 'static,
+        S: 'static,
+    {
+        self.functions.push(Function::aggregate(
+            name,
+            Aggregate::new(init, step, finalize),
+        ));
+        self
+    }
+
+    /// Add a user-defined aggregate window function.
+    ///
+    /// In addition to the callbacks of [`.create_aggregate()`][Self::create_aggregate],
+    /// `inverse` removes a row that left the window from the state and `value` returns the
+    /// current result without consuming the state.
+    ///
+    /// See [User-Defined Aggregate Window Functions](https://www.sqlite.org/windowfunctions.html#udfwinfunc)
+    /// for details.
+    pub fn create_window_function<N, A, R, S>(
+        mut self,
+        name: N,
+        init: impl Fn() -> S + Send + Sync + 'static,
+        step: impl Fn(&mut S, A) + Send + Sync + 'static,
+        inverse: impl Fn(&mut S, A) + Send + Sync + 'static,
+        value: impl Fn(&S) -> R + Send + Sync + 'static,
+        finalize: impl Fn(S) -> R + Send + Sync + 'static,
+    ) -> Self
+    where
+        N: Into<Arc<str>>,
+        A: SqliteFunctionArgs + 'static,
+        R: Encode<'static, Sqlite> + 'static,
+        S: 'static,
+    {
+        self.functions.push(Function::aggregate(
+            name,
+            Aggregate::new(init, step, finalize).window(inverse, value),
+        ));
+        self
+    }
+
+    /// Add a virtual table module implemented in Rust.
+    ///
+    /// `connect` creates the table for each connection that uses it, from the arguments of
+    /// `CREATE VIRTUAL TABLE ... USING name(args)`. The module can also be queried directly as
+    /// an [eponymous virtual table](https://www.sqlite.org/vtab.html#eponymous_virtual_tables)
+    /// named `name`, in which case there are no arguments.
+    ///
+    /// See [`SqliteVirtualTable`][crate::SqliteVirtualTable] and
+    /// [`sqlite3_create_module()`](https://www.sqlite.org/c3ref/create_module.html) for details.
+    pub fn create_module<N, T, F>(mut self, name: N, connect: F) -> Self
+    where
+        N: Into<Arc<str>>,
+        T: SqliteVirtualTable,
+        F: Fn(&[String]) -> Result<T, BoxDynError> + Send + Sync + 'static,
+    {
+        self.modules.push(Module::new(name, connect));
+        self
+    }
+
+    /// Initialize the `main` database of each connection with a private, in-memory copy of
+    /// `data`, as produced by [`SqliteConnection::serialize()`][crate::SqliteConnection::serialize]
+    /// or read from a database file.
+    ///
+    /// This replaces the database opened from the filename, so it's best combined with an
+    /// in-memory database. Changes are not written back to `data` and are not shared between
+    /// connections.
+    ///
+    /// See [`sqlite3_deserialize()`](https://www.sqlite.org/c3ref/deserialize.html) for details.
+    ///
+    /// ```rust,no_run
+    /// # async fn example() -> sqlx_core::error::Result<()> {
+    /// use sqlx_core::connection::{ConnectOptions, Connection};
+    /// use sqlx_sqlite::{SqliteConnectOptions, SqliteConnection};
+    /// use std::str::FromStr;
+    ///
+    /// let mut seeded = SqliteConnection::connect("sqlite::memory:").await?;
+    /// // run migrations and insert fixtures here
+    /// let snapshot = seeded.serialize(None).await?;
+    ///
+    /// let mut conn = SqliteConnectOptions::from_str("sqlite::memory:")?
+    ///     .deserialize_from(snapshot)
+    ///     .connect()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn deserialize_from(mut self, data: impl Into<Arc<[u8]>>) -> Self {
+        self.deserialize = Some(SerializedDatabase::new(data.into()));
+        self
+    }
+
     /// Set to `true` to signal to SQLite that the database file is on read-only media.
     ///
     /// If enabled, SQLite assumes the database file _cannot_ be modified, even by higher
@@ -549,3 +766,9 @@ impl SqliteConnectOptions {
         self
     }
 }
+
+/// Quote a string as an SQL string literal.
+#[cfg(feature = "sqlcipher")]
+pub(crate) fn quote_string(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
@@ -0,0 +1,288 @@
+use std::path::Path;
+
+use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
+use futures_util::TryStreamExt;
+use sqlx_core::describe::Describe;
+use sqlx_core::error::Error;
+use sqlx_core::executor::{Execute, Executor};
+use sqlx_core::pool::PoolConnection;
+use sqlx_core::transaction::Transaction;
+use sqlx_core::Either;
+
+use crate::{
+    Sqlite, SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteQueryResult, SqliteRow,
+    SqliteStatement, SqliteTypeInfo,
+};
+
+/// A pair of SQLite connection pools: one with a single read-write connection, and one with
+/// read-only connections.
+///
+/// In [WAL mode](https://www.sqlite.org/wal.html), SQLite allows any number of readers
+/// alongside a single writer. Funneling all writes through one connection means writers wait
+/// for each other in the pool instead of contending for the database lock, which fails with
+/// `SQLITE_BUSY` once the [busy timeout][SqliteConnectOptions::busy_timeout] elapses.
+///
+/// When used as an [`Executor`], each query is run on a reader if all of its statements are
+/// read-only (as reported by [`sqlite3_stmt_readonly()`](https://www.sqlite.org/c3ref/stmt_readonly.html)),
+/// and on the writer otherwise. Queries are classified by preparing them on separate read-only
+/// connections, as many as there are readers, so writes never wait for a reader to become
+/// available and reads can be classified concurrently. Use
+/// [`.reader()`][Self::reader] and [`.writer()`][Self::writer] to choose explicitly.
+///
+/// Transactions started with [`.begin()`][Self::begin] always run on the writer, with
+/// `BEGIN IMMEDIATE` so the write lock is taken up front. Statements controlling transactions
+/// are read-only, so they should not be executed on this pool directly.
+///
+/// ```rust,no_run
+/// # async fn example() -> sqlx_core::error::Result<()> {
+/// use sqlx_core::executor::Executor;
+/// use sqlx_sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteRwPoolOptions};
+///
+/// let options = SqliteConnectOptions::new()
+///     .filename("data.db")
+///     .create_if_missing(true)
+///     .journal_mode(SqliteJournalMode::Wal);
+///
+/// let pool = SqliteRwPoolOptions::new()
+///     .max_readers(8)
+///     .connect_with(options)
+///     .await?;
+///
+/// // runs on the writer
+/// pool.execute("CREATE TABLE IF NOT EXISTS users (id INTEGER PRIMARY KEY, name TEXT)")
+///     .await?;
+///
+/// // runs on a reader
+/// pool.fetch_all("SELECT * FROM users").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SqliteRwPool {
+    reader: SqlitePool,
+    writer: SqlitePool,
+    // read-only connections that only prepare queries to classify them, as many as the readers
+    classifier: SqlitePool,
+}
+
+/// Configuration options for [`SqliteRwPool`].
+#[derive(Debug)]
+pub struct SqliteRwPoolOptions {
+    reader: SqlitePoolOptions,
+    writer: SqlitePoolOptions,
+}
+
+impl Default for SqliteRwPoolOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SqliteRwPoolOptions {
+    /// Returns the default options for both pools, with up to 10 readers.
+    pub fn new() -> Self {
+        Self {
+            reader: SqlitePoolOptions::new().max_connections(10),
+            writer: SqlitePoolOptions::new().max_connections(1),
+        }
+    }
+
+    /// Set the maximum number of read-only connections.
+    pub fn max_readers(mut self, max: u32) -> Self {
+        self.reader = self.reader.max_connections(max);
+        self
+    }
+
+    /// Set the options of the pool of read-only connections.
+    pub fn reader_options(mut self, options: SqlitePoolOptions) -> Self {
+        self.reader = options;
+        self
+    }
+
+    /// Set the options of the pool of the writer.
+    ///
+    /// [`max_connections`][crate::pool::PoolOptions::max_connections] is ignored, as the pool
+    /// always has a single connection.
+    pub fn writer_options(mut self, options: SqlitePoolOptions) -> Self {
+        self.writer = options;
+        self
+    }
+
+    /// Create the pools from a connection URL and immediately open the writer and a reader.
+    ///
+    /// See [`.connect_with()`][Self::connect_with] for details.
+    pub async fn connect(self, url: &str) -> Result<SqliteRwPool, Error> {
+        self.connect_with(url.parse()?).await
+    }
+
+    /// Create the pools and immediately open the writer and a reader.
+    ///
+    /// The writer is opened with `options`, and the readers with
+    /// <code>options.[read_only][SqliteConnectOptions::read_only](true)</code>. The writer is
+    /// opened first so it can create the database if it's missing.
+    ///
+    /// Returns an error if `options` refers to a private in-memory database, which cannot be
+    /// shared between connections; use a file, or a shared in-memory database instead.
+    pub async fn connect_with(self, options: SqliteConnectOptions) -> Result<SqliteRwPool, Error> {
+        if !options.in_memory && options.filename == Path::new(":memory:") {
+            return Err(Error::Configuration(
+                "a private in-memory database cannot be shared by the connections of a `SqliteRwPool`"
+                    .into(),
+            ));
+        }
+
+        let mut writer_options = options.clone();
+        writer_options.begin_immediate = true;
+
+        let writer = self
+            .writer
+            .max_connections(1)
+            .connect_with(writer_options)
+            .await?;
+
+        let options = options.read_only(true);
+
+        let max_readers = self.reader.get_max_connections();
+
+        let reader = match self.reader.connect_with(options.clone()).await {
+            Ok(reader) => reader,
+            Err(e) => {
+                writer.close().await;
+                return Err(e);
+            }
+        };
+
+        let classifier = SqlitePoolOptions::new()
+            .max_connections(max_readers)
+            .connect_lazy_with(options.statement_cache_capacity(0));
+
+        Ok(SqliteRwPool {
+            reader,
+            writer,
+            classifier,
+        })
+    }
+}
+
+impl SqliteRwPool {
+    /// Create the pools with the default options.
+    ///
+    /// See [`SqliteRwPoolOptions::connect()`] for details.
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        SqliteRwPoolOptions::new().connect(url).await
+    }
+
+    /// Create the pools with the default options.
+    ///
+    /// See [`SqliteRwPoolOptions::connect_with()`] for details.
+    pub async fn connect_with(options: SqliteConnectOptions) -> Result<Self, Error> {
+        SqliteRwPoolOptions::new().connect_with(options).await
+    }
+
+    /// The pool of read-only connections.
+    pub fn reader(&self) -> &SqlitePool {
+        &self.reader
+    }
+
+    /// The pool of the single read-write connection.
+    pub fn writer(&self) -> &SqlitePool {
+        &self.writer
+    }
+
+    /// Retrieves the writer and immediately begins a new transaction with `BEGIN IMMEDIATE`.
+    pub async fn begin(&self) -> Result<Transaction<'static, Sqlite>, Error> {
+        self.writer.begin().await
+    }
+
+    /// Shut down both pools.
+    ///
+    /// See [`Pool::close()`][crate::pool::Pool::close] for details.
+    pub async fn close(&self) {
+        futures_util::future::join3(
+            self.reader.close(),
+            self.writer.close(),
+            self.classifier.close(),
+        )
+        .await;
+    }
+
+    /// Returns `true` if [`.close()`][Self::close] has been called.
+    pub fn is_closed(&self) -> bool {
+        self.reader.is_closed() && self.writer.is_closed() && self.classifier.is_closed()
+    }
+
+    /// Acquire the connection a query should run on.
+    async fn acquire_for(&self, sql: &str) -> Result<PoolConnection<Sqlite>, Error> {
+        // classifying is quick, so the classifier is released before acquiring either pool
+        let read_only = self
+            .classifier
+            .acquire()
+            .await?
+            .worker
+            .is_read_only(sql)
+            .await?;
+
+        if read_only {
+            self.reader.acquire().await
+        } else {
+            self.writer.acquire().await
+        }
+    }
+}
+
+impl<'p> Executor<'p> for &'_ SqliteRwPool {
+    type Database = Sqlite;
+
+    fn fetch_many<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> BoxStream<'e, Result<Either<SqliteQueryResult, SqliteRow>, Error>>
+    where
+        E: 'q + Execute<'q, Self::Database>,
+    {
+        let pool = self.clone();
+
+        Box::pin(try_stream! {
+            let mut conn = pool.acquire_for(query.sql()).await?;
+            let mut s = conn.fetch_many(query);
+
+            while let Some(v) = s.try_next().await? {
+                r#yield!(v);
+            }
+
+            Ok(())
+        })
+    }
+
+    fn fetch_optional<'e, 'q: 'e, E>(
+        self,
+        query: E,
+    ) -> BoxFuture<'e, Result<Option<SqliteRow>, Error>>
+    where
+        E: 'q + Execute<'q, Self::Database>,
+    {
+        let pool = self.clone();
+
+        Box::pin(async move {
+            pool.acquire_for(query.sql())
+                .await?
+                .fetch_optional(query)
+                .await
+        })
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [SqliteTypeInfo],
+    ) -> BoxFuture<'e, Result<SqliteStatement<'q>, Error>> {
+        // statements are prepared per connection, so any of them will do
+        self.reader.prepare_with(sql, parameters)
+    }
+
+    #[doc(hidden)]
+    fn describe<'e, 'q: 'e>(self, sql: &'q str) -> BoxFuture<'e, Result<Describe<Sqlite>, Error>> {
+        self.reader.describe(sql)
+    }
+}
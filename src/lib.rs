@@ -10,6 +10,7 @@ pub use sqlx_core::database::{self, Database};
 pub use sqlx_core::describe::Describe;
 pub use sqlx_core::executor::{Execute, Executor};
 pub use sqlx_core::from_row::FromRow;
+pub use sqlx_core::insertable::{InsertDialect, Insertable};
 pub use sqlx_core::pool::{self, Pool};
 pub use sqlx_core::query::{query, query_with};
 pub use sqlx_core::query_as::{query_as, query_as_with};
@@ -29,6 +30,10 @@ pub use sqlx_core::error::{self, Error, Result};
 #[cfg(feature = "migrate")]
 pub use sqlx_core::migrate;
 
+#[cfg(feature = "sqlx-toml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sqlx-toml")))]
+pub use sqlx_core::config;
+
 #[cfg(feature = "mysql")]
 #[cfg_attr(docsrs, doc(cfg(feature = "mysql")))]
 pub use sqlx_mysql::{self as mysql, MySql, MySqlConnection, MySqlExecutor, MySqlPool};
@@ -52,7 +57,7 @@ pub extern crate sqlx_macros;
 // derives
 #[cfg(feature = "macros")]
 #[doc(hidden)]
-pub use sqlx_macros::{FromRow, Type};
+pub use sqlx_macros::{FromRow, Insertable, Type};
 
 // We can't do our normal facade approach with an attribute, but thankfully we can now
 // have docs out-of-line quite easily.
@@ -81,6 +86,12 @@ pub mod ty_match;
 #[doc(hidden)]
 pub use sqlx_core::rt as __rt;
 
+#[doc(hidden)]
+pub use sqlx_core::from_row::try_get_prefixed as __try_get_prefixed;
+
+#[doc(hidden)]
+pub use sqlx_core::from_row::try_get_prefixed_nth as __try_get_prefixed_nth;
+
 /// Conversions between Rust and SQL types.
 ///
 /// To see how each SQL type maps to a Rust type, see the corresponding `types` module for each
@@ -100,6 +100,126 @@
 /// * Postgres: `$N` where `N` is the 1-based positional argument index
 /// * MySQL/SQLite: `?` which matches arguments in order that it appears in the query
 ///
+/// ## Named Parameters
+/// Arguments can instead be given as `name = expr`, and referred to as `:name` in the SQL,
+/// any number of times:
+///
+/// ```rust,ignore
+/// # async fn main() {
+/// # let mut conn = panic!();
+/// let posts = sqlx::query!(
+///         "select id from posts where author_id = :user_id or reviewer_id = :user_id",
+///         user_id = user.id,
+///     )
+///     .fetch_all(&mut conn)
+///     .await?;
+/// # }
+/// ```
+///
+/// The macro rewrites these into the bind parameters of the database. In Postgres, each name
+/// is bound once as `$N`. In MySQL and SQLite, each use is a `?` and the argument is bound again.
+/// Names inside quotes, comments and dollar-quoted strings are left as they are, as are `::`
+/// casts and array slices like `a[1:n]`; names in `ARRAY[..]` constructors are rewritten. Every
+/// argument must be named, and used in the query.
+/// The data saved by `cargo sqlx prepare` is for the query as written, so it does not change
+/// with the rewriting.
+///
+/// ## Optional Clauses
+/// After the arguments, clauses written as `"<SQL>" if <expr>` are only appended to the query
+/// when `<expr>` is `Some`, binding the value inside as the clause's parameter. This allows
+/// filters to be added at runtime while the query stays checked at compile time:
+///
+/// ```rust,ignore
+/// # async fn main() {
+/// # let mut conn = panic!();
+/// let status: Option<&str> = Some("open");
+/// let due_before: Option<time::Date> = None;
+///
+/// let tasks = sqlx::query!(
+///         "select id, title from tasks where owner_id = $1",
+///         owner_id,
+///         "and status = $2" if status,
+///         "and due < $3" if due_before,
+///     )
+///     .fetch_all(&mut conn)
+///     .await?;
+/// # }
+/// ```
+///
+/// Clauses are appended in order, separated by a space. In Postgres, `$N` parameters are
+/// numbered as in the query with every clause, so the parameter of the first clause above is
+/// `$2` and that of the second is `$3`. A clause may also use the parameters of the query
+/// before the clauses, like `$1` above. The parameters are renumbered for the combinations
+/// leaving out some clauses. In MySQL and SQLite, the parameter of a clause must be `?`.
+///
+/// Every combination of the clauses is checked against the database, and saved separately by
+/// `cargo sqlx prepare`, so at most 6 are allowed in a query. They must all return the same
+/// columns, and a column is nullable if it is in any combination. At runtime, only the
+/// combination selected by the arguments is executed.
+///
+/// ## Multiple Databases
+/// A query can be checked against a database other than the one at `DATABASE_URL` by naming it
+/// before the query, as `db = "<name>"`. The databases are configured in a `sqlx.toml` file at
+/// the root of the crate or workspace, each with the environment variable holding its URL
+/// (which may be set in `.env`), or the URL itself:
+///
+/// ```toml
+/// [databases.analytics]
+/// url-var = "ANALYTICS_DATABASE_URL"
+///
+/// [databases.cache]
+/// url = "sqlite://cache.db"
+/// ```
+///
+/// ```rust,ignore
+/// # async fn main() {
+/// # let mut analytics_conn = panic!();
+/// let visits = sqlx::query!(db = "analytics", "select page, count(*) from visits group by page")
+///     .fetch_all(&mut analytics_conn)
+///     .await?;
+/// # }
+/// ```
+///
+/// A relative SQLite path in `url` is relative to `sqlx.toml`, and the crate is recompiled when
+/// `sqlx.toml` changes.
+///
+/// The driver is chosen by the scheme of each URL, so crates in a workspace, or queries in
+/// one crate, can use different databases. Every variant of `query!()` accepts `db = "<name>"`
+/// as its first argument. In [offline mode](#offline-mode-requires-the-offline-feature), the
+/// data of each named database is kept in a directory of its name in `.sqlx`.
+///
+/// ## Configuration
+/// Other settings of the macros are read from `sqlx.toml` too, relative paths being relative to
+/// that file:
+///
+/// ```toml
+/// [common]
+/// # read the database URL from this variable instead of `DATABASE_URL`
+/// database-url-var = "APP_DATABASE_URL"
+///
+/// [macros]
+/// # read and save query data here instead of `.sqlx`
+/// offline-dir = "queries"
+/// # use `time` types for dates and times when both `chrono` and `time` are enabled
+/// preferred-crates.date-time = "time"
+/// # use `rust_decimal` for `NUMERIC` when both `rust_decimal` and `bigdecimal` are enabled
+/// preferred-crates.numeric = "rust_decimal"
+///
+/// # the Rust types of SQL types of each database (`postgres`, `mysql` or `sqlite`), by their
+/// # names, for parameters and columns
+/// [macros.type-overrides.postgres]
+/// timestamptz = "my_crate::Timestamp"
+/// # custom types, such as Postgres enums and domains, have no Rust type otherwise
+/// mood = "crate::models::Mood"
+/// ```
+///
+/// Unlike the [per-column overrides](#type-overrides-output-columns), a type mapped under
+/// `[macros.type-overrides]` applies to every query of its database, and is checked against the
+/// arguments too.
+/// It must implement [`Type`][trait@crate::Type], [`Encode`][trait@crate::Encode] and
+/// [`Decode`][trait@crate::Decode] for the database, as it does when derived with
+/// [`sqlx::Type`][derive@crate::Type].
+///
 /// ## Nullability: Bind Parameters
 /// For a given expected type `T`, both `T` and `Option<T>` are allowed (as well as either
 /// behind references). `Option::None` will be bound as `NULL`, so if binding a type behind `Option`
@@ -121,6 +241,20 @@
 /// as the implementation will need to query the table metadata to find if a given column
 /// has a `NOT NULL` constraint. Columns that do not have a `NOT NULL` constraint or are the result
 /// of an expression are assumed to be nullable and so `Option<T>` is used instead of `T`.
+/// Columns of views, subqueries and CTEs which select a table column directly are followed
+/// back to that column, and `count()` is known to never be `NULL`.
+///
+/// The nullability of columns which cannot be inferred this way, such as those of views over
+/// expressions, can be set once for the whole project in `sqlx.toml`, at the root of the crate
+/// or workspace, mapping each table or view to its columns:
+///
+/// ```toml
+/// [macros.column-nullability."public.active_users"]
+/// id = false
+/// last_seen = true
+/// ```
+///
+/// The schema may be left out to match a relation of that name in any schema.
 ///
 /// For MySQL, the implementation looks at [the `NOT_NULL` flag](https://dev.mysql.com/doc/dev/mysql-server/8.0.12/group__group__cs__column__definition__flags.html#ga50377f5ca5b3e92f3931a81fe7b44043)
 /// of [the `ColumnDefinition` structure in `COM_QUERY_OK`](https://dev.mysql.com/doc/internals/en/com-query-response.html#column-definition):
@@ -210,9 +344,10 @@
 ///
 /// In Postgres, we patch up this inference by analyzing `EXPLAIN VERBOSE` output (which is not
 /// well documented, is highly dependent on the query plan that Postgres generates, and may differ
-/// between releases) to find columns that are the result of left/right/full outer joins. This
-/// analysis errs on the side of producing false positives (marking columns nullable that are not
-/// in practice) but there are likely edge cases that it does not cover yet.
+/// between releases) to find columns that are the result of left/right/full outer joins or of
+/// grouping sets, and to follow columns through views, subqueries and CTEs. This analysis errs
+/// on the side of producing false positives (marking columns nullable that are not in practice)
+/// but there are likely edge cases that it does not cover yet.
 ///
 /// Using `?` as an override we can fix this for columns we know to be nullable in practice:
 ///
@@ -303,6 +438,13 @@
 #[macro_export]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 macro_rules! query (
+    // check the query against a database configured in `sqlx.toml`
+    (db = $db:literal, $query:expr) => ({
+        $crate::sqlx_macros::expand_query!(db = $db, source = $query)
+    });
+    (db = $db:literal, $query:expr, $($args:tt)*) => ({
+        $crate::sqlx_macros::expand_query!(db = $db, source = $query, args = [$($args)*])
+    });
     // in Rust 1.45 we can now invoke proc macros in expression position
     ($query:expr) => ({
         $crate::sqlx_macros::expand_query!(source = $query)
@@ -325,6 +467,12 @@ macro_rules! query (
 #[macro_export]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 macro_rules! query_unchecked (
+    (db = $db:literal, $query:expr) => ({
+        $crate::sqlx_macros::expand_query!(db = $db, source = $query, checked = false)
+    });
+    (db = $db:literal, $query:expr, $($args:tt)*) => ({
+        $crate::sqlx_macros::expand_query!(db = $db, source = $query, args = [$($args)*], checked = false)
+    });
     ($query:expr) => ({
         $crate::sqlx_macros::expand_query!(source = $query, checked = false)
     });
@@ -378,6 +526,12 @@ macro_rules! query_unchecked (
 #[macro_export]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 macro_rules! query_file (
+    (db = $db:literal, $path:literal) => ({
+        $crate::sqlx_macros::expand_query!(db = $db, source_file = $path)
+    });
+    (db = $db:literal, $path:literal, $($args:tt)*) => ({
+        $crate::sqlx_macros::expand_query!(db = $db, source_file = $path, args = [$($args)*])
+    });
     ($path:literal) => ({
         $crate::sqlx_macros::expand_query!(source_file = $path)
     });
@@ -391,6 +545,12 @@ macro_rules! query_file (
 #[macro_export]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 macro_rules! query_file_unchecked (
+    (db = $db:literal, $path:literal) => ({
+        $crate::sqlx_macros::expand_query!(db = $db, source_file = $path, checked = false)
+    });
+    (db = $db:literal, $path:literal, $($args:tt)*) => ({
+        $crate::sqlx_macros::expand_query!(db = $db, source_file = $path, args = [$($args)*], checked = false)
+    });
     ($path:literal) => ({
         $crate::sqlx_macros::expand_query!(source_file = $path, checked = false)
     });
@@ -554,6 +714,12 @@ macro_rules! query_file_unchecked (
 #[macro_export]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 macro_rules! query_as (
+    (db = $db:literal, $out_struct:path, $query:expr) => ( {
+        $crate::sqlx_macros::expand_query!(db = $db, record = $out_struct, source = $query)
+    });
+    (db = $db:literal, $out_struct:path, $query:expr, $($args:tt)*) => ( {
+        $crate::sqlx_macros::expand_query!(db = $db, record = $out_struct, source = $query, args = [$($args)*])
+    });
     ($out_struct:path, $query:expr) => ( {
         $crate::sqlx_macros::expand_query!(record = $out_struct, source = $query)
     });
@@ -598,6 +764,12 @@ macro_rules! query_as (
 #[macro_export]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 macro_rules! query_file_as (
+    (db = $db:literal, $out_struct:path, $path:literal) => ( {
+        $crate::sqlx_macros::expand_query!(db = $db, record = $out_struct, source_file = $path)
+    });
+    (db = $db:literal, $out_struct:path, $path:literal, $($args:tt)*) => ( {
+        $crate::sqlx_macros::expand_query!(db = $db, record = $out_struct, source_file = $path, args = [$($args)*])
+    });
     ($out_struct:path, $path:literal) => ( {
         $crate::sqlx_macros::expand_query!(record = $out_struct, source_file = $path)
     });
@@ -611,6 +783,12 @@ macro_rules! query_file_as (
 #[macro_export]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 macro_rules! query_as_unchecked (
+    (db = $db:literal, $out_struct:path, $query:expr) => ( {
+        $crate::sqlx_macros::expand_query!(db = $db, record = $out_struct, source = $query, checked = false)
+    });
+    (db = $db:literal, $out_struct:path, $query:expr, $($args:tt)*) => ( {
+        $crate::sqlx_macros::expand_query!(db = $db, record = $out_struct, source = $query, args = [$($args)*], checked = false)
+    });
     ($out_struct:path, $query:expr) => ( {
         $crate::sqlx_macros::expand_query!(record = $out_struct, source = $query, checked = false)
     });
@@ -626,6 +804,12 @@ macro_rules! query_as_unchecked (
 #[macro_export]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 macro_rules! query_file_as_unchecked (
+    (db = $db:literal, $out_struct:path, $path:literal) => ( {
+        $crate::sqlx_macros::expand_query!(db = $db, record = $out_struct, source_file = $path, checked = false)
+    });
+    (db = $db:literal, $out_struct:path, $path:literal, $($args:tt)*) => ( {
+        $crate::sqlx_macros::expand_query!(db = $db, record = $out_struct, source_file = $path, args = [$($args)*], checked = false)
+    });
     ($out_struct:path, $path:literal) => ( {
         $crate::sqlx_macros::expand_query!(record = $out_struct, source_file = $path, checked = false)
     });
@@ -652,6 +836,12 @@ macro_rules! query_file_as_unchecked (
 #[macro_export]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 macro_rules! query_scalar (
+    (db = $db:literal, $query:expr) => (
+        $crate::sqlx_macros::expand_query!(db = $db, scalar = _, source = $query)
+    );
+    (db = $db:literal, $query:expr, $($args:tt)*) => (
+        $crate::sqlx_macros::expand_query!(db = $db, scalar = _, source = $query, args = [$($args)*])
+    );
     ($query:expr) => (
         $crate::sqlx_macros::expand_query!(scalar = _, source = $query)
     );
@@ -664,6 +854,12 @@ macro_rules! query_scalar (
 #[macro_export]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 macro_rules! query_file_scalar (
+    (db = $db:literal, $path:literal) => (
+        $crate::sqlx_macros::expand_query!(db = $db, scalar = _, source_file = $path)
+    );
+    (db = $db:literal, $path:literal, $($args:tt)*) => (
+        $crate::sqlx_macros::expand_query!(db = $db, scalar = _, source_file = $path, args = [$($args)*])
+    );
     ($path:literal) => (
         $crate::sqlx_macros::expand_query!(scalar = _, source_file = $path)
     );
@@ -681,6 +877,12 @@ macro_rules! query_file_scalar (
 #[macro_export]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 macro_rules! query_scalar_unchecked (
+    (db = $db:literal, $query:expr) => (
+        $crate::sqlx_macros::expand_query!(db = $db, scalar = _, source = $query, checked = false)
+    );
+    (db = $db:literal, $query:expr, $($args:tt)*) => (
+        $crate::sqlx_macros::expand_query!(db = $db, scalar = _, source = $query, args = [$($args)*], checked = false)
+    );
     ($query:expr) => (
         $crate::sqlx_macros::expand_query!(scalar = _, source = $query, checked = false)
     );
@@ -698,6 +900,12 @@ macro_rules! query_scalar_unchecked (
 #[macro_export]
 #[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
 macro_rules! query_file_scalar_unchecked (
+    (db = $db:literal, $path:literal) => (
+        $crate::sqlx_macros::expand_query!(db = $db, scalar = _, source_file = $path, checked = false)
+    );
+    (db = $db:literal, $path:literal, $($args:tt)*) => (
+        $crate::sqlx_macros::expand_query!(db = $db, scalar = _, source_file = $path, args = [$($args)*], checked = false)
+    );
     ($path:literal) => (
         $crate::sqlx_macros::expand_query!(scalar = _, source_file = $path, checked = false)
     );
@@ -724,6 +932,10 @@ macro_rules! query_file_scalar_unchecked (
 /// unlike `include_str!()` which uses compiler internals to get the path of the file where it
 /// was invoked.
 ///
+/// Without a directory, `migrations-dir` under `[migrate]` in `sqlx.toml` is used if it is set,
+/// relative to that file. The table applied migrations are recorded in can be set there as
+/// `table-name`.
+///
 /// See [MigrationSource][crate::migrate::MigrationSource] for details on structure of the ./migrations directory.
 ///
 /// ## Triggering Recompilation on Migration Changes
@@ -795,6 +1007,6 @@ macro_rules! migrate {
     }};
 
     () => {{
-        $crate::sqlx_macros::migrate!("./migrations")
+        $crate::sqlx_macros::migrate!()
     }};
 }
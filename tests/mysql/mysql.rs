@@ -1,6 +1,9 @@
 use futures::TryStreamExt;
-use sqlx::mysql::{MySql, MySqlConnection, MySqlPool, MySqlPoolOptions, MySqlRow};
-use sqlx::{Column, Connection, Executor, Row, Statement, TypeInfo};
+use sqlx::mysql::{
+    MySql, MySqlConnection, MySqlPool, MySqlPoolOptions, MySqlRow, MySqlServerFlavor,
+    MySqlSessionStateChange,
+};
+use sqlx::{Column, Connection, Either, Executor, Row, Statement, TypeInfo};
 use sqlx_test::{new, setup_if_needed};
 use std::env;
 
@@ -477,3 +480,145 @@ async fn test_shrink_buffers() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[sqlx_macros::test]
+async fn it_can_read_multiple_result_sets_and_out_params() -> anyhow::Result<()> {
+    let mut conn = new::<MySql>().await?;
+
+    conn.execute(
+        r#"
+DROP PROCEDURE IF EXISTS sqlx_test_out_params;
+CREATE PROCEDURE sqlx_test_out_params(IN x INT, OUT y INT)
+BEGIN
+    SELECT 1 AS a;
+    SELECT 'two' AS b, 3 AS c;
+    SET y = x + 1;
+END;
+        "#,
+    )
+    .await?;
+
+    let mut s = conn.fetch_many(
+        sqlx::query("CALL sqlx_test_out_params(?, ?)")
+            .bind(41_i32)
+            .bind(None::<i32>),
+    );
+
+    let mut result_sets = Vec::new();
+    let mut rows = Vec::new();
+
+    while let Some(v) = s.try_next().await? {
+        match v {
+            Either::Left(result) if !result.columns().is_empty() => {
+                let columns: Vec<String> = result
+                    .columns()
+                    .iter()
+                    .map(|c| c.name().to_owned())
+                    .collect();
+
+                result_sets.push((columns, rows.len(), result.is_out_params()));
+                rows.clear();
+            }
+
+            Either::Left(_) => {}
+
+            Either::Right(row) => rows.push(row),
+        }
+    }
+
+    drop(s);
+
+    assert_eq!(
+        result_sets,
+        vec![
+            (vec!["a".to_owned()], 1, false),
+            (vec!["b".to_owned(), "c".to_owned()], 1, false),
+            (vec!["y".to_owned()], 1, true),
+        ]
+    );
+
+    let row = conn
+        .fetch_out_params(
+            sqlx::query("CALL sqlx_test_out_params(?, ?)")
+                .bind(41_i32)
+                .bind(None::<i32>),
+        )
+        .await?
+        .expect("expected OUT parameters");
+
+    assert_eq!(row.try_get::<i32, _>("y")?, 42);
+
+    conn.execute("DROP PROCEDURE sqlx_test_out_params").await?;
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_tracks_session_state_changes() -> anyhow::Result<()> {
+    let mut conn = new::<MySql>().await?;
+
+    conn.execute("SET SESSION session_track_state_change = ON")
+        .await?;
+
+    let result = conn.execute("SET @sqlx_test_session_state = 1").await?;
+
+    assert!(result
+        .session_state_changes()
+        .contains(&MySqlSessionStateChange::StateChange(true)));
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_detects_server_flavor() -> anyhow::Result<()> {
+    let mut conn = new::<MySql>().await?;
+
+    let version: String = sqlx::query_scalar("SELECT VERSION()")
+        .fetch_one(&mut conn)
+        .await?;
+
+    let expected = if version.contains("MariaDB") {
+        MySqlServerFlavor::MariaDb
+    } else {
+        MySqlServerFlavor::MySql
+    };
+
+    assert_eq!(conn.server_flavor(), expected);
+
+    Ok(())
+}
+
+#[cfg(feature = "uuid")]
+#[sqlx_macros::test]
+async fn it_binds_and_decodes_mariadb_uuid() -> anyhow::Result<()> {
+    use sqlx::types::Uuid;
+
+    let mut conn = new::<MySql>().await?;
+
+    if conn.server_flavor() != MySqlServerFlavor::MariaDb {
+        return Ok(());
+    }
+
+    // the native `UUID` type is only available since MariaDB 10.7
+    if conn
+        .execute("CREATE TEMPORARY TABLE mariadb_uuids (id UUID NOT NULL)")
+        .await
+        .is_err()
+    {
+        return Ok(());
+    }
+
+    let id = Uuid::parse_str("b731678f-636f-4135-bc6f-19440c13bd19")?;
+
+    sqlx::query("INSERT INTO mariadb_uuids (id) VALUES (?)")
+        .bind(id.hyphenated())
+        .execute(&mut conn)
+        .await?;
+
+    let row = conn.fetch_one("SELECT id FROM mariadb_uuids").await?;
+
+    assert_eq!(row.column(0).type_info().name(), "UUID");
+    assert_eq!(row.try_get::<Uuid, _>(0)?, id);
+
+    Ok(())
+}
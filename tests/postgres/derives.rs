@@ -649,3 +649,215 @@ async fn test_flatten() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(feature = "macros")]
+#[sqlx_macros::test]
+async fn test_skip() -> anyhow::Result<()> {
+    #[derive(Debug, sqlx::FromRow)]
+    struct AccountSkip {
+        id: i32,
+        #[sqlx(skip)]
+        display_name: String,
+    }
+
+    let mut conn = new::<Postgres>().await?;
+
+    let account: AccountSkip = sqlx::query_as(r#"SELECT 1 AS id, 'ignored' AS display_name"#)
+        .fetch_one(&mut conn)
+        .await?;
+
+    assert_eq!(1, account.id);
+    assert_eq!("", account.display_name);
+
+    Ok(())
+}
+
+#[cfg(all(feature = "macros", feature = "json"))]
+#[sqlx_macros::test]
+async fn test_json() -> anyhow::Result<()> {
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Settings {
+        theme: String,
+        notifications: bool,
+    }
+
+    #[derive(Debug, sqlx::FromRow)]
+    struct Account {
+        id: i32,
+        #[sqlx(json)]
+        settings: Settings,
+        #[sqlx(json, default)]
+        tags: Vec<String>,
+    }
+
+    let mut conn = new::<Postgres>().await?;
+
+    let account: Account = sqlx::query_as(
+        r#"SELECT 1 AS id, '{"theme": "dark", "notifications": true}'::jsonb AS settings"#,
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    assert_eq!(1, account.id);
+    assert_eq!(
+        Settings {
+            theme: "dark".into(),
+            notifications: true,
+        },
+        account.settings
+    );
+    assert!(account.tags.is_empty());
+
+    Ok(())
+}
+
+#[cfg(feature = "macros")]
+#[sqlx_macros::test]
+async fn test_flatten_prefix() -> anyhow::Result<()> {
+    #[derive(Debug, sqlx::FromRow)]
+    struct Name {
+        first: String,
+        last: String,
+    }
+
+    #[derive(Debug, sqlx::FromRow)]
+    struct User {
+        id: i32,
+        #[sqlx(flatten)]
+        name: Name,
+    }
+
+    #[derive(Debug, sqlx::FromRow)]
+    struct Post {
+        id: i32,
+        #[sqlx(flatten, prefix = "author_")]
+        author: User,
+        #[sqlx(flatten, prefix = "editor_")]
+        editor: User,
+    }
+
+    let mut conn = new::<Postgres>().await?;
+
+    let post: Post = sqlx::query_as(
+        r#"
+SELECT 1 AS id,
+       2 AS author_id, 'Ada' AS author_first, 'Lovelace' AS author_last,
+       3 AS editor_id, 'Alan' AS editor_first, 'Turing' AS editor_last
+        "#,
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    assert_eq!(1, post.id);
+    assert_eq!(2, post.author.id);
+    assert_eq!("Ada", post.author.name.first);
+    assert_eq!("Lovelace", post.author.name.last);
+    assert_eq!(3, post.editor.id);
+    assert_eq!("Alan", post.editor.name.first);
+    assert_eq!("Turing", post.editor.name.last);
+
+    Ok(())
+}
+
+#[cfg(feature = "macros")]
+#[sqlx_macros::test]
+async fn test_enum_tag() -> anyhow::Result<()> {
+    #[derive(Debug, PartialEq, sqlx::FromRow)]
+    struct Dimensions {
+        width: f64,
+        height: f64,
+    }
+
+    #[derive(Debug, PartialEq, sqlx::FromRow)]
+    #[sqlx(tag = "kind", rename_all = "snake_case")]
+    enum Shape {
+        Circle {
+            radius: f64,
+        },
+        Rectangle(Dimensions),
+        #[sqlx(rename = "pt")]
+        Point,
+    }
+
+    let mut conn = new::<Postgres>().await?;
+
+    let shapes: Vec<Shape> = sqlx::query_as(
+        r#"
+SELECT * FROM (VALUES
+    ('circle', 1.5::float8, NULL::float8, NULL::float8),
+    ('rectangle', NULL, 2.0, 3.0),
+    ('pt', NULL, NULL, NULL)
+) shapes(kind, radius, width, height)
+        "#,
+    )
+    .fetch_all(&mut conn)
+    .await?;
+
+    assert_eq!(
+        shapes,
+        [
+            Shape::Circle { radius: 1.5 },
+            Shape::Rectangle(Dimensions {
+                width: 2.0,
+                height: 3.0
+            }),
+            Shape::Point,
+        ]
+    );
+
+    let res = sqlx::query_as::<_, Shape>(r#"SELECT 'triangle' AS kind"#)
+        .fetch_one(&mut conn)
+        .await;
+    assert!(matches!(res, Err(sqlx::Error::ColumnDecode { .. })));
+
+    Ok(())
+}
+
+#[cfg(feature = "macros")]
+#[sqlx_macros::test]
+async fn test_enum_tag_type() -> anyhow::Result<()> {
+    #[derive(Debug, PartialEq, sqlx::Type)]
+    #[sqlx(type_name = "status", rename_all = "lowercase")]
+    enum Status {
+        New,
+        Open,
+        Closed,
+    }
+
+    #[derive(Debug, PartialEq, sqlx::FromRow)]
+    #[sqlx(tag = "status", tag_type = "Status", rename_all = "camelCase")]
+    enum Ticket {
+        New,
+        Open { assigned_to: String },
+        Closed { closed_by: String },
+    }
+
+    let mut conn = new::<Postgres>().await?;
+
+    let tickets: Vec<Ticket> = sqlx::query_as(
+        r#"
+SELECT * FROM (VALUES
+    ('new'::status, NULL::text, NULL::text),
+    ('open', 'Ada', NULL),
+    ('closed', NULL, 'Alan')
+) tickets(status, "assignedTo", "closedBy")
+        "#,
+    )
+    .fetch_all(&mut conn)
+    .await?;
+
+    assert_eq!(
+        tickets,
+        [
+            Ticket::New,
+            Ticket::Open {
+                assigned_to: "Ada".into()
+            },
+            Ticket::Closed {
+                closed_by: "Alan".into()
+            },
+        ]
+    );
+
+    Ok(())
+}
@@ -1,5 +1,8 @@
-use sqlx::{postgres::Postgres, Column, Executor, TypeInfo};
+use sqlx::postgres::{PgConnectOptions, PgConnection, Postgres};
+use sqlx::{Column, Connection, Executor, TypeInfo};
 use sqlx_test::new;
+use std::env;
+use std::str::FromStr;
 
 #[sqlx_macros::test]
 async fn it_describes_simple() -> anyhow::Result<()> {
@@ -93,3 +96,55 @@ async fn it_describes_composite() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[sqlx_macros::test]
+async fn it_describes_nullability_through_joins_and_ctes() -> anyhow::Result<()> {
+    let mut conn = new::<Postgres>().await?;
+
+    let d = conn
+        .describe(
+            "SELECT tweet.id, tweet_reply.id AS reply_id \
+             FROM tweet_reply RIGHT JOIN tweet ON tweet.id = tweet_reply.tweet_id",
+        )
+        .await?;
+
+    assert_eq!(d.nullable(0), Some(false));
+    assert_eq!(d.nullable(1), Some(true));
+
+    let d = conn
+        .describe(
+            "WITH replies AS (SELECT tweet_id, text FROM tweet_reply) \
+             SELECT replies.tweet_id, replies.text, count(*) FROM replies \
+             GROUP BY replies.tweet_id, replies.text",
+        )
+        .await?;
+
+    assert_eq!(d.nullable(0), Some(false));
+    assert_eq!(d.nullable(1), Some(false));
+    assert_eq!(d.nullable(2), Some(false));
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_describes_nullability_overrides() -> anyhow::Result<()> {
+    let options = PgConnectOptions::from_str(&env::var("DATABASE_URL")?)?.column_nullability(
+        "tweet_summary",
+        "text",
+        false,
+    );
+
+    let mut conn = PgConnection::connect_with(&options).await?;
+
+    conn.execute(
+        "CREATE TEMPORARY VIEW tweet_summary AS SELECT id, upper(text) AS text FROM tweet",
+    )
+    .await?;
+
+    let d = conn.describe("SELECT id, text FROM tweet_summary").await?;
+
+    assert_eq!(d.nullable(0), Some(false));
+    assert_eq!(d.nullable(1), Some(false));
+
+    Ok(())
+}
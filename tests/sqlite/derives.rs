@@ -1,5 +1,5 @@
-use sqlx::Sqlite;
-use sqlx_test::test_type;
+use sqlx::{Executor, Insertable, Sqlite};
+use sqlx_test::{new, test_type};
 
 #[derive(Debug, PartialEq, sqlx::Type)]
 #[repr(u32)]
@@ -12,3 +12,225 @@ test_type!(origin_enum<Origin>(Sqlite,
     "1" == Origin::Foo,
     "2" == Origin::Bar,
 ));
+
+#[derive(Debug, PartialEq, sqlx::FromRow, sqlx::Insertable)]
+#[sqlx(table = "insertable_users", rename_all = "camelCase")]
+struct User {
+    #[sqlx(primary_key)]
+    user_id: i64,
+    display_name: String,
+    #[sqlx(rename = "bio")]
+    about_me: Option<String>,
+    #[sqlx(skip)]
+    cached: bool,
+}
+
+#[sqlx_macros::test]
+async fn it_inserts_updates_and_upserts() -> anyhow::Result<()> {
+    let mut conn = new::<Sqlite>().await?;
+
+    conn.execute(
+        "CREATE TEMPORARY TABLE insertable_users (userId INTEGER PRIMARY KEY, displayName TEXT NOT NULL, bio TEXT)",
+    )
+    .await?;
+
+    assert_eq!(<User as Insertable<Sqlite>>::TABLE, "insertable_users");
+
+    let users = [
+        User {
+            user_id: 1,
+            display_name: "Alice".into(),
+            about_me: None,
+            cached: true,
+        },
+        User {
+            user_id: 2,
+            display_name: "Bob".into(),
+            about_me: Some("builder".into()),
+            cached: false,
+        },
+    ];
+
+    let mut query = User::insert_all(&users);
+    assert_eq!(
+        query.sql(),
+        r#"INSERT INTO "insertable_users" ("userId", "displayName", "bio") VALUES (?, ?, ?), (?, ?, ?)"#
+    );
+    query.build().execute(&mut conn).await?;
+
+    let mut alice = User {
+        user_id: 1,
+        display_name: "Alice Liddell".into(),
+        about_me: Some("curious".into()),
+        cached: false,
+    };
+
+    let mut query = Insertable::<Sqlite>::update(&alice);
+    assert_eq!(
+        query.sql(),
+        r#"UPDATE "insertable_users" SET "displayName" = ?, "bio" = ? WHERE "userId" = ?"#
+    );
+    query.build().execute(&mut conn).await?;
+
+    let carol = User {
+        user_id: 3,
+        display_name: "Carol".into(),
+        about_me: None,
+        cached: false,
+    };
+
+    Insertable::<Sqlite>::insert(&carol)
+        .build()
+        .execute(&mut conn)
+        .await?;
+
+    alice.display_name = "Alice".into();
+
+    let mut query = Insertable::<Sqlite>::upsert(&alice);
+    assert_eq!(
+        query.sql(),
+        r#"INSERT INTO "insertable_users" ("userId", "displayName", "bio") VALUES (?, ?, ?) ON CONFLICT ("userId") DO UPDATE SET "displayName" = EXCLUDED."displayName", "bio" = EXCLUDED."bio""#
+    );
+    query.build().execute(&mut conn).await?;
+
+    let rows: Vec<User> = sqlx::query_as("SELECT * FROM insertable_users ORDER BY userId")
+        .fetch_all(&mut conn)
+        .await?;
+
+    assert_eq!(
+        rows,
+        [
+            User {
+                user_id: 1,
+                display_name: "Alice".into(),
+                about_me: Some("curious".into()),
+                cached: false,
+            },
+            User {
+                user_id: 2,
+                display_name: "Bob".into(),
+                about_me: Some("builder".into()),
+                cached: false,
+            },
+            carol,
+        ]
+    );
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_upserts_records_made_only_of_their_key() -> anyhow::Result<()> {
+    #[derive(Debug, PartialEq, sqlx::FromRow, sqlx::Insertable)]
+    #[sqlx(table = "insertable_tags")]
+    struct Tag {
+        // a keyword, which must be quoted
+        #[sqlx(primary_key)]
+        group: String,
+    }
+
+    let mut conn = new::<Sqlite>().await?;
+
+    conn.execute(r#"CREATE TEMPORARY TABLE insertable_tags ("group" TEXT PRIMARY KEY)"#)
+        .await?;
+
+    let tag = Tag {
+        group: "admin".into(),
+    };
+
+    let mut query = Insertable::<Sqlite>::upsert(&tag);
+    assert_eq!(
+        query.sql(),
+        r#"INSERT INTO "insertable_tags" ("group") VALUES (?) ON CONFLICT ("group") DO NOTHING"#
+    );
+    query.build().execute(&mut conn).await?;
+
+    // the existing row is left unchanged
+    Insertable::<Sqlite>::upsert(&tag)
+        .build()
+        .execute(&mut conn)
+        .await?;
+
+    let tags: Vec<Tag> = sqlx::query_as("SELECT * FROM insertable_tags")
+        .fetch_all(&mut conn)
+        .await?;
+    assert_eq!(tags, [tag]);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_flattens_tuple_structs_with_prefix() -> anyhow::Result<()> {
+    #[derive(Debug, PartialEq, sqlx::FromRow)]
+    struct Point(i64, i64);
+
+    #[derive(Debug, PartialEq, sqlx::FromRow)]
+    struct Line {
+        id: i64,
+        #[sqlx(flatten, prefix = "from_")]
+        from: Point,
+        #[sqlx(flatten, prefix = "to_")]
+        to: Point,
+    }
+
+    let mut conn = new::<Sqlite>().await?;
+
+    let line: Line =
+        sqlx::query_as("SELECT 1 AS id, 2 AS from_x, 3 AS from_y, 4 AS to_x, 5 AS to_y")
+            .fetch_one(&mut conn)
+            .await?;
+
+    assert_eq!(
+        line,
+        Line {
+            id: 1,
+            from: Point(2, 3),
+            to: Point(4, 5),
+        }
+    );
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_reads_enums_with_an_integer_tag() -> anyhow::Result<()> {
+    #[derive(Debug, PartialEq, sqlx::Type)]
+    #[repr(i32)]
+    enum EventKind {
+        Login = 1,
+        Rename = 2,
+    }
+
+    #[derive(Debug, PartialEq, sqlx::FromRow)]
+    #[sqlx(tag = "kind", tag_type = "EventKind", rename_all = "camelCase")]
+    enum Event {
+        Login,
+        Rename { old_name: String, new_name: String },
+    }
+
+    let mut conn = new::<Sqlite>().await?;
+
+    let events: Vec<Event> = sqlx::query_as(
+        "SELECT 1 AS kind, NULL AS oldName, NULL AS newName UNION ALL SELECT 2, 'a', 'b'",
+    )
+    .fetch_all(&mut conn)
+    .await?;
+
+    assert_eq!(
+        events,
+        [
+            Event::Login,
+            Event::Rename {
+                old_name: "a".into(),
+                new_name: "b".into(),
+            },
+        ]
+    );
+
+    let res = sqlx::query_as::<_, Event>("SELECT 3 AS kind")
+        .fetch_one(&mut conn)
+        .await;
+    assert!(matches!(res, Err(sqlx::Error::ColumnDecode { .. })));
+
+    Ok(())
+}
@@ -88,6 +88,100 @@ async fn macro_select_bind() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[sqlx_macros::test]
+async fn macro_select_named_params() -> anyhow::Result<()> {
+    let mut conn = new::<Sqlite>().await?;
+
+    let account = sqlx::query!(
+        "select id, name, :id + 1 as next_id from accounts where id = :id and name <> ':name'",
+        id = 1i32,
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    assert_eq!(1, account.id);
+    assert_eq!("Herp Derpinson", account.name);
+    assert_eq!(Some(2), account.next_id);
+
+    let account = sqlx::query_as!(
+        RawAccount,
+        "select id, name, is_active from accounts where name = :name and id = :id",
+        id = 1i64,
+        name = "Herp Derpinson",
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    assert_eq!(1, account.id);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn macro_select_from_named_database() -> anyhow::Result<()> {
+    let mut conn = new::<Sqlite>().await?;
+
+    let account = sqlx::query!(
+        db = "sqlite-tests",
+        "select id, name from accounts where id = ?",
+        1i32
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    assert_eq!(1, account.id);
+    assert_eq!("Herp Derpinson", account.name);
+
+    let name = sqlx::query_scalar!(
+        db = "sqlite-tests",
+        "select name from accounts where id = 1"
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    assert_eq!("Herp Derpinson", name);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn macro_select_optional_clauses() -> anyhow::Result<()> {
+    let mut conn = new::<Sqlite>().await?;
+
+    let cases = [
+        (None, None, 1),
+        (Some("Herp Derpinson"), None, 1),
+        (Some("Derp Herpinson"), None, 0),
+        (None, Some(2i64), 0),
+        (Some("Herp Derpinson"), Some(1i64), 1),
+    ];
+
+    for (name, id, expected) in cases {
+        let accounts = sqlx::query!(
+            "select id, name from accounts where is_active = ?",
+            true,
+            "and name = ?" if name,
+            "and id = ?" if id,
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        assert_eq!(accounts.len(), expected);
+
+        let count = sqlx::query_scalar!(
+            "select count(*) from accounts where true",
+            "and name = ?" if name,
+            "and id = ?" if id,
+        )
+        .fetch_one(&mut conn)
+        .await?;
+
+        assert_eq!(count as usize, expected);
+    }
+
+    Ok(())
+}
+
 #[derive(Debug)]
 struct RawAccount {
     id: i64,
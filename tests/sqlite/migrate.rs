@@ -66,6 +66,51 @@ async fn reversible(mut conn: PoolConnection<Sqlite>) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[sqlx::test(migrations = false)]
+async fn custom_table_name(mut conn: PoolConnection<Sqlite>) -> anyhow::Result<()> {
+    clean_up(&mut conn).await?;
+
+    let mut migrator = Migrator::new(Path::new("tests/sqlite/migrations_simple")).await?;
+    migrator.set_table_name("schema_versions");
+
+    migrator.run(&mut conn).await?;
+
+    // the migrations are recorded in the given table only
+    let applied: i64 = conn
+        .fetch_one("SELECT COUNT(*) FROM schema_versions")
+        .await?
+        .get(0);
+    assert_eq!(applied, 2);
+    assert!(conn
+        .fetch_one("SELECT * FROM _sqlx_migrations")
+        .await
+        .is_err());
+
+    // running it a 2nd time should still work
+    migrator.run(&mut conn).await?;
+
+    Ok(())
+}
+
+#[sqlx::test(migrations = false)]
+async fn custom_table_name_is_quoted(mut conn: PoolConnection<Sqlite>) -> anyhow::Result<()> {
+    clean_up(&mut conn).await?;
+
+    // qualified with the schema, and not a plain identifier
+    let mut migrator = Migrator::new(Path::new("tests/sqlite/migrations_simple")).await?;
+    migrator.set_table_name("main.schema versions");
+
+    migrator.run(&mut conn).await?;
+
+    let applied: i64 = conn
+        .fetch_one(r#"SELECT COUNT(*) FROM "schema versions""#)
+        .await?
+        .get(0);
+    assert_eq!(applied, 2);
+
+    Ok(())
+}
+
 /// Ensure that we have a clean initial state.
 async fn clean_up(conn: &mut SqliteConnection) -> anyhow::Result<()> {
     conn.execute("DROP TABLE migrations_simple_test").await.ok();
@@ -73,6 +118,8 @@ async fn clean_up(conn: &mut SqliteConnection) -> anyhow::Result<()> {
         .await
         .ok();
     conn.execute("DROP TABLE _sqlx_migrations").await.ok();
+    conn.execute("DROP TABLE schema_versions").await.ok();
+    conn.execute(r#"DROP TABLE "schema versions""#).await.ok();
 
     Ok(())
 }
@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
-use sqlx::sqlite::SqliteQueryResult;
+use futures::TryStreamExt;
+use sqlx::sqlite::{SqliteBackupOptions, SqliteQueryResult};
 use sqlx::{query, Connection, SqliteConnection};
 use sqlx::{sqlite::SqliteConnectOptions, ConnectOptions};
 use tempdir::TempDir;
@@ -109,18 +110,13 @@ async fn it_fails_if_password_is_incorrect() -> anyhow::Result<()> {
 
     fill_db(&mut conn).await?;
 
-    // Connection with invalid key should not allow to execute queries
-    let mut conn = SqliteConnectOptions::from_str(&url)?
+    // Connection with invalid key should fail to open
+    let res = SqliteConnectOptions::from_str(&url)?
         .pragma("key", "BADBADBAD")
         .connect()
-        .await?;
+        .await;
 
-    assert!(conn
-        .transaction(|tx| {
-            Box::pin(async move { query("SELECT * FROM Company;").fetch_all(&mut **tx).await })
-        })
-        .await
-        .is_err());
+    assert!(matches!(res, Err(sqlx::Error::Configuration(_))));
 
     Ok(())
 }
@@ -201,3 +197,84 @@ async fn it_allows_to_rekey_the_db() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[sqlx_macros::test]
+async fn it_opens_with_key_and_cipher_settings() -> anyhow::Result<()> {
+    let (url, _dir) = new_db_url().await?;
+
+    let options = SqliteConnectOptions::from_str(&url)?
+        .cipher_page_size(8192)
+        .kdf_iter(64000)
+        .key("it's a secret");
+
+    let mut conn = options.clone().create_if_missing(true).connect().await?;
+    fill_db(&mut conn).await?;
+    conn.close().await?;
+
+    // the cipher settings must match
+    assert!(SqliteConnectOptions::from_str(&url)?
+        .key("it's a secret")
+        .connect()
+        .await
+        .is_err());
+
+    let mut conn = options.clone().connect().await?;
+    conn.rekey("new secret").await?;
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Company")
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(count, 2);
+    conn.close().await?;
+
+    let res = options.connect().await;
+    assert!(matches!(res, Err(sqlx::Error::Configuration(_))));
+
+    let mut conn = SqliteConnectOptions::from_str(&url)?
+        .cipher_page_size(8192)
+        .kdf_iter(64000)
+        .key("new secret")
+        .connect()
+        .await?;
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Company")
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(count, 2);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_backs_up_into_an_encrypted_db() -> anyhow::Result<()> {
+    let (url, _dir) = new_db_url().await?;
+    let (dest_url, _dest_dir) = new_db_url().await?;
+
+    let mut conn = SqliteConnectOptions::from_str(&url)?
+        .key("it's a secret")
+        .create_if_missing(true)
+        .connect()
+        .await?;
+    fill_db(&mut conn).await?;
+
+    let dest = SqliteConnectOptions::from_str(&dest_url)?
+        .key("it's a secret")
+        .create_if_missing(true);
+
+    conn.backup_to(&dest, SqliteBackupOptions::new())
+        .try_for_each(|_| async { Ok(()) })
+        .await?;
+
+    // the backup is encrypted, so it can't be read without the key
+    let mut conn = SqliteConnection::connect(&dest_url).await?;
+    assert!(sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM Company")
+        .fetch_one(&mut conn)
+        .await
+        .is_err());
+
+    let mut conn = dest.connect().await?;
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Company")
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(count, 2);
+
+    Ok(())
+}
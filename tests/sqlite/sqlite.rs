@@ -438,6 +438,789 @@ CREATE TEMPORARY TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL COLLATE
     Ok(())
 }
 
+#[sqlx_macros::test]
+async fn it_supports_user_defined_functions() -> anyhow::Result<()> {
+    let mut conn = new::<Sqlite>().await?;
+
+    conn.create_function("add_one", |(x,): (i64,)| x + 1)
+        .await?;
+    conn.create_function("fail", |(): ()| -> i64 { panic!("boom") })
+        .await?;
+    conn.create_function("concat_all", |values: Vec<sqlx::sqlite::SqliteValue>| {
+        values.len() as i64
+    })
+    .await?;
+
+    let (x, n): (i64, i64) = sqlx::query_as("SELECT add_one(41), concat_all(1, 'a', NULL)")
+        .fetch_one(&mut conn)
+        .await?;
+
+    assert_eq!(x, 42);
+    assert_eq!(n, 3);
+
+    let err = conn.execute("SELECT fail()").await.unwrap_err();
+    assert!(err.to_string().contains("boom"), "{}", err);
+
+    conn.create_aggregate(
+        "product",
+        || 1i64,
+        |product, (x,): (i64,)| *product *= x,
+        |product| product,
+    )
+    .await?;
+
+    conn.create_window_function(
+        "sum_text",
+        String::new,
+        |s, (x,): (String,)| s.push_str(&x),
+        |s, (x,): (String,)| {
+            s.drain(..x.len());
+        },
+        |s| s.clone(),
+        |s| s,
+    )
+    .await?;
+
+    let product: i64 = sqlx::query_scalar(
+        "SELECT product(value) FROM (SELECT 2 AS value UNION ALL SELECT 3 UNION ALL SELECT 7)",
+    )
+    .fetch_one(&mut conn)
+    .await?;
+
+    assert_eq!(product, 42);
+
+    let empty: i64 = sqlx::query_scalar("SELECT product(1) WHERE 0")
+        .fetch_one(&mut conn)
+        .await?;
+
+    assert_eq!(empty, 1);
+
+    let windows: Vec<String> = sqlx::query_scalar(
+        r#"
+SELECT sum_text(value) OVER (ORDER BY value ROWS BETWEEN 1 PRECEDING AND CURRENT ROW)
+FROM (SELECT 'a' AS value UNION ALL SELECT 'b' UNION ALL SELECT 'c')
+        "#,
+    )
+    .fetch_all(&mut conn)
+    .await?;
+
+    assert_eq!(windows, ["a", "ab", "bc"]);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_registers_user_defined_functions_on_pooled_connections() -> anyhow::Result<()> {
+    use std::str::FromStr;
+
+    let options = SqliteConnectOptions::from_str(&dotenvy::var("DATABASE_URL")?)?
+        .create_function("shout", |(s,): (Option<String>,)| {
+            s.map(|s| s.to_uppercase())
+        })
+        .create_aggregate(
+            "str_count",
+            || 0i64,
+            |count, (_,): (String,)| *count += 1,
+            |count| count,
+        );
+
+    let pool: SqlitePool = SqlitePoolOptions::new()
+        .min_connections(2)
+        .max_connections(2)
+        .connect_with(options)
+        .await?;
+
+    for _ in 0..2 {
+        let mut conn = pool.acquire().await?;
+
+        let (shouted, null, count): (String, Option<String>, i64) =
+            sqlx::query_as("SELECT shout('hello'), shout(NULL), str_count('a')")
+                .fetch_one(&mut *conn)
+                .await?;
+
+        assert_eq!(shouted, "HELLO");
+        assert_eq!(null, None);
+        assert_eq!(count, 1);
+
+        conn.detach();
+    }
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_reports_changes_through_hooks() -> anyhow::Result<()> {
+    use futures::StreamExt;
+    use sqlx::sqlite::{SqliteOperation, SqliteUpdateEvent};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let mut conn = new::<Sqlite>().await?;
+
+    let veto = Arc::new(AtomicBool::new(false));
+
+    let mut updates = conn.update_hook().await?;
+    let mut commits = conn
+        .commit_hook({
+            let veto = veto.clone();
+            move || !veto.load(Ordering::SeqCst)
+        })
+        .await?;
+    let mut rollbacks = conn.rollback_hook().await?;
+
+    conn.execute("CREATE TEMPORARY TABLE hooked (id INTEGER PRIMARY KEY, name TEXT)")
+        .await?;
+
+    let mut tx = conn.begin().await?;
+    tx.execute("INSERT INTO hooked (id, name) VALUES (1, 'a')")
+        .await?;
+    tx.execute("UPDATE hooked SET name = 'b' WHERE id = 1")
+        .await?;
+    tx.commit().await?;
+
+    assert_eq!(
+        updates.next().await,
+        Some(SqliteUpdateEvent {
+            operation: SqliteOperation::Insert,
+            database: "temp".into(),
+            table: "hooked".into(),
+            rowid: 1,
+        })
+    );
+    assert_eq!(
+        updates.next().await.map(|e| e.operation),
+        Some(SqliteOperation::Update)
+    );
+    // the `CREATE TABLE` and the transaction
+    assert_eq!(commits.next().await, Some(()));
+    assert_eq!(commits.next().await, Some(()));
+
+    veto.store(true, Ordering::SeqCst);
+
+    let mut tx = conn.begin().await?;
+    tx.execute("DELETE FROM hooked WHERE id = 1").await?;
+    assert!(tx.commit().await.is_err());
+
+    assert_eq!(
+        updates.next().await.map(|e| e.operation),
+        Some(SqliteOperation::Delete)
+    );
+    assert_eq!(rollbacks.next().await, Some(()));
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM hooked")
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(count, 1);
+
+    conn.clear_hooks().await?;
+    conn.ping().await?;
+
+    assert_eq!(updates.next().await, None);
+    assert_eq!(commits.next().await, None);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_backs_up_and_restores() -> anyhow::Result<()> {
+    use sqlx::sqlite::SqliteBackupOptions;
+    use std::time::Duration;
+
+    let dir = tempdir::TempDir::new("sqlite_backup_test")?;
+    let dest = SqliteConnectOptions::new()
+        .filename(dir.path().join("backup.db"))
+        .create_if_missing(true);
+
+    let mut conn = SqliteConnection::connect(":memory:").await?;
+
+    conn.execute(
+        r#"
+CREATE TABLE backed_up (id INTEGER PRIMARY KEY, data BLOB NOT NULL);
+WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 200)
+INSERT INTO backed_up (id, data) SELECT i, randomblob(1024) FROM n;
+        "#,
+    )
+    .await?;
+
+    let progress: Vec<_> = conn
+        .backup_to(
+            &dest,
+            SqliteBackupOptions::new()
+                .pages_per_step(50)
+                .step_sleep(Duration::ZERO),
+        )
+        .try_collect()
+        .await?;
+
+    assert!(progress.len() > 1);
+    assert!(progress.last().unwrap().is_done());
+    assert!(progress.iter().all(|p| p.page_count() > 0));
+
+    let mut restored = SqliteConnection::connect(":memory:").await?;
+
+    restored
+        .restore_from(&dest, SqliteBackupOptions::new().all_pages())
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM backed_up")
+        .fetch_one(&mut restored)
+        .await?;
+
+    assert_eq!(count, 200);
+
+    let missing = SqliteConnectOptions::new().filename(dir.path().join("missing.db"));
+    let res = restored
+        .restore_from(&missing, SqliteBackupOptions::new())
+        .try_collect::<Vec<_>>()
+        .await;
+
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_gives_up_or_abandons_backups() -> anyhow::Result<()> {
+    use sqlx::sqlite::SqliteBackupOptions;
+    use std::time::Duration;
+
+    let dir = tempdir::TempDir::new("sqlite_backup_test")?;
+    let dest = SqliteConnectOptions::new()
+        .filename(dir.path().join("backup.db"))
+        .create_if_missing(true);
+
+    let mut conn = SqliteConnection::connect(":memory:").await?;
+
+    conn.execute(
+        r#"
+CREATE TABLE backed_up (id INTEGER PRIMARY KEY, data BLOB NOT NULL);
+WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 200)
+INSERT INTO backed_up (id, data) SELECT i, randomblob(1024) FROM n;
+        "#,
+    )
+    .await?;
+
+    let mut locker = SqliteConnection::connect_with(&dest).await?;
+    locker.execute("BEGIN EXCLUSIVE").await?;
+
+    // the destination stays locked, so every step fails with `SQLITE_BUSY`
+    let res = conn
+        .backup_to(
+            &dest.clone().busy_timeout(Duration::ZERO),
+            SqliteBackupOptions::new()
+                .busy_retries(3)
+                .step_sleep(Duration::from_millis(1)),
+        )
+        .try_collect::<Vec<_>>()
+        .await;
+
+    assert!(res.is_err());
+
+    locker.execute("ROLLBACK").await?;
+
+    // abandon a backup after its first step
+    let mut backup = conn.backup_to(
+        &dest,
+        SqliteBackupOptions::new()
+            .pages_per_step(1)
+            .step_sleep(Duration::ZERO),
+    );
+    let progress = backup.try_next().await?.unwrap();
+    assert!(!progress.is_done());
+    drop(backup);
+
+    // the backup is released before the next command, which unlocks the destination
+    conn.ping().await?;
+    locker.execute("CREATE TABLE unlocked (id INTEGER)").await?;
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_reads_and_writes_blobs_incrementally() -> anyhow::Result<()> {
+    use futures::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom};
+
+    let mut conn = SqliteConnection::connect(":memory:").await?;
+
+    conn.execute(
+        r#"
+CREATE TABLE attachments (id INTEGER PRIMARY KEY, data BLOB NOT NULL);
+INSERT INTO attachments (id, data) VALUES (1, zeroblob(1024));
+        "#,
+    )
+    .await?;
+
+    let mut blob = conn.open_blob("attachments", "data", 1, true).await?;
+    assert_eq!(blob.len(), 1024);
+
+    blob.write_all(b"hello").await?;
+    blob.seek(SeekFrom::End(-5)).await?;
+    blob.write_all(b"world").await?;
+
+    // the size of a blob can't be changed
+    assert!(blob.write_all(b"!").await.is_err());
+
+    blob.seek(SeekFrom::Start(0)).await?;
+    let mut data = Vec::new();
+    blob.read_to_end(&mut data).await?;
+    drop(blob);
+
+    assert_eq!(data.len(), 1024);
+    assert_eq!(&data[..5], b"hello");
+    assert_eq!(&data[1019..], b"world");
+
+    let stored: Vec<u8> = sqlx::query_scalar("SELECT data FROM attachments WHERE id = 1")
+        .fetch_one(&mut conn)
+        .await?;
+
+    assert_eq!(stored, data);
+
+    let mut blob = conn.open_blob("attachments", "data", 1, false).await?;
+    assert!(blob.write_all(b"read only").await.is_err());
+    drop(blob);
+
+    assert!(conn
+        .open_blob("attachments", "data", 2, false)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_serializes_and_deserializes() -> anyhow::Result<()> {
+    use std::str::FromStr;
+
+    let mut seeded = SqliteConnection::connect(":memory:").await?;
+
+    assert!(seeded.serialize(None).await?.is_empty());
+
+    seeded
+        .execute(
+            r#"
+CREATE TABLE seeded (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+INSERT INTO seeded (name) VALUES ('a'), ('b'), ('c');
+            "#,
+        )
+        .await?;
+
+    let snapshot = seeded.serialize(None).await?;
+    assert!(snapshot.starts_with(b"SQLite format 3\0"));
+
+    assert!(seeded.serialize(Some("missing")).await.is_err());
+
+    let options = SqliteConnectOptions::from_str(":memory:")?.deserialize_from(snapshot);
+
+    let mut a = SqliteConnection::connect_with(&options).await?;
+    let mut b = SqliteConnection::connect_with(&options).await?;
+
+    a.execute("DELETE FROM seeded").await?;
+
+    let count_a: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM seeded")
+        .fetch_one(&mut a)
+        .await?;
+    let count_b: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM seeded")
+        .fetch_one(&mut b)
+        .await?;
+
+    assert_eq!(count_a, 0);
+    assert_eq!(count_b, 3);
+
+    // the deserialized database can grow
+    b.execute("INSERT INTO seeded (name) SELECT name || 'x' FROM seeded")
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(feature = "sqlite-session")]
+#[sqlx_macros::test]
+async fn it_records_and_applies_changesets() -> anyhow::Result<()> {
+    use sqlx::sqlite::{SqliteConflictAction, SqliteConflictKind, SqliteOperation};
+    use std::sync::{Arc, Mutex};
+
+    const SCHEMA: &str = r#"
+CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+INSERT INTO items (id, name) VALUES (1, 'a'), (2, 'b');
+    "#;
+
+    let mut primary = SqliteConnection::connect(":memory:").await?;
+    let mut replica = SqliteConnection::connect(":memory:").await?;
+
+    primary.execute(SCHEMA).await?;
+    replica.execute(SCHEMA).await?;
+
+    let mut session = primary.session(None).await?;
+    session.attach(Some("items")).await?;
+    assert!(session.is_empty().await?);
+
+    (&mut *session)
+        .execute("INSERT INTO items (id, name) VALUES (3, 'c'); DELETE FROM items WHERE id = 1")
+        .await?;
+    assert!(!session.is_empty().await?);
+
+    let changeset = session.changeset().await?;
+    let patchset = session.patchset().await?;
+    assert!(patchset.len() < changeset.len());
+    drop(session);
+
+    // the replica already has a row with id 3, which conflicts with the insert
+    replica
+        .execute("INSERT INTO items (id, name) VALUES (3, 'x')")
+        .await?;
+
+    let conflicts = Arc::new(Mutex::new(Vec::new()));
+    let conflicts_ = Arc::clone(&conflicts);
+
+    replica
+        .apply_changeset(&changeset, move |conflict| {
+            conflicts_.lock().unwrap().push((
+                conflict.kind(),
+                conflict.table().to_owned(),
+                conflict.operation(),
+            ));
+
+            SqliteConflictAction::Replace
+        })
+        .await?;
+
+    assert_eq!(
+        *conflicts.lock().unwrap(),
+        [(
+            SqliteConflictKind::Conflict,
+            "items".to_owned(),
+            Some(SqliteOperation::Insert)
+        )]
+    );
+
+    let names: Vec<String> = sqlx::query_scalar("SELECT name FROM items ORDER BY id")
+        .fetch_all(&mut replica)
+        .await?;
+    assert_eq!(names, ["b", "c"]);
+
+    // aborting rolls back the whole changeset
+    let res = replica
+        .apply_changeset(&changeset, |_| SqliteConflictAction::Abort)
+        .await;
+    assert!(res.is_err());
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM items")
+        .fetch_one(&mut replica)
+        .await?;
+    assert_eq!(count, 2);
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_routes_queries_in_rw_pool() -> anyhow::Result<()> {
+    use sqlx::sqlite::{SqliteJournalMode, SqliteRwPool, SqliteRwPoolOptions};
+
+    let dir = tempdir::TempDir::new("sqlite_rw_pool_test")?;
+    let options = SqliteConnectOptions::new()
+        .filename(dir.path().join("rw.db"))
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal);
+
+    // a private in-memory database can't be shared
+    assert!(SqliteRwPool::connect_with(SqliteConnectOptions::new())
+        .await
+        .is_err());
+
+    let pool = SqliteRwPoolOptions::new()
+        .max_readers(2)
+        .connect_with(options)
+        .await?;
+
+    // the second statement can't be prepared before the first has run
+    pool.execute(
+        r#"
+CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+INSERT INTO items (name) VALUES ('a');
+        "#,
+    )
+    .await?;
+
+    // readers can't write
+    assert!(pool
+        .reader()
+        .execute("INSERT INTO items (name) VALUES ('b')")
+        .await
+        .is_err());
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("INSERT INTO items (name) VALUES ('b')")
+        .execute(&mut *tx)
+        .await?;
+
+    // reads don't wait for the writer, and don't see its uncommitted changes
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM items")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(count, 1);
+
+    tx.commit().await?;
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM items")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(count, 2);
+
+    let deleted = sqlx::query("DELETE FROM items WHERE name = ?")
+        .bind("a")
+        .execute(&pool)
+        .await?;
+    assert_eq!(deleted.rows_affected(), 1);
+
+    // writes don't wait for a reader, even when all of them are in use
+    let busy = (
+        pool.reader().acquire().await?,
+        pool.reader().acquire().await?,
+    );
+    sqlx::query("INSERT INTO items (name) VALUES ('c')")
+        .execute(&pool)
+        .await?;
+    drop(busy);
+
+    pool.close().await;
+    assert!(pool.is_closed());
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_supports_virtual_tables() -> anyhow::Result<()> {
+    use sqlx::error::BoxDynError;
+    use sqlx::sqlite::{
+        SqliteColumnResult, SqliteConstraintOp, SqliteIndexInfo, SqliteValue, SqliteVirtualTable,
+        SqliteVirtualTableCursor,
+    };
+    use sqlx::Value;
+    use std::sync::{Arc, Mutex};
+
+    type Buffer = Arc<Mutex<Vec<(i64, String)>>>;
+
+    struct Samples {
+        buffer: Buffer,
+    }
+
+    struct SamplesCursor {
+        buffer: Buffer,
+        rows: Vec<(i64, String)>,
+        pos: usize,
+    }
+
+    impl SqliteVirtualTable for Samples {
+        type Cursor = SamplesCursor;
+
+        fn schema(&self) -> String {
+            "CREATE TABLE x(name TEXT)".into()
+        }
+
+        fn best_index(&self, info: &mut SqliteIndexInfo<'_>) -> Result<(), BoxDynError> {
+            let rowid_eq = info
+                .constraints()
+                .position(|c| c.usable && c.column == -1 && c.op == SqliteConstraintOp::Eq);
+
+            if let Some(constraint) = rowid_eq {
+                info.use_constraint(constraint, 0, true);
+                info.set_index_num(1);
+                info.set_estimated_cost(1.0);
+            }
+
+            Ok(())
+        }
+
+        fn open(&self) -> Result<SamplesCursor, BoxDynError> {
+            Ok(SamplesCursor {
+                buffer: self.buffer.clone(),
+                rows: Vec::new(),
+                pos: 0,
+            })
+        }
+
+        fn insert(
+            &mut self,
+            rowid: Option<i64>,
+            values: Vec<SqliteValue>,
+        ) -> Result<i64, BoxDynError> {
+            let mut buffer = self.buffer.lock().unwrap();
+            let rowid = rowid.unwrap_or(buffer.len() as i64 + 1);
+            buffer.push((rowid, values[0].try_decode()?));
+            Ok(rowid)
+        }
+    }
+
+    impl SqliteVirtualTableCursor for SamplesCursor {
+        fn filter(&mut self, index_num: i32, args: Vec<SqliteValue>) -> Result<(), BoxDynError> {
+            let buffer = self.buffer.lock().unwrap();
+            self.rows = match index_num {
+                1 => {
+                    let rowid: i64 = args[0].try_decode()?;
+                    buffer
+                        .iter()
+                        .filter(|(id, _)| *id == rowid)
+                        .cloned()
+                        .collect()
+                }
+                _ => buffer.clone(),
+            };
+            self.pos = 0;
+            Ok(())
+        }
+
+        fn next(&mut self) -> Result<(), BoxDynError> {
+            self.pos += 1;
+            Ok(())
+        }
+
+        fn eof(&self) -> bool {
+            self.pos >= self.rows.len()
+        }
+
+        fn column(
+            &self,
+            _index: usize,
+            result: &mut SqliteColumnResult<'_>,
+        ) -> Result<(), BoxDynError> {
+            result.set(self.rows[self.pos].1.clone());
+            Ok(())
+        }
+
+        fn rowid(&self) -> Result<i64, BoxDynError> {
+            Ok(self.rows[self.pos].0)
+        }
+    }
+
+    let buffer: Buffer = Arc::new(Mutex::new(vec![(1, "a".into()), (2, "b".into())]));
+
+    let mut conn = SqliteConnectOptions::new()
+        .create_module("samples", {
+            let buffer = buffer.clone();
+            move |_args| {
+                Ok(Samples {
+                    buffer: buffer.clone(),
+                })
+            }
+        })
+        .connect()
+        .await?;
+
+    let names: Vec<String> = sqlx::query_scalar("SELECT name FROM samples ORDER BY rowid")
+        .fetch_all(&mut conn)
+        .await?;
+    assert_eq!(names, ["a", "b"]);
+
+    let name: String = sqlx::query_scalar("SELECT name FROM samples WHERE rowid = ?")
+        .bind(2)
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(name, "b");
+
+    conn.execute("CREATE VIRTUAL TABLE temp.more USING samples()")
+        .await?;
+    sqlx::query("INSERT INTO more (name) VALUES (?)")
+        .bind("c")
+        .execute(&mut conn)
+        .await?;
+    assert_eq!(buffer.lock().unwrap().len(), 3);
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM samples")
+        .fetch_one(&mut conn)
+        .await?;
+    assert_eq!(count, 3);
+
+    // the eponymous table is read-only
+    assert!(conn
+        .execute("DELETE FROM samples WHERE rowid = 1")
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[sqlx_macros::test]
+async fn it_interrupts_queries() -> anyhow::Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const ENDLESS: &str =
+        "WITH RECURSIVE r(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM r) SELECT COUNT(*) FROM r";
+
+    let mut conn = SqliteConnectOptions::new()
+        .statement_timeout(Duration::from_millis(100))
+        .connect()
+        .await?;
+
+    let res = conn.fetch_one(ENDLESS).await;
+    assert!(matches!(res, Err(sqlx::Error::Interrupted)));
+
+    // the timeout applies to describing a query too, which takes a while for a huge one
+    let mut short_timeout_conn = SqliteConnectOptions::new()
+        .statement_timeout(Duration::from_millis(1))
+        .connect()
+        .await?;
+
+    let huge = format!("SELECT * FROM (VALUES {})", vec!["(1)"; 100_000].join(", "));
+    let res = short_timeout_conn.describe(&huge).await;
+    assert!(matches!(res, Err(sqlx::Error::Interrupted)));
+
+    // time spent waiting for a slow consumer doesn't count toward the timeout
+    let mut rows = sqlx::query_scalar::<_, i64>(
+        "WITH RECURSIVE r(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM r WHERE n < 1000) SELECT n FROM r",
+    )
+    .fetch(&mut conn);
+
+    let mut count = 0;
+    while let Some(n) = rows.try_next().await? {
+        count += 1;
+        assert_eq!(n, count);
+
+        if count % 250 == 0 {
+            sqlx_core::rt::sleep(Duration::from_millis(100)).await;
+        }
+    }
+    assert_eq!(count, 1000);
+    drop(rows);
+
+    // dropping the future interrupts the query, so the connection can be used again
+    let mut conn = SqliteConnection::connect(":memory:").await?;
+
+    let res = sqlx_core::rt::timeout(Duration::from_millis(100), conn.fetch_one(ENDLESS)).await;
+    assert!(res.is_err());
+
+    let one: i64 = sqlx_core::rt::timeout(
+        Duration::from_secs(5),
+        sqlx::query_scalar("SELECT 1").fetch_one(&mut conn),
+    )
+    .await??;
+    assert_eq!(one, 1);
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    conn.progress_handler(100, {
+        let calls = calls.clone();
+        move || calls.fetch_add(1, Ordering::SeqCst) < 10
+    })
+    .await?;
+
+    let res = conn.fetch_one(ENDLESS).await;
+    assert!(matches!(res, Err(sqlx::Error::Interrupted)));
+    assert_eq!(calls.load(Ordering::SeqCst), 11);
+
+    conn.clear_progress_handler().await?;
+
+    let count: i64 = sqlx::query_scalar(
+        "WITH RECURSIVE r(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM r WHERE n < 10000) SELECT COUNT(*) FROM r",
+    )
+    .fetch_one(&mut conn)
+    .await?;
+    assert_eq!(count, 10000);
+
+    Ok(())
+}
+
 #[sqlx_macros::test]
 async fn it_caches_statements() -> anyhow::Result<()> {
     let mut conn = new::<Sqlite>().await?;
@@ -0,0 +1,36 @@
+#[derive(sqlx::Type)]
+#[sqlx(transparent)]
+struct Id(#[sqlx(skip)] i64);
+
+#[derive(sqlx::Encode)]
+enum Mood {
+    #[sqlx(json)]
+    Happy,
+    Sad,
+}
+
+#[derive(sqlx::Decode)]
+struct Point {
+    #[sqlx(primary_key)]
+    x: i32,
+    y: i32,
+}
+
+#[derive(sqlx::FromRow)]
+struct User {
+    #[sqlx(primary_key)]
+    id: i64,
+    name: String,
+}
+
+#[derive(sqlx::FromRow)]
+#[sqlx(tag = "kind")]
+enum Shape {
+    #[sqlx(skip)]
+    Circle { radius: f64 },
+    Square { side: f64 },
+}
+
+fn main() {
+    compile_error!("trybuild test needs to fail for stderr checking");
+}